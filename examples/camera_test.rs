@@ -8,17 +8,16 @@ extern crate rand;
 use ggez::conf;
 use ggez::event;
 use ggez::graphics;
-use ggez::mint;
 use ggez::{Context, GameResult};
-use nalgebra_glm::Vec2;
 
 extern crate ggez_goodies;
 use ggez_goodies::camera::*;
+use ggez_goodies::math::{point2, vector2, Point2};
 
 struct MainState {
     camera: Camera,
     image: graphics::Image,
-    image_location: Vec2,
+    image_location: Point2,
 }
 
 impl MainState {
@@ -27,15 +26,15 @@ impl MainState {
 
         println!("Camera test instructions; WASD move the object, arrow keys move the camera.");
         println!(
-            "The red dots are drawn on every integer point in the camera's coordinate \
-             system."
+            "The grid lines are drawn on every integer coordinate in the camera's \
+             coordinate system."
         );
         let image = graphics::Image::solid(ctx, 5, graphics::Color::new(1.0, 0.0, 0.0, 1.0))?;
 
         let state = MainState {
-            camera: camera,
-            image: image,
-            image_location: Vec2::new(0.0, 0.0),
+            camera,
+            image,
+            image_location: point2(0.0, 0.0),
         };
         Ok(state)
     }
@@ -55,22 +54,7 @@ impl event::EventHandler for MainState {
     fn draw(&mut self, ctx: &mut Context) -> GameResult<()> {
         graphics::clear(ctx, [0.0, 0.0, 0.0, 1.0].into());
 
-        let half_width = (CAMERA_WIDTH / 2.0) as i32;
-        let half_height = (CAMERA_HEIGHT / 2.0) as i32;
-        for y in -half_height..half_height {
-            for x in -half_width..half_width {
-                let frompt = Vec2::new(x as f32, y as f32);
-                let (px, py) = self.camera.world_to_screen_coords(frompt);
-                let rectangle = ggez::graphics::Rect::new_i32(px, py, 1, 1);
-                let rectangle = graphics::Mesh::new_rectangle(
-                    ctx,
-                    graphics::DrawMode::fill(),
-                    rectangle,
-                    graphics::Color::from((255, 0, 0)),
-                )?;
-                graphics::draw(ctx, &rectangle, (mint::Point2 { x: 0.0, y: 0.0 },))?;
-            }
-        }
+        self.camera.draw_debug(ctx, 1.0)?;
         self.image
             .draw_camera(&self.camera, ctx, self.image_location, 0.0)?;
         graphics::present(ctx)?;
@@ -97,10 +81,10 @@ impl event::EventHandler for MainState {
             event::KeyCode::D => {
                 self.image_location.x += 0.1;
             }
-            event::KeyCode::Up => self.camera.move_by(Vec2::new(0.0, 0.1)),
-            event::KeyCode::Left => self.camera.move_by(Vec2::new(-0.1, 0.0)),
-            event::KeyCode::Down => self.camera.move_by(Vec2::new(0.0, -0.1)),
-            event::KeyCode::Right => self.camera.move_by(Vec2::new(0.1, 0.0)),
+            event::KeyCode::Up => self.camera.move_by(vector2(0.0, 0.1)),
+            event::KeyCode::Left => self.camera.move_by(vector2(-0.1, 0.0)),
+            event::KeyCode::Down => self.camera.move_by(vector2(0.0, -0.1)),
+            event::KeyCode::Right => self.camera.move_by(vector2(0.1, 0.0)),
             _ => (),
         };
         println!(
@@ -19,9 +19,7 @@ struct Tween {
 }
 
 fn interpolate(tween: &Tween) -> f32 {
-    cubic_inout((tween.t - tween.start) / tween.end)
-        .min(1.0)
-        .max(0.0)
+    cubic_inout((tween.t - tween.start) / tween.end).clamp(0.0, 1.0)
 }
 
 struct MainState {
@@ -61,7 +59,7 @@ impl event::EventHandler for MainState {
         graphics::clear(ctx, [0.0, 0.0, 0.0, 1.0].into());
         graphics::draw(
             ctx,
-            &mut self.image,
+            &self.image,
             (Point2::new(
                 WINDOW_WIDTH * interpolate(&self.tween) / 2.0,
                 WINDOW_HEIGHT / 2.0,
@@ -1,182 +1,148 @@
+//! Two scenes taking turns, plus a pause overlay on top, run with a
+//! `SceneRunner`.
+//!
+//! Space switches scenes, P pauses, Escape leaves the current scene
+//! (quitting once there are none left).
+
 extern crate ggez;
 extern crate ggez_goodies;
+
 use ggez::conf;
-/*
-use ggez::event;
+use ggez::event::{self, KeyCode};
 use ggez::graphics;
 use ggez::timer;
-use ggez::GameResult;
-use std::time::Duration;
+use ggez::{Context, GameResult};
 
 use ggez_goodies::scene::*;
 
-struct MainState {
-    font: graphics::Font,
+/// What all the scenes share.
+struct World {
     message_text: graphics::Text,
 }
 
-/// A bootstrap scene whose only purpose is to
-/// load all the other bits necessary...
-struct StartScene;
-impl SavedScene<MainState> for StartScene {
-    fn load(&self) -> Box<Scene<MainState>> {
-        Box::new(StartScene)
-    }
-    fn name(&self) -> &str {
-        "Starting Scene"
-    }
+struct TimerScene {
+    name: String,
+    next_name: String,
+    current_time: f64,
+    next: Option<SceneSwitch<World, SceneEvents>>,
 }
 
-impl Scene<MainState> for StartScene {
-    fn unload(&mut self) -> Box<SavedScene<MainState>> {
-        Box::new(StartScene)
-    }
-
-
-    fn update(&mut self,
-              _ctx: &mut ggez::Context,
-              _dt: Duration,
-              state: &mut SceneStore<MainState>)
-              -> GameResult<Option<String>> {
-        let s1 = SavedScene1::new("Scene 1", "Scene 2");
-        let s2 = SavedScene1::new("Scene 2", "Scene 1");
-        state.add(s1);
-        state.add(s2);
-        Ok(Some("Scene 1".to_string()))
-    }
-
-    fn draw(&mut self,
-            _ctx: &mut ggez::Context,
-            _store: &mut SceneStore<MainState>)
-            -> GameResult<()> {
-        Ok(())
+impl TimerScene {
+    fn new(name: &str, next_name: &str) -> Self {
+        TimerScene {
+            name: name.to_owned(),
+            next_name: next_name.to_owned(),
+            current_time: 0.0,
+            next: None,
+        }
     }
 }
 
-impl GameData<MainState> for MainState {
-    fn load(ctx: &mut ggez::Context, _conf: &conf::Conf) -> GameResult<Self> {
-        let font = graphics::Font::new(ctx, "DejaVuSerif.ttf", 16)?;
-
-        let text = graphics::Text::new(ctx, "Press space to switch to the next scene.", &font)?;
-        Ok(MainState {
-            font: font,
-            message_text: text,
-        })
+impl Scene<World, SceneEvents> for TimerScene {
+    fn update(&mut self, _world: &mut World, ctx: &mut Context) -> SceneSwitch<World, SceneEvents> {
+        self.current_time += timer::duration_to_f64(timer::delta(ctx));
+        self.next.take().unwrap_or(SceneSwitch::None)
     }
-    fn starting_scene() -> Box<SavedScene<MainState>> {
-        Box::new(StartScene)
-    }
-}
 
-#[derive(Clone, Debug)]
-struct SavedScene1 {
-    time_unloaded: f64,
-    name: String,
-    next_scene: String,
-}
+    fn draw(&mut self, world: &mut World, ctx: &mut Context) -> GameResult {
+        graphics::clear(ctx, [0.1, 0.2, 0.3, 1.0].into());
+        let message = format!(
+            "Scene '{}' has been running for {:0.2} seconds",
+            self.name, self.current_time
+        );
+        let text = graphics::Text::new(message);
+        graphics::draw(ctx, &text, (ggez::mint::Point2 { x: 10.0, y: 240.0 },))?;
+        graphics::draw(
+            ctx,
+            &world.message_text,
+            (ggez::mint::Point2 { x: 10.0, y: 270.0 },),
+        )
+    }
 
-impl SavedScene1 {
-    fn new(name: &str, next_scene: &str) -> Self {
-        SavedScene1 {
-            time_unloaded: 0.0,
-            name: name.to_string(),
-            next_scene: next_scene.to_string(),
+    fn input(&mut self, _world: &mut World, event: SceneEvents, started: bool) {
+        if let (SceneEvents::Key { keycode, .. }, true) = (event, started) {
+            match keycode {
+                KeyCode::Space => {
+                    let next = TimerScene::new(&self.next_name, &self.name);
+                    self.next = Some(SceneSwitch::replace(next));
+                }
+                KeyCode::P => self.next = Some(SceneSwitch::push(PauseScene { unpause: false })),
+                KeyCode::Escape => self.next = Some(SceneSwitch::Pop),
+                _ => (),
+            }
         }
     }
-}
-
-#[derive(Clone, Debug)]
-struct Scene1 {
-    current_time: f64,
-    name: String,
-    next_scene: String,
-    switch_to_next: bool,
-}
 
-
-impl SavedScene<MainState> for SavedScene1 {
-    fn load(&self) -> Box<Scene<MainState>> {
-        Box::new(Scene1 {
-            current_time: self.time_unloaded,
-            name: self.name.clone(),
-            next_scene: self.next_scene.clone(),
-            switch_to_next: false,
-        })
-    }
     fn name(&self) -> &str {
         &self.name
     }
 }
 
-impl Scene<MainState> for Scene1 {
-    fn unload(&mut self) -> Box<SavedScene<MainState>> {
-        Box::new(SavedScene1 {
-            time_unloaded: self.current_time,
-            name: self.name.clone(),
-            next_scene: self.next_scene.clone(),
-        })
-    }
-
+/// Dims the scene underneath, which doesn't update meanwhile.
+struct PauseScene {
+    unpause: bool,
+}
 
-    fn update(&mut self,
-              _ctx: &mut ggez::Context,
-              dt: Duration,
-              _state: &mut SceneStore<MainState>)
-              -> GameResult<Option<String>> {
-        let seconds = timer::duration_to_f64(dt);
-        self.current_time += seconds;
-        if self.switch_to_next {
-            Ok(Some(self.next_scene.clone()))
+impl Scene<World, SceneEvents> for PauseScene {
+    fn update(
+        &mut self,
+        _world: &mut World,
+        _ctx: &mut Context,
+    ) -> SceneSwitch<World, SceneEvents> {
+        if self.unpause {
+            SceneSwitch::Pop
         } else {
-            Ok(None)
+            SceneSwitch::None
         }
     }
 
-    fn draw(&mut self,
-            ctx: &mut ggez::Context,
-            store: &mut SceneStore<MainState>)
-            -> GameResult<()> {
-        ctx.renderer.clear();
-        let message = format!("Scene '{}' has been running for {:0.2} seconds",
-                              self.name,
-                              self.current_time);
-        let state = &mut store.game_data;
-        let text = &mut graphics::Text::new(ctx, &message, &state.font)?;
-        let text_rect = graphics::Rect::new(10, 240, text.width(), text.height());
-
-        try!(graphics::draw(ctx, text, None, Some(text_rect)));
-
-
-        let text_rect2 = graphics::Rect::new(10,
-                                             270,
-                                             state.message_text.width(),
-                                             state.message_text.height());
-
-        try!(graphics::draw(ctx, &mut state.message_text, None, Some(text_rect2)));
+    fn draw(&mut self, _world: &mut World, ctx: &mut Context) -> GameResult {
+        let (w, h) = graphics::drawable_size(ctx);
+        let shade = graphics::Mesh::new_rectangle(
+            ctx,
+            graphics::DrawMode::fill(),
+            graphics::Rect::new(0.0, 0.0, w, h),
+            graphics::Color::new(0.0, 0.0, 0.0, 0.6),
+        )?;
+        graphics::draw(ctx, &shade, graphics::DrawParam::default())?;
+        let text = graphics::Text::new("Paused; press P to carry on.");
+        graphics::draw(ctx, &text, (ggez::mint::Point2 { x: 10.0, y: 10.0 },))
+    }
 
-        ctx.renderer.present();
-        timer::sleep_until_next_frame(ctx, 60);
-        Ok(())
+    fn input(&mut self, _world: &mut World, event: SceneEvents, started: bool) {
+        if let (SceneEvents::Key { keycode, .. }, true) = (event, started) {
+            if keycode == KeyCode::P || keycode == KeyCode::Escape {
+                self.unpause = true;
+            }
+        }
     }
 
-    fn key_down_event(&mut self,
-                      keycode: Option<event::Keycode>,
-                      _keymod: event::Mod,
-                      _repeat: bool) {
-        if let Some(event::Keycode::Space) = keycode {
-            self.switch_to_next = true;
+    fn name(&self) -> &str {
+        "Pause"
+    }
 
-        }
+    fn draw_previous(&self) -> bool {
+        true
     }
 }
-*/
+
 pub fn main() {
-    let _c = conf::Conf::new();
-    /*let mut game: Game<SceneManager<MainState>> = Game::new("scenetest", c).unwrap();
-        if let Err(e) = game.run() {
-            println!("Error encountered: {:?}", e);
-        } else {
-            println!("Game exited cleanly.");
-        }
-    */
+    let (ctx, event_loop) = &mut ggez::ContextBuilder::new("scene_test", "test")
+        .window_setup(conf::WindowSetup::default().title("Scene test"))
+        .build()
+        .unwrap();
+    let world = World {
+        message_text: graphics::Text::new(
+            "Press space to switch to the next scene, P to pause, escape to leave.",
+        ),
+    };
+    let mut stack = SceneStack::new(ctx, world);
+    stack.push(Box::new(TimerScene::new("Scene 1", "Scene 2")));
+    let runner = &mut SceneRunner::new(stack);
+
+    if let Err(e) = event::run(ctx, event_loop, runner) {
+        println!("Error encountered: {}", e);
+    } else {
+        println!("Game exited cleanly.");
+    }
 }
@@ -1,3 +1,4 @@
+extern crate ezing;
 extern crate ggez;
 extern crate rand;
 
@@ -35,7 +36,8 @@ impl MainState {
                 Vector2 { x: 50.0, y: 0.0 },
             )
             .start_ang_vel_range(-10.0, 10.0)
-            .delta_size(Transition::range(15.0, 5.0))
+            .delta_size(Transition::eased(15.0, 5.0, ezing::quad_out))
+            .delta_alpha(Transition::eased(1.0, 0.0, ezing::cubic_in))
             .delta_color(Transition::range(
                 ggez::graphics::Color::from((255, 0, 0)),
                 ggez::graphics::Color::from((255, 255, 0)),
@@ -72,7 +74,7 @@ impl event::EventHandler for MainState {
         graphics::clear(ctx, [0.0, 0.0, 0.0, 1.0].into());
         graphics::draw(
             ctx,
-            &mut self.particles,
+            &self.particles,
             (Point2 {
                 x: WINDOW_WIDTH / 2.0,
                 y: WINDOW_HEIGHT / 2.0,
@@ -1,12 +1,28 @@
+//! Monospaced fonts drawn from a grid of glyphs in an image, for
+//! pixel-art games where ggez's TrueType text looks out of place.
+//!
+//! ```rust,ignore
+//! let image = graphics::Image::new(ctx, "/font.png")?;
+//! let map = TextMap::from_grid(" !\"#$%&'()*+,-./0123456789:;<=>?@ABCDEFGHIJKLMNOPQRSTUVWXYZ", 16, 4);
+//! let font = BitmapFont::new(image, map);
+//! font.draw(ctx, "HELLO", DrawParam::new().dest(Point2 { x: 10.0, y: 10.0 }))?;
+//! ```
 
+use std::cell::RefCell;
 use std::collections::HashMap;
-use ggez;
+
+use ggez::graphics::{self, spritebatch::SpriteBatch, DrawParam, Rect};
+use ggez::mint::Point2;
+use ggez::{Context, GameResult};
 
 /// Describes the layout of characters in your
 /// bitmap font.
-#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[derive(Clone, Debug, Default, PartialEq)]
 pub struct TextMap {
-    map: HashMap<char, ggez::Rect>,
+    map: HashMap<char, Rect>,
+    // Size of the grid, in glyphs.
+    columns: usize,
+    rows: usize,
 }
 
 impl TextMap {
@@ -15,18 +31,18 @@ impl TextMap {
     /// tall that the bitmap should be, and a string
     /// describing the characters in the map... in order,
     /// left to right, top to bottom.
-    /// 
+    ///
     /// The characters do not necessarily need to fill
     /// the entire image.  ie, if your image is 16x16 glyphs
     /// for 256 total, and you only use the first 150 of them,
     /// that's fine.
-    /// 
+    ///
     /// The floating point math involved should always be
-    /// exact for `Image`'s and sprites with a resolution 
+    /// exact for `Image`'s and sprites with a resolution
     /// that is a power of two, I think.
-    fn from_grid(mapping: &str, width: usize, height: usize) -> Self {
+    pub fn from_grid(mapping: &str, width: usize, height: usize) -> Self {
         // Assert the given width and height can fit the listed characters.
-        let num_chars = mapping.chars.count();
+        let num_chars = mapping.chars().count();
         assert!(num_chars <= width * height);
         let rect_width = 1.0 / (width as f32);
         let rect_height = 1.0 / (height as f32);
@@ -36,11 +52,11 @@ impl TextMap {
         for c in mapping.chars() {
             let x_offset = current_x as f32 * rect_width;
             let y_offset = current_y as f32 * rect_height;
-            let char_rect = ggez::Rect {
+            let char_rect = Rect {
                 x: x_offset,
                 y: y_offset,
                 w: rect_width,
-                h: rect_height;
+                h: rect_height,
             };
             map.insert(c, char_rect);
             current_x = (current_x + 1) % width;
@@ -51,17 +67,75 @@ impl TextMap {
 
         Self {
             map,
+            columns: width,
+            rows: height,
         }
     }
+
+    /// Where a character is in the image, as a fraction of its size,
+    /// or `None` if the font doesn't have it.
+    pub fn get(&self, c: char) -> Option<Rect> {
+        self.map.get(&c).copied()
+    }
 }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+/// A font made of a `TextMap` and the image it describes.  Every
+/// glyph is the same size, one grid cell of the image.
+#[derive(Clone, Debug, PartialEq)]
 pub struct BitmapFont {
-    bitmap: ggez::graphics::Image,
-    batch: ggez::graphics::SpriteBatch,
+    bitmap: graphics::Image,
+    batch: RefCell<SpriteBatch>,
     map: TextMap,
 }
 
 impl BitmapFont {
+    pub fn new(bitmap: graphics::Image, map: TextMap) -> Self {
+        let batch = RefCell::new(SpriteBatch::new(bitmap.clone()));
+        BitmapFont { bitmap, batch, map }
+    }
+
+    /// Width and height of one glyph in pixels, unscaled.
+    pub fn glyph_size(&self) -> (f32, f32) {
+        (
+            f32::from(self.bitmap.width()) / self.map.columns as f32,
+            f32::from(self.bitmap.height()) / self.map.rows as f32,
+        )
+    }
+
+    /// How wide `text` is in pixels, unscaled.  Text is a single line.
+    pub fn text_width(&self, text: &str) -> f32 {
+        self.glyph_size().0 * text.chars().count() as f32
+    }
 
-}
\ No newline at end of file
+    /// Draws `text` as a single line with its top-left corner at
+    /// `param`'s `dest`.  Characters the font doesn't have are left
+    /// blank.
+    pub fn draw(&self, ctx: &mut Context, text: &str, param: DrawParam) -> GameResult {
+        let mut batch = self.batch.borrow_mut();
+        batch.clear();
+        let (glyph_w, _) = self.glyph_size();
+        for (i, c) in text.chars().enumerate() {
+            if let Some(src) = self.map.get(c) {
+                batch.add(DrawParam::new().src(src).dest(Point2 {
+                    x: i as f32 * glyph_w,
+                    y: 0.0,
+                }));
+            }
+        }
+        graphics::draw(ctx, &*batch, param)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_text_map_grid() {
+        let map = TextMap::from_grid("ABCDEF", 4, 2);
+        assert_eq!(map.get('A'), Some(Rect::new(0.0, 0.0, 0.25, 0.5)));
+        assert_eq!(map.get('D'), Some(Rect::new(0.75, 0.0, 0.25, 0.5)));
+        assert_eq!(map.get('F'), Some(Rect::new(0.25, 0.5, 0.25, 0.5)));
+        assert_eq!(map.get('G'), None);
+    }
+}
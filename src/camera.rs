@@ -9,64 +9,819 @@
 //!
 //! Because that makes sense, darn it.
 //!
-//! However, does not yet do any actual camera movements like
-//! easing, pinning, etc.
-//! But a great source for how such things work is this:
+//! It can also follow a target around smoothly: call `follow()`
+//! with wherever the target is and `update()` once per frame.
+//! `play_path()` takes over from that for scripted moves, such as in
+//! cutscenes, and `blend_to()` cuts smoothly over to another camera's
+//! view.
+//! `add_trauma()` shakes the view, without moving the camera's
+//! actual location.  `ParallaxLayer`s draw backgrounds that scroll
+//! slower than the world, for a sense of depth.  `SplitScreen`
+//! draws several cameras' views side by side, for local multiplayer.
+//! `CameraBuilder` sets up cameras that keep the world the right
+//! shape when the window is resized.
+//!
+//! Instead of converting every draw call with `CameraDraw`, you can
+//! also `apply()` the camera's whole transform once and draw in world
+//! coordinates:
+//!
+//! ```rust,ignore
+//! self.camera.apply(ctx)?;
+//! // Flip things back over, since world Y is up.
+//! graphics::draw(ctx, &self.sprite, DrawParam::new().dest(pos).scale([1.0, -1.0]))?;
+//! Camera::restore(ctx)?;
+//! ```
+//!
+//! A great source for how such things work, and what else a camera
+//! could do, is this:
 //! http://www.gamasutra.com/blogs/ItayKeren/20150511/243083/Scroll_Back_The_Theory_and_Practice_of_Cameras_in_SideScrollers.php
 
-// TODO: Debug functions to draw world and camera grid!
+use std::hash::Hash;
 
 use ggez;
-use ggez::graphics;
-use ggez::mint;
-use ggez::GameResult;
-use nalgebra_glm::Vec2;
+use ggez::conf::NumSamples;
+use ggez::graphics::{self, Canvas, Color, DrawMode, Rect};
+use ggez::{Context, GameResult};
+
+use crate::input::InputState;
+use crate::math::{self, Point2, Vector2};
+use crate::random::ValueNoise;
+
+/// One step of a `CameraPath`.
+#[derive(Debug, Copy, Clone)]
+pub struct CameraKeyframe {
+    pub position: Point2,
+    pub zoom: f32,
+    /// Seconds it takes to get here from the previous keyframe.
+    pub duration: f32,
+    /// Maps time to progress, both in [0, 1].  The `ezing` crate
+    /// has plenty to choose from.
+    pub easing: fn(f32) -> f32,
+}
 
-// Used for mint interoperability.
-struct Vector2(Vec2);
-struct MintPoint2(mint::Point2<f32>);
+/// A scripted series of camera moves.
+///
+/// ```rust,ignore
+/// let path = CameraPath::new()
+///     .then(point2(10.0, 0.0), 1.0, 2.0, ezing::quad_inout)
+///     .then(point2(10.0, 10.0), 2.0, 1.0, ezing::linear)
+///     .on_finish(|| println!("fly-through done"));
+/// camera.play_path(path);
+/// ```
+#[derive(Default)]
+pub struct CameraPath {
+    keyframes: Vec<CameraKeyframe>,
+    on_finish: Option<Box<dyn FnOnce()>>,
+}
+
+impl CameraPath {
+    pub fn new() -> Self {
+        Self::default()
+    }
 
-impl Into<mint::Point2<f32>> for Vector2 {
-    fn into(self) -> mint::Point2<f32> {
-        mint::Point2 {
-            x: self.0.x,
-            y: self.0.y,
+    /// Adds a move to `position` and `zoom`, taking `duration`
+    /// seconds.
+    pub fn then(
+        mut self,
+        position: Point2,
+        zoom: f32,
+        duration: f32,
+        easing: fn(f32) -> f32,
+    ) -> Self {
+        self.keyframes.push(CameraKeyframe {
+            position,
+            zoom,
+            duration,
+            easing,
+        });
+        self
+    }
+
+    /// Sets something to call once the path has finished playing.
+    pub fn on_finish<F>(mut self, f: F) -> Self
+    where
+        F: FnOnce() + 'static,
+    {
+        self.on_finish = Some(Box::new(f));
+        self
+    }
+
+    pub fn keyframes(&self) -> &[CameraKeyframe] {
+        &self.keyframes
+    }
+}
+
+struct PathState {
+    path: CameraPath,
+    keyframe: usize,
+    elapsed: f32,
+    // Where the current keyframe started from.
+    from: (Point2, f32),
+    paused: bool,
+}
+
+struct BlendState {
+    from: (Point2, f32, f32),
+    to: (Point2, f32, f32),
+    duration: f32,
+    elapsed: f32,
+    easing: fn(f32) -> f32,
+}
+
+/// How a `Camera` shakes.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ShakeConfig {
+    /// How far the view moves at full trauma, in world units.
+    pub max_offset: f32,
+    /// How far the view turns at full trauma, in radians.
+    pub max_angle: f32,
+    /// How fast it shakes, roughly in bumps per second.
+    pub frequency: f32,
+    /// How much trauma wears off per second.
+    pub decay: f32,
+}
+
+impl Default for ShakeConfig {
+    fn default() -> Self {
+        ShakeConfig {
+            max_offset: 1.0,
+            max_angle: 0.1,
+            frequency: 15.0,
+            decay: 1.0,
         }
     }
 }
 
-impl Into<Vec2> for MintPoint2 {
-    fn into(self) -> Vec2 {
-        Vec2::new(self.0.x, self.0.y)
+/// How a `Camera` follows its target along one world axis.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub enum AxisFollow {
+    /// Follows both ways.  The default.
+    #[default]
+    Free,
+    /// Doesn't follow at all.
+    Locked,
+    /// Only follows towards larger values, never back, like the
+    /// scrolling in Super Mario Bros.
+    RatchetPositive,
+    /// Only follows towards smaller values, never back.
+    RatchetNegative,
+    /// Follows where the target last stood, as told by
+    /// `Camera::set_grounded()`, so the view doesn't bob up and down
+    /// with every jump.  Usually used for Y.
+    OnLanding,
+}
+
+impl AxisFollow {
+    /// Where the view center goes along this axis, given where it is
+    /// now, where following would put it, and where following the
+    /// last place the target landed would.
+    fn constrain(self, center: f32, goal: f32, landed: Option<f32>) -> f32 {
+        match self {
+            AxisFollow::Free => goal,
+            AxisFollow::Locked => center,
+            AxisFollow::RatchetPositive => goal.max(center),
+            AxisFollow::RatchetNegative => goal.min(center),
+            AxisFollow::OnLanding => landed.unwrap_or(center),
+        }
+    }
+}
+
+/// What a `Camera` does with its view size when the window's shape
+/// doesn't match it.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub enum AspectPolicy {
+    /// Keeps the view size, squashing or stretching the world to fit.
+    /// What `Camera::new()` does.
+    #[default]
+    Stretch,
+    /// Shows at least the view size, plus more world along whichever
+    /// axis the window has room to spare.
+    Expand,
+    /// Fills the window, cutting some of the view size off along
+    /// whichever axis the window is short of room.
+    Crop,
+}
+
+/// How a `Camera` decides how much of the world it shows.
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum ViewSizing {
+    /// A width and height, fitted to the window by an `AspectPolicy`.
+    Fixed(f32, f32, AspectPolicy),
+    /// A height, with the width following the window's shape.
+    Height(f32),
+    /// So many world units per pixel, whatever the window's size.
+    UnitsPerPixel(f32),
+}
+
+/// How far to move towards a goal this frame to close all but
+/// `e^-speed` of the distance per second.
+fn smoothing(speed: f32, dt: f32) -> f32 {
+    if speed.is_infinite() {
+        1.0
+    } else {
+        1.0 - (-speed * dt).exp()
     }
 }
 
 // Hmm.  Could, instead, use a 2d transformation
 // matrix, or create one of such.
 pub struct Camera {
-    screen_size: Vec2,
-    view_size: Vec2,
-    view_center: Vec2,
+    screen_size: Vector2,
+    // The size the game is laid out for, in pixels, if it's
+    // letterboxed to keep that aspect ratio.
+    virtual_resolution: Option<Vector2>,
+    view_sizing: ViewSizing,
+    view_size: Vector2,
+    view_center: Point2,
+    // Radians, counterclockwise.
+    rotation: f32,
+    zoom: f32,
+    follow_target: Option<Point2>,
+    follow_speed: f32,
+    // Size of the window around the view center the target can
+    // move in without the camera following, in view units.
+    dead_zone: Vector2,
+    bounds: Option<Rect>,
+    axis_follow: (AxisFollow, AxisFollow),
+    grounded: bool,
+    // Where the target was last time it was on the ground.
+    landed: Option<Point2>,
+    // Look-ahead: how many seconds of the target's velocity to lead
+    // it by, up to how far, and how fast the lead changes.
+    look_ahead_time: f32,
+    look_ahead_max: f32,
+    look_ahead_speed: f32,
+    look_ahead: Vector2,
+    target_velocity: Option<Vector2>,
+    last_target: Option<Point2>,
+    // Screen shake.  Trauma is in [0, 1] and the shake goes with
+    // its square, so small knocks are subtle and big ones violent.
+    shake: ShakeConfig,
+    trauma: f32,
+    shake_time: f32,
+    shake_offset: Vector2,
+    shake_angle: f32,
+    noise: ValueNoise,
+    path: Option<PathState>,
+    blend: Option<BlendState>,
 }
 
 impl Camera {
     pub fn new(screen_width: u32, screen_height: u32, view_width: f32, view_height: f32) -> Self {
-        let screen_size = Vec2::new(screen_width as f32, screen_height as f32);
-        let view_size = Vec2::new(view_width as f32, view_height as f32);
+        let screen_size = math::vector2(screen_width as f32, screen_height as f32);
+        let view_size = math::vector2(view_width, view_height);
         Camera {
-            screen_size: screen_size,
-            view_size: view_size,
-            view_center: Vec2::new(0.0, 0.0),
+            screen_size,
+            virtual_resolution: None,
+            view_sizing: ViewSizing::Fixed(view_width, view_height, AspectPolicy::Stretch),
+            view_size,
+            view_center: math::point2(0.0, 0.0),
+            rotation: 0.0,
+            zoom: 1.0,
+            follow_target: None,
+            follow_speed: 5.0,
+            dead_zone: math::vector2(0.0, 0.0),
+            bounds: None,
+            axis_follow: (AxisFollow::Free, AxisFollow::Free),
+            grounded: true,
+            landed: None,
+            look_ahead_time: 0.0,
+            look_ahead_max: 0.0,
+            look_ahead_speed: 2.0,
+            look_ahead: math::vector2(0.0, 0.0),
+            target_velocity: None,
+            last_target: None,
+            shake: ShakeConfig::default(),
+            trauma: 0.0,
+            shake_time: 0.0,
+            shake_offset: math::vector2(0.0, 0.0),
+            shake_angle: 0.0,
+            noise: ValueNoise::with_seed(0),
+            path: None,
+            blend: None,
+        }
+    }
+
+    /// Tells the camera the window's new size, in pixels, and works
+    /// out how much of the world it now shows.  Call it from your
+    /// `resize_event()` handler, along with
+    /// `graphics::set_screen_coordinates()` so ggez's pixels stay
+    /// the window's pixels.
+    pub fn resize(&mut self, screen_width: f32, screen_height: f32) {
+        self.screen_size = math::vector2(screen_width, screen_height);
+        self.update_view_size();
+    }
+
+    /// Sets how the view size adapts when the window's shape doesn't
+    /// match it.  See `CameraBuilder` for other ways to size the view.
+    pub fn set_aspect_policy(&mut self, policy: AspectPolicy) {
+        let (w, h) = match self.view_sizing {
+            ViewSizing::Fixed(w, h, _) => (w, h),
+            _ => (self.view_size.x, self.view_size.y),
+        };
+        self.view_sizing = ViewSizing::Fixed(w, h, policy);
+        self.update_view_size();
+    }
+
+    /// The size of the world-space area the view covers, in world
+    /// units, before zooming.
+    pub fn view_size(&self) -> (f32, f32) {
+        (self.view_size.x, self.view_size.y)
+    }
+
+    fn update_view_size(&mut self) {
+        let screen = self.screen_viewport();
+        if screen.w <= 0.0 || screen.h <= 0.0 {
+            return;
         }
+        let aspect = screen.w / screen.h;
+        let (w, h) = match self.view_sizing {
+            ViewSizing::Fixed(w, h, AspectPolicy::Stretch) => (w, h),
+            ViewSizing::Fixed(w, h, AspectPolicy::Expand) => {
+                if aspect > w / h {
+                    (h * aspect, h)
+                } else {
+                    (w, w / aspect)
+                }
+            }
+            ViewSizing::Fixed(w, h, AspectPolicy::Crop) => {
+                if aspect > w / h {
+                    (w, w / aspect)
+                } else {
+                    (h * aspect, h)
+                }
+            }
+            ViewSizing::Height(h) => (h * aspect, h),
+            ViewSizing::UnitsPerPixel(u) => (screen.w * u, screen.h * u),
+        };
+        self.view_size = math::vector2(w, h);
+        self.clamp_to_bounds();
+    }
+
+    /// Lays the game out for a fixed resolution: the view is drawn
+    /// into the biggest area of the same shape that fits in the
+    /// window, centered, with bars down the sides or along the top
+    /// and bottom to fill the rest.  `draw_letterbox()` draws them.
+    pub fn set_virtual_resolution(&mut self, width: f32, height: f32) {
+        self.virtual_resolution = Some(math::vector2(width, height));
+        self.update_view_size();
+    }
+
+    /// Goes back to using the whole window, however it's shaped.
+    pub fn clear_virtual_resolution(&mut self) {
+        self.virtual_resolution = None;
+        self.update_view_size();
     }
 
-    pub fn move_by(&mut self, by: Vec2) {
+    pub fn virtual_resolution(&self) -> Option<(f32, f32)> {
+        self.virtual_resolution.map(|v| (v.x, v.y))
+    }
+
+    /// The part of the window the view is drawn into, in pixels.
+    /// That's the whole window unless there's a virtual resolution.
+    pub fn screen_viewport(&self) -> Rect {
+        let (w, h) = (self.screen_size.x, self.screen_size.y);
+        match self.virtual_resolution {
+            Some(v) if v.x > 0.0 && v.y > 0.0 => {
+                let scale = (w / v.x).min(h / v.y);
+                let (vw, vh) = (v.x * scale, v.y * scale);
+                Rect::new((w - vw) / 2.0, (h - vh) / 2.0, vw, vh)
+            }
+            _ => Rect::new(0.0, 0.0, w, h),
+        }
+    }
+
+    /// Fills the window outside `screen_viewport()` with `color`.
+    /// Draw it last, in screen coordinates.
+    pub fn draw_letterbox(&self, ctx: &mut Context, color: Color) -> GameResult {
+        let r = self.screen_viewport();
+        let (w, h) = (self.screen_size.x, self.screen_size.y);
+        let bars = [
+            Rect::new(0.0, 0.0, r.x, h),
+            Rect::new(r.x + r.w, 0.0, w - r.x - r.w, h),
+            Rect::new(r.x, 0.0, r.w, r.y),
+            Rect::new(r.x, r.y + r.h, r.w, h - r.y - r.h),
+        ];
+        let mut mb = graphics::MeshBuilder::new();
+        let mut any = false;
+        for bar in bars.iter().filter(|b| b.w > 0.0 && b.h > 0.0) {
+            mb.rectangle(DrawMode::fill(), *bar, color);
+            any = true;
+        }
+        if !any {
+            return Ok(());
+        }
+        let mesh = mb.build(ctx)?;
+        graphics::draw(ctx, &mesh, graphics::DrawParam::default())
+    }
+
+    pub fn move_by(&mut self, by: Vector2) {
         self.view_center.x += by.x;
         self.view_center.y += by.y;
+        self.clamp_to_bounds();
     }
 
-    pub fn move_to(&mut self, to: Vec2) {
+    pub fn move_to(&mut self, to: Point2) {
         self.view_center = to;
+        self.clamp_to_bounds();
+    }
+
+    /// Keeps the camera from showing anything outside `bounds`, a
+    /// world-space rectangle whose `x` and `y` are its bottom-left
+    /// corner.  If the bounds are smaller than the view, the camera
+    /// stays centered on them.
+    pub fn set_bounds(&mut self, bounds: Rect) {
+        self.bounds = Some(bounds);
+        self.clamp_to_bounds();
+    }
+
+    pub fn clear_bounds(&mut self) {
+        self.bounds = None;
+    }
+
+    pub fn bounds(&self) -> Option<Rect> {
+        self.bounds
+    }
+
+    /// Half the width and height of the world-space area the view
+    /// covers, allowing for rotation.
+    fn visible_half_size(&self) -> (f32, f32) {
+        let (sin, cos) = self.rotation.sin_cos();
+        let (sin, cos) = (sin.abs(), cos.abs());
+        let w = self.view_size.x / self.zoom / 2.0;
+        let h = self.view_size.y / self.zoom / 2.0;
+        (w * cos + h * sin, w * sin + h * cos)
+    }
+
+    fn clamp_to_bounds(&mut self) {
+        fn clamp(center: f32, half_size: f32, min: f32, size: f32) -> f32 {
+            if size <= half_size * 2.0 {
+                min + size / 2.0
+            } else {
+                center.max(min + half_size).min(min + size - half_size)
+            }
+        }
+        if let Some(bounds) = self.bounds {
+            let (half_w, half_h) = self.visible_half_size();
+            self.view_center.x = clamp(self.view_center.x, half_w, bounds.x, bounds.w);
+            self.view_center.y = clamp(self.view_center.y, half_h, bounds.y, bounds.h);
+        }
+    }
+
+    /// Makes the camera move towards `target` in `update()`.  Call it
+    /// every frame with wherever the thing you're following is.
+    pub fn follow(&mut self, target: Point2) {
+        self.follow_target = Some(target);
+    }
+
+    /// Like `follow()`, but gives the target's velocity for
+    /// look-ahead instead of working it out from how it moves.
+    pub fn follow_with_velocity(&mut self, target: Point2, velocity: Vector2) {
+        self.follow_target = Some(target);
+        self.target_velocity = Some(velocity);
+    }
+
+    /// Stops following, leaving the camera where it is.
+    pub fn unfollow(&mut self) {
+        self.follow_target = None;
+        self.last_target = None;
+        self.landed = None;
+        self.look_ahead = math::vector2(0.0, 0.0);
+    }
+
+    /// Sets how the camera follows its target along the world's X and
+    /// Y axes, for the usual side-scroller tricks: locking the Y
+    /// axis, scrolling only one way, or only moving vertically when
+    /// the player lands.  See `AxisFollow`.
+    pub fn set_axis_follow(&mut self, x: AxisFollow, y: AxisFollow) {
+        self.axis_follow = (x, y);
+    }
+
+    pub fn axis_follow(&self) -> (AxisFollow, AxisFollow) {
+        self.axis_follow
+    }
+
+    /// Tells the camera whether the target is standing on something,
+    /// for `AxisFollow::OnLanding`.  Call it every frame along with
+    /// `follow()`.  Defaults to true.
+    pub fn set_grounded(&mut self, grounded: bool) {
+        self.grounded = grounded;
+    }
+
+    /// Makes the camera lead the target in the direction it's moving,
+    /// by `time` seconds' worth of its velocity but at most
+    /// `max_offset` world units.  The lead changes at `return_speed`,
+    /// like `set_follow_speed()`, so it doesn't jerk about when the
+    /// target turns.  A `time` of 0, the default, turns it off.
+    pub fn set_look_ahead(&mut self, time: f32, max_offset: f32, return_speed: f32) {
+        self.look_ahead_time = time;
+        self.look_ahead_max = max_offset;
+        self.look_ahead_speed = return_speed;
+    }
+
+    /// Sets how quickly the camera catches up with its target: each
+    /// second it closes all but `e^-speed` of the distance.  The
+    /// default is 5; `f32::INFINITY` keeps it locked on the target.
+    pub fn set_follow_speed(&mut self, speed: f32) {
+        self.follow_speed = speed;
+    }
+
+    /// Sets a window around the middle of the view that the target
+    /// can move around in without the camera following, like in most
+    /// platformers.  It's `width` by `height` world units, measured
+    /// along the view's axes.  0 by 0, the default, always follows.
+    pub fn set_dead_zone(&mut self, width: f32, height: f32) {
+        self.dead_zone = math::vector2(width, height);
+    }
+
+    /// Shakes the camera.  Trauma adds up to a maximum of 1 and
+    /// wears off over time.
+    pub fn add_trauma(&mut self, amount: f32) {
+        self.trauma = (self.trauma + amount).clamp(0.0, 1.0);
+    }
+
+    pub fn trauma(&self) -> f32 {
+        self.trauma
+    }
+
+    pub fn set_shake_config(&mut self, config: ShakeConfig) {
+        self.shake = config;
+    }
+
+    /// Moves the camera along its blend, its path or after what it's
+    /// following, in that order of priority, and updates the screen
+    /// shake.
+    pub fn update(&mut self, dt: f32) {
+        if self.blend.is_some() {
+            self.update_blend(dt);
+        } else if self.path.is_some() {
+            self.update_path(dt);
+        } else if let Some(target) = self.follow_target {
+            self.update_look_ahead(target, dt);
+            let target = math::point2(target.x + self.look_ahead.x, target.y + self.look_ahead.y);
+            if self.grounded {
+                self.landed = Some(target);
+            }
+            let goal = self.dead_zone_goal(target);
+            let landed = self.landed.map(|p| self.dead_zone_goal(p));
+            let (follow_x, follow_y) = self.axis_follow;
+            let goal = math::point2(
+                follow_x.constrain(self.view_center.x, goal.x, landed.map(|p| p.x)),
+                follow_y.constrain(self.view_center.y, goal.y, landed.map(|p| p.y)),
+            );
+            let t = smoothing(self.follow_speed, dt);
+            self.view_center.x += (goal.x - self.view_center.x) * t;
+            self.view_center.y += (goal.y - self.view_center.y) * t;
+            self.clamp_to_bounds();
+        }
+        self.update_shake(dt);
+    }
+
+    fn update_look_ahead(&mut self, target: Point2, dt: f32) {
+        let last = self.last_target.replace(target);
+        let velocity = match (self.target_velocity.take(), last) {
+            (Some(v), _) => v,
+            (None, Some(last)) if dt > 0.0 => {
+                math::vector2((target.x - last.x) / dt, (target.y - last.y) / dt)
+            }
+            _ => math::vector2(0.0, 0.0),
+        };
+        if self.look_ahead_time <= 0.0 {
+            self.look_ahead = math::vector2(0.0, 0.0);
+            return;
+        }
+        let mut x = velocity.x * self.look_ahead_time;
+        let mut y = velocity.y * self.look_ahead_time;
+        let len = (x * x + y * y).sqrt();
+        if len > self.look_ahead_max {
+            x *= self.look_ahead_max / len;
+            y *= self.look_ahead_max / len;
+        }
+        let t = smoothing(self.look_ahead_speed, dt);
+        self.look_ahead = math::vector2(
+            self.look_ahead.x + (x - self.look_ahead.x) * t,
+            self.look_ahead.y + (y - self.look_ahead.y) * t,
+        );
+    }
+
+    fn update_shake(&mut self, dt: f32) {
+        self.trauma = (self.trauma - self.shake.decay * dt).max(0.0);
+        if self.trauma <= 0.0 {
+            self.shake_time = 0.0;
+            self.shake_offset = math::vector2(0.0, 0.0);
+            self.shake_angle = 0.0;
+            return;
+        }
+        self.shake_time += dt;
+        let amount = self.trauma * self.trauma;
+        let t = self.shake_time * self.shake.frequency;
+        // Separate stretches of the same noise for each, mapped to
+        // [-1, 1].
+        let noise = |offset: f32| self.noise.noise1(t + offset) * 2.0 - 1.0;
+        let x = noise(0.0) * self.shake.max_offset * amount;
+        let y = noise(64.0) * self.shake.max_offset * amount;
+        self.shake_angle = noise(128.0) * self.shake.max_angle * amount;
+        self.shake_offset = math::vector2(x, y);
+    }
+
+    /// The view center and rotation things are actually drawn with,
+    /// including any shake.
+    fn shaken_view(&self) -> (Point2, f32) {
+        let center = math::point2(
+            self.view_center.x + self.shake_offset.x,
+            self.view_center.y + self.shake_offset.y,
+        );
+        (center, self.rotation + self.shake_angle)
+    }
+
+    /// Where the view center needs to be to get the target just back
+    /// inside the dead zone.
+    fn dead_zone_goal(&self, target: Point2) -> Point2 {
+        fn outside(d: f32, half_width: f32) -> f32 {
+            if d > half_width {
+                d - half_width
+            } else if d < -half_width {
+                d + half_width
+            } else {
+                0.0
+            }
+        }
+        let (sin, cos) = self.rotation.sin_cos();
+        let dx = target.x - self.view_center.x;
+        let dy = target.y - self.view_center.y;
+        let view_x = outside(dx * cos + dy * sin, self.dead_zone.x / 2.0);
+        let view_y = outside(dy * cos - dx * sin, self.dead_zone.y / 2.0);
+        math::point2(
+            self.view_center.x + view_x * cos - view_y * sin,
+            self.view_center.y + view_x * sin + view_y * cos,
+        )
+    }
+
+    /// Zooms in (above 1) or out (below 1) from the view size the
+    /// camera was created with.
+    pub fn set_zoom(&mut self, zoom: f32) {
+        self.zoom = zoom;
+        self.clamp_to_bounds();
+    }
+
+    pub fn zoom(&self) -> f32 {
+        self.zoom
+    }
+
+    /// Centers and zooms the camera so the given world-space
+    /// rectangle (bottom-left corner at `x` and `y`), plus `padding`
+    /// world units all round, just fits in the view.
+    pub fn fit_rect(&mut self, rect: Rect, padding: f32) {
+        let w = rect.w + padding * 2.0;
+        let h = rect.h + padding * 2.0;
+        // How big the rectangle is along the view's axes.
+        let (sin, cos) = self.rotation.sin_cos();
+        let (sin, cos) = (sin.abs(), cos.abs());
+        let view_w = w * cos + h * sin;
+        let view_h = w * sin + h * cos;
+        let zoom_x = self.view_size.x / view_w;
+        let zoom_y = self.view_size.y / view_h;
+        let zoom = zoom_x.min(zoom_y);
+        if zoom.is_finite() && zoom > 0.0 {
+            self.zoom = zoom;
+        }
+        self.move_to(math::point2(rect.x + rect.w / 2.0, rect.y + rect.h / 2.0));
+    }
+
+    /// Starts playing a camera path from wherever the camera is now.
+    /// Following is suspended until it finishes.
+    pub fn play_path(&mut self, path: CameraPath) {
+        self.path = Some(PathState {
+            path,
+            keyframe: 0,
+            elapsed: 0.0,
+            from: (self.view_center, self.zoom),
+            paused: false,
+        });
+    }
+
+    pub fn pause_path(&mut self) {
+        if let Some(state) = self.path.as_mut() {
+            state.paused = true;
+        }
+    }
+
+    pub fn resume_path(&mut self) {
+        if let Some(state) = self.path.as_mut() {
+            state.paused = false;
+        }
+    }
+
+    /// Stops the current path where it is, without calling its
+    /// `on_finish`.
+    pub fn stop_path(&mut self) {
+        self.path = None;
+    }
+
+    /// Returns whether a path is playing, or paused partway through.
+    pub fn is_playing_path(&self) -> bool {
+        self.path.is_some()
+    }
+
+    fn update_path(&mut self, dt: f32) {
+        let mut state = match self.path.take() {
+            Some(state) => state,
+            None => return,
+        };
+        if !state.paused {
+            state.elapsed += dt;
+        }
+        while let Some(key) = state.path.keyframes.get(state.keyframe).copied() {
+            let (from, from_zoom) = state.from;
+            if state.elapsed < key.duration {
+                let t = (key.easing)(state.elapsed / key.duration);
+                self.view_center = math::point2(
+                    from.x + (key.position.x - from.x) * t,
+                    from.y + (key.position.y - from.y) * t,
+                );
+                self.zoom = from_zoom + (key.zoom - from_zoom) * t;
+                self.clamp_to_bounds();
+                self.path = Some(state);
+                return;
+            }
+            state.elapsed -= key.duration;
+            state.keyframe += 1;
+            state.from = (key.position, key.zoom);
+            self.view_center = key.position;
+            self.zoom = key.zoom;
+            self.clamp_to_bounds();
+        }
+        if let Some(on_finish) = state.path.on_finish {
+            on_finish();
+        }
+    }
+
+    /// Moves the camera smoothly to where `other` is looking, over
+    /// `duration` seconds: its center, zoom and rotation, turning
+    /// whichever way round is shorter.  `easing` maps time to
+    /// progress, both in [0, 1].
+    ///
+    /// Takes over from following and paths until it's done, so
+    /// `unfollow()` first if the camera should stay where it ends up.
+    pub fn blend_to(&mut self, other: &Camera, duration: f32, easing: fn(f32) -> f32) {
+        self.blend = Some(BlendState {
+            from: (self.view_center, self.zoom, self.rotation),
+            to: (other.view_center, other.zoom, other.rotation),
+            duration,
+            elapsed: 0.0,
+            easing,
+        });
+        self.update_blend(0.0);
+    }
+
+    /// Stops the current blend where it is.
+    pub fn stop_blend(&mut self) {
+        self.blend = None;
+    }
+
+    pub fn is_blending(&self) -> bool {
+        self.blend.is_some()
+    }
+
+    fn update_blend(&mut self, dt: f32) {
+        let state = match self.blend.as_mut() {
+            Some(state) => state,
+            None => return,
+        };
+        state.elapsed += dt;
+        let (from, from_zoom, from_rotation) = state.from;
+        let (to, to_zoom, to_rotation) = state.to;
+        if state.elapsed >= state.duration {
+            self.blend = None;
+            self.view_center = to;
+            self.zoom = to_zoom;
+            self.rotation = to_rotation;
+        } else {
+            let t = (state.easing)(state.elapsed / state.duration);
+            let turn = std::f32::consts::PI * 2.0;
+            let by = (to_rotation - from_rotation + turn / 2.0).rem_euclid(turn) - turn / 2.0;
+            self.view_center =
+                math::point2(from.x + (to.x - from.x) * t, from.y + (to.y - from.y) * t);
+            self.zoom = from_zoom + (to_zoom - from_zoom) * t;
+            self.rotation = from_rotation + by * t;
+        }
+        self.clamp_to_bounds();
+    }
+
+    /// Sets how far the view is turned, in radians counterclockwise.
+    /// The world appears to turn the other way.
+    pub fn set_rotation(&mut self, rotation: f32) {
+        self.rotation = rotation;
+        self.clamp_to_bounds();
+    }
+
+    pub fn rotate_by(&mut self, by: f32) {
+        self.rotation += by;
+        self.clamp_to_bounds();
+    }
+
+    pub fn rotation(&self) -> f32 {
+        self.rotation
     }
 
     /// Translates a point in world-space to a point in
@@ -75,41 +830,292 @@ impl Camera {
     /// Does not do any clipping or anything, since it does
     /// not know how large the thing that might be drawn is;
     /// that's not its job.
-    pub fn world_to_screen_coords(&self, from: Vec2) -> (i32, i32) {
-        let pixels_per_unit = self.screen_size.component_div(&self.view_size);
-        let view_offset = from - self.view_center;
-        let view_scale = view_offset.component_mul(&pixels_per_unit);
+    ///
+    /// The result isn't rounded to whole pixels, so things move
+    /// smoothly when the camera or they move by less than a pixel.
+    pub fn world_to_screen_coords(&self, from: Point2) -> Point2 {
+        let screen = self.screen_viewport();
+        let pixels_per_unit_x = screen.w / self.view_size.x * self.zoom;
+        let pixels_per_unit_y = screen.h / self.view_size.y * self.zoom;
+        // Undo the view's rotation around its center.
+        let (center, rotation) = self.shaken_view();
+        let (sin, cos) = rotation.sin_cos();
+        let dx = from.x - center.x;
+        let dy = from.y - center.y;
+        let view_x = dx * cos + dy * sin;
+        let view_y = dy * cos - dx * sin;
+        let view_scale_x = view_x * pixels_per_unit_x;
+        let view_scale_y = view_y * pixels_per_unit_y;
+
+        let x = screen.x + view_scale_x + screen.w / 2.0;
+        let y = screen.y + screen.h - (view_scale_y + screen.h / 2.0);
+        math::point2(x, y)
+    }
 
-        let x = view_scale.x + self.screen_size.x / 2.0;
-        let y = self.screen_size.y - (view_scale.y + self.screen_size.y / 2.0);
-        (x as i32, y as i32)
+    /// Like `world_to_screen_coords()`, rounded to the nearest pixel.
+    pub fn world_to_screen_coords_i32(&self, from: Point2) -> (i32, i32) {
+        let p = self.world_to_screen_coords(from);
+        (p.x.round() as i32, p.y.round() as i32)
     }
 
     // p_screen = max_p - p + max_p/2
     // p_screen - max_p/2 = max_p - p
     // p_screen - max_p/2 + max_p = -p
     // -p_screen - max_p/2 + max_p = p
-    pub fn screen_to_world_coords(&self, from: (i32, i32)) -> Vec2 {
-        let (sx, sy) = from;
-        let sx = sx as f32;
-        let sy = sy as f32;
-        let flipped_x = sx - (self.screen_size.x / 2.0);
-        let flipped_y = -sy + self.screen_size.y / 2.0;
-        let screen_coords = Vec2::new(flipped_x, flipped_y);
-        let units_per_pixel = self.view_size.component_div(&self.screen_size);
-        let view_scale = screen_coords.component_mul(&units_per_pixel);
-        let view_offset = self.view_center + view_scale;
+    pub fn screen_to_world_coords(&self, from: (i32, i32)) -> Point2 {
+        self.screen_to_world(from.0 as f32, from.1 as f32)
+    }
+
+    fn screen_to_world(&self, sx: f32, sy: f32) -> Point2 {
+        let screen = self.screen_viewport();
+        let flipped_x = sx - (screen.x + screen.w / 2.0);
+        let flipped_y = -sy + screen.y + screen.h / 2.0;
+        let units_per_pixel_x = self.view_size.x / self.zoom / screen.w;
+        let units_per_pixel_y = self.view_size.y / self.zoom / screen.h;
+        let view_x = flipped_x * units_per_pixel_x;
+        let view_y = flipped_y * units_per_pixel_y;
+        let (center, rotation) = self.shaken_view();
+        let (sin, cos) = rotation.sin_cos();
+        math::point2(
+            center.x + view_x * cos - view_y * sin,
+            center.y + view_x * sin + view_y * cos,
+        )
+    }
 
-        view_offset
+    /// Where the mouse cursor is in the world, going by the last
+    /// position `input` was told about, with zoom, rotation and shake
+    /// all accounted for.
+    pub fn mouse_world_position<Axes, Buttons>(&self, input: &InputState<Axes, Buttons>) -> Point2
+    where
+        Axes: Hash + Eq + Clone,
+        Buttons: Hash + Eq + Clone,
+    {
+        let p = input.get_mouse_position();
+        self.screen_to_world(p.x, p.y)
     }
 
-    pub fn location(&self) -> Vec2 {
+    pub fn location(&self) -> Point2 {
         self.view_center
     }
 
-    fn calculate_dest_point(&self, location: Vec2) -> Vec2 {
-        let (sx, sy) = self.world_to_screen_coords(location);
-        Vec2::new(sx as f32, sy as f32)
+    fn calculate_dest_point(&self, location: Point2) -> Point2 {
+        self.world_to_screen_coords(location)
+    }
+
+    /// The transform from world to screen coordinates as a matrix:
+    /// translation, scale, rotation and flipping Y, shake included.
+    pub fn transform(&self) -> ggez::mint::ColumnMatrix4<f32> {
+        let screen = self.screen_viewport();
+        let sx = screen.w / self.view_size.x * self.zoom;
+        let sy = screen.h / self.view_size.y * self.zoom;
+        let (center, rotation) = self.shaken_view();
+        let (sin, cos) = rotation.sin_cos();
+        // The same thing world_to_screen_coords() does, multiplied out:
+        // screen = (a, b; c, d) * (p - center) + viewport center
+        let (a, b) = (sx * cos, sx * sin);
+        let (c, d) = (sy * sin, -sy * cos);
+        let tx = screen.x + screen.w / 2.0 - (a * center.x + b * center.y);
+        let ty = screen.y + screen.h / 2.0 - (c * center.x + d * center.y);
+        [
+            [a, c, 0.0, 0.0],
+            [b, d, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [tx, ty, 0.0, 1.0],
+        ]
+        .into()
+    }
+
+    /// Draws lines every `grid_spacing` world units, brighter for the
+    /// axes, plus outlines of the view itself (without shake), the
+    /// dead zone and the bounds, for debugging.  Draws in screen
+    /// coordinates, so don't `apply()` the camera first.
+    pub fn draw_debug(&self, ctx: &mut Context, grid_spacing: f32) -> GameResult {
+        const MAX_GRID_LINES: f32 = 500.0;
+        let grid_color = Color::new(0.5, 0.5, 0.5, 0.3);
+        let axis_color = Color::new(0.8, 0.8, 0.8, 0.8);
+        let line = |mb: &mut graphics::MeshBuilder, a: Point2, b: Point2, color| {
+            let (a, b) = (
+                self.world_to_screen_coords(a),
+                self.world_to_screen_coords(b),
+            );
+            mb.line(
+                &[math::point2_to_mint(a), math::point2_to_mint(b)],
+                1.0,
+                color,
+            )
+            .map(|_| ())
+        };
+        let rect =
+            |mb: &mut graphics::MeshBuilder, center: Point2, w: f32, h: f32, angle: f32, color| {
+                let (sin, cos) = angle.sin_cos();
+                let corners: Vec<_> = [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)]
+                    .iter()
+                    .map(|(x, y)| {
+                        let (x, y) = (x * w / 2.0, y * h / 2.0);
+                        let p = math::point2(
+                            center.x + x * cos - y * sin,
+                            center.y + x * sin + y * cos,
+                        );
+                        math::point2_to_mint(self.world_to_screen_coords(p))
+                    })
+                    .collect();
+                mb.polygon(DrawMode::stroke(2.0), &corners, color)
+                    .map(|_| ())
+            };
+
+        let mut mb = graphics::MeshBuilder::new();
+        // The world-space area on screen.
+        let screen = self.screen_viewport();
+        let corners = [
+            self.screen_to_world(screen.x, screen.y),
+            self.screen_to_world(screen.x + screen.w, screen.y),
+            self.screen_to_world(screen.x, screen.y + screen.h),
+            self.screen_to_world(screen.x + screen.w, screen.y + screen.h),
+        ];
+        let min_x = corners.iter().map(|p| p.x).fold(f32::INFINITY, f32::min);
+        let max_x = corners
+            .iter()
+            .map(|p| p.x)
+            .fold(f32::NEG_INFINITY, f32::max);
+        let min_y = corners.iter().map(|p| p.y).fold(f32::INFINITY, f32::min);
+        let max_y = corners
+            .iter()
+            .map(|p| p.y)
+            .fold(f32::NEG_INFINITY, f32::max);
+        let lines = (max_x - min_x + max_y - min_y) / grid_spacing;
+        if grid_spacing > 0.0 && lines < MAX_GRID_LINES {
+            let first_x = (min_x / grid_spacing).ceil() as i32;
+            let last_x = (max_x / grid_spacing).floor() as i32;
+            for i in first_x..=last_x {
+                let x = i as f32 * grid_spacing;
+                let color = if i == 0 { axis_color } else { grid_color };
+                line(
+                    &mut mb,
+                    math::point2(x, min_y),
+                    math::point2(x, max_y),
+                    color,
+                )?;
+            }
+            let first_y = (min_y / grid_spacing).ceil() as i32;
+            let last_y = (max_y / grid_spacing).floor() as i32;
+            for i in first_y..=last_y {
+                let y = i as f32 * grid_spacing;
+                let color = if i == 0 { axis_color } else { grid_color };
+                line(
+                    &mut mb,
+                    math::point2(min_x, y),
+                    math::point2(max_x, y),
+                    color,
+                )?;
+            }
+        }
+
+        let view_w = self.view_size.x / self.zoom;
+        let view_h = self.view_size.y / self.zoom;
+        let green = Color::new(0.2, 1.0, 0.2, 1.0);
+        rect(
+            &mut mb,
+            self.view_center,
+            view_w,
+            view_h,
+            self.rotation,
+            green,
+        )?;
+        if self.dead_zone.x > 0.0 || self.dead_zone.y > 0.0 {
+            let yellow = Color::new(1.0, 1.0, 0.2, 1.0);
+            let (w, h) = (self.dead_zone.x, self.dead_zone.y);
+            rect(&mut mb, self.view_center, w, h, self.rotation, yellow)?;
+        }
+        if let Some(b) = self.bounds {
+            let red = Color::new(1.0, 0.2, 0.2, 1.0);
+            let center = math::point2(b.x + b.w / 2.0, b.y + b.h / 2.0);
+            rect(&mut mb, center, b.w, b.h, 0.0, red)?;
+        }
+        let mesh = mb.build(ctx)?;
+        graphics::draw(ctx, &mesh, graphics::DrawParam::default())
+    }
+
+    /// Pushes the camera's transform, so everything drawn until
+    /// `restore()` is in world coordinates.
+    pub fn apply(&self, ctx: &mut Context) -> GameResult {
+        graphics::push_transform(ctx, Some(self.transform()));
+        graphics::apply_transformations(ctx)
+    }
+
+    /// Undoes `apply()`.
+    pub fn restore(ctx: &mut Context) -> GameResult {
+        graphics::pop_transform(ctx);
+        graphics::apply_transformations(ctx)
+    }
+}
+
+/// Sets up a `Camera` that copes with the window changing shape.
+///
+/// ```rust,ignore
+/// let camera = CameraBuilder::new(800, 600)
+///     .view_size(40.0, 30.0)
+///     .aspect_policy(AspectPolicy::Expand)
+///     .build();
+/// ```
+pub struct CameraBuilder {
+    camera: Camera,
+}
+
+impl CameraBuilder {
+    /// Starts off with a view one world unit per pixel, stretched
+    /// to fit, like `Camera::new()`.
+    pub fn new(screen_width: u32, screen_height: u32) -> Self {
+        let camera = Camera::new(
+            screen_width,
+            screen_height,
+            screen_width as f32,
+            screen_height as f32,
+        );
+        CameraBuilder { camera }
+    }
+
+    pub fn build(mut self) -> Camera {
+        self.camera.update_view_size();
+        self.camera
+    }
+
+    /// Shows `width` by `height` world units, fitted to the window
+    /// according to the aspect policy.
+    pub fn view_size(mut self, width: f32, height: f32) -> Self {
+        let policy = match self.camera.view_sizing {
+            ViewSizing::Fixed(_, _, policy) => policy,
+            _ => AspectPolicy::default(),
+        };
+        self.camera.view_sizing = ViewSizing::Fixed(width, height, policy);
+        self
+    }
+
+    /// Shows `height` world units top to bottom, and however many
+    /// fit across the window.
+    pub fn view_height(mut self, height: f32) -> Self {
+        self.camera.view_sizing = ViewSizing::Height(height);
+        self
+    }
+
+    /// Makes each pixel cover `units` world units, so a bigger window
+    /// shows more of the world rather than bigger things.
+    pub fn units_per_pixel(mut self, units: f32) -> Self {
+        self.camera.view_sizing = ViewSizing::UnitsPerPixel(units);
+        self
+    }
+
+    /// Sets how a `view_size()` is fitted to the window.
+    pub fn aspect_policy(mut self, policy: AspectPolicy) -> Self {
+        if let ViewSizing::Fixed(w, h, _) = self.camera.view_sizing {
+            self.camera.view_sizing = ViewSizing::Fixed(w, h, policy);
+        }
+        self
+    }
+
+    /// See `Camera::set_virtual_resolution()`.
+    pub fn virtual_resolution(mut self, width: f32, height: f32) -> Self {
+        self.camera.virtual_resolution = Some(math::vector2(width, height));
+        self
     }
 }
 
@@ -123,9 +1129,12 @@ where
         ctx: &mut ggez::Context,
         p: ggez::graphics::DrawParam,
     ) -> GameResult<()> {
-        let dest = camera.calculate_dest_point(MintPoint2(p.dest).into());
+        let dest = camera.calculate_dest_point(math::point2_from_mint(p.dest));
         let mut my_p = p;
-        my_p.dest = Vector2(dest).into();
+        my_p.dest = math::point2_to_mint(dest);
+        // Screen rotations are clockwise, so this turns things the
+        // opposite way to the view.
+        my_p.rotation += camera.shaken_view().1;
         self.draw(ctx, my_p)
     }
 
@@ -133,23 +1142,326 @@ where
         &self,
         camera: &Camera,
         ctx: &mut ggez::Context,
-        dest: Vec2,
+        dest: Point2,
         rotation: f32,
     ) -> GameResult<()> {
         let dest = camera.calculate_dest_point(dest);
-        let mut draw_param = ggez::graphics::DrawParam::default();
-        draw_param.dest = Vector2(dest).into();
-        draw_param.rotation = rotation;
+        let draw_param = ggez::graphics::DrawParam {
+            dest: math::point2_to_mint(dest),
+            rotation: rotation + camera.shaken_view().1,
+            ..Default::default()
+        };
         self.draw(ctx, draw_param)
     }
+
+    /// Draws the whole thing in one go with the camera's transform
+    /// applied, rather than moving its `dest` about.  Meant for
+    /// `SpriteBatch`es and `Mesh`es built in world coordinates: their
+    /// contents get transformed on the GPU, so thousands of sprites
+    /// cost no more than one.  `p` is in world coordinates too; like
+    /// with `Camera::apply()`, sprites come out upside down unless
+    /// they're added with a negative Y scale.
+    ///
+    /// ```rust,ignore
+    /// let mut batch = SpriteBatch::new(image);
+    /// for tile in &self.tiles {
+    ///     batch.add(DrawParam::new().dest(tile.pos).scale([TILE_SCALE, -TILE_SCALE]));
+    /// }
+    /// batch.draw_batch_camera(&self.camera, ctx, DrawParam::default())?;
+    /// ```
+    fn draw_batch_camera(
+        &self,
+        camera: &Camera,
+        ctx: &mut ggez::Context,
+        p: ggez::graphics::DrawParam,
+    ) -> GameResult<()> {
+        camera.apply(ctx)?;
+        let result = self.draw(ctx, p);
+        Camera::restore(ctx)?;
+        result
+    }
 }
 
 impl<T> CameraDraw for T where T: graphics::Drawable {}
 
+/// A background (or foreground) that scrolls at a different speed
+/// to the world, optionally tiled to fill the screen.
+///
+/// The factor says how much it moves with the camera: 1 moves with
+/// the world, 0 stays still on screen, and anything in between looks
+/// further away.  Draw layers from the furthest back to the front.
+/// Tiling doesn't take the camera's rotation into account.
+#[derive(Debug, Clone)]
+pub struct ParallaxLayer<D> {
+    drawable: D,
+    factor: Vector2,
+    position: Point2,
+    repeat_x: bool,
+    repeat_y: bool,
+}
+
+impl<D> ParallaxLayer<D> {
+    pub fn new(drawable: D, factor: f32) -> Self {
+        ParallaxLayer {
+            drawable,
+            factor: math::vector2(factor, factor),
+            position: math::point2(0.0, 0.0),
+            repeat_x: false,
+            repeat_y: false,
+        }
+    }
+
+    /// Uses different factors horizontally and vertically.
+    pub fn with_factors(mut self, x: f32, y: f32) -> Self {
+        self.factor = math::vector2(x, y);
+        self
+    }
+
+    /// Sets where the layer is drawn when the camera is at the
+    /// origin, in world coordinates.
+    pub fn at(mut self, position: Point2) -> Self {
+        self.position = position;
+        self
+    }
+
+    /// Tiles the layer horizontally and/or vertically to cover the
+    /// whole screen.
+    pub fn repeat(mut self, x: bool, y: bool) -> Self {
+        self.repeat_x = x;
+        self.repeat_y = y;
+        self
+    }
+
+    pub fn drawable(&self) -> &D {
+        &self.drawable
+    }
+
+    pub fn drawable_mut(&mut self) -> &mut D {
+        &mut self.drawable
+    }
+
+    /// Where the layer's origin lands on screen.
+    pub fn screen_position(&self, camera: &Camera) -> Point2 {
+        // Shift the layer along with the camera by whatever fraction
+        // of its motion it shouldn't appear to make.
+        let center = camera.location();
+        let world = math::point2(
+            self.position.x + center.x * (1.0 - self.factor.x),
+            self.position.y + center.y * (1.0 - self.factor.y),
+        );
+        camera.calculate_dest_point(world)
+    }
+}
+
+impl<D> ParallaxLayer<D>
+where
+    D: graphics::Drawable,
+{
+    pub fn draw(&self, ctx: &mut ggez::Context, camera: &Camera) -> GameResult<()> {
+        let origin = self.screen_position(camera);
+        let size = self.drawable.dimensions(ctx);
+        let tiles = |start: f32, tile: Option<f32>, screen: f32| match tile {
+            Some(tile) if tile > 0.0 => {
+                let first = start.rem_euclid(tile) - tile;
+                let count = ((screen - first) / tile).ceil() as usize;
+                (0..count).map(|i| first + i as f32 * tile).collect()
+            }
+            _ => vec![start],
+        };
+        let xs = tiles(
+            origin.x,
+            size.filter(|_| self.repeat_x).map(|r| r.w),
+            camera.screen_size.x,
+        );
+        let ys = tiles(
+            origin.y,
+            size.filter(|_| self.repeat_y).map(|r| r.h),
+            camera.screen_size.y,
+        );
+        let rotation = camera.shaken_view().1;
+        for y in &ys {
+            for x in &xs {
+                let param = graphics::DrawParam {
+                    dest: math::point2_to_mint(math::point2(*x, *y)),
+                    rotation,
+                    ..Default::default()
+                };
+                self.drawable.draw(ctx, param)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A camera and the part of the window it's drawn to.  Create the
+/// camera with the viewport's size as its screen size.
+pub struct Viewport {
+    pub camera: Camera,
+    rect: Rect,
+    canvas: Option<Canvas>,
+}
+
+impl Viewport {
+    pub fn new(camera: Camera, rect: Rect) -> Self {
+        Viewport {
+            camera,
+            rect,
+            canvas: None,
+        }
+    }
+
+    /// The part of the window this viewport covers, in pixels.
+    pub fn rect(&self) -> Rect {
+        self.rect
+    }
+
+    pub fn set_rect(&mut self, rect: Rect) {
+        self.rect = rect;
+        self.canvas = None;
+    }
+
+    /// Translates a point in window coordinates to world
+    /// coordinates as this viewport's camera sees them, or `None`
+    /// if it's outside the viewport.
+    pub fn screen_to_world_coords(&self, from: (i32, i32)) -> Option<Point2> {
+        let (x, y) = (from.0 as f32, from.1 as f32);
+        if !self.rect.contains(ggez::mint::Point2 { x, y }) {
+            return None;
+        }
+        let local = ((x - self.rect.x) as i32, (y - self.rect.y) as i32);
+        Some(self.camera.screen_to_world_coords(local))
+    }
+
+    /// Where the mouse cursor is in this viewport's world, or `None`
+    /// if it's outside the viewport.
+    pub fn mouse_world_position<Axes, Buttons>(
+        &self,
+        input: &InputState<Axes, Buttons>,
+    ) -> Option<Point2>
+    where
+        Axes: Hash + Eq + Clone,
+        Buttons: Hash + Eq + Clone,
+    {
+        let p = input.get_mouse_position();
+        if !self.rect.contains(math::point2_to_mint(p)) {
+            return None;
+        }
+        Some(
+            self.camera
+                .screen_to_world(p.x - self.rect.x, p.y - self.rect.y),
+        )
+    }
+
+    /// Draws `draw_scene` into the viewport.  While it runs, screen
+    /// coordinates are the viewport's, with (0, 0) at its top-left.
+    ///
+    /// `target` is the canvas the viewport ends up on, `None` for the
+    /// screen.  ggez can't tell us which canvas is active, so if
+    /// you're drawing into one, like `PostFx::canvas()`, pass it here
+    /// to have it set again afterwards.
+    pub fn draw<F>(
+        &mut self,
+        ctx: &mut Context,
+        target: Option<&Canvas>,
+        draw_scene: F,
+    ) -> GameResult
+    where
+        F: FnOnce(&mut Context, &Camera) -> GameResult,
+    {
+        let (w, h) = (self.rect.w as u16, self.rect.h as u16);
+        let stale = self
+            .canvas
+            .as_ref()
+            .map_or(true, |c| c.image().width() != w || c.image().height() != h);
+        if stale {
+            self.canvas = Some(Canvas::new(ctx, w, h, NumSamples::One)?);
+        }
+        let canvas = self.canvas.as_ref().expect("canvas was just created");
+        let screen = graphics::screen_coordinates(ctx);
+        graphics::set_canvas(ctx, Some(canvas));
+        graphics::set_screen_coordinates(ctx, Rect::new(0.0, 0.0, self.rect.w, self.rect.h))?;
+        graphics::clear(ctx, graphics::Color::new(0.0, 0.0, 0.0, 0.0));
+        let result = draw_scene(ctx, &self.camera);
+        graphics::set_canvas(ctx, target);
+        graphics::set_screen_coordinates(ctx, screen)?;
+        result?;
+        let dest = ggez::mint::Point2 {
+            x: self.rect.x,
+            y: self.rect.y,
+        };
+        graphics::draw(ctx, canvas, graphics::DrawParam::default().dest(dest))
+    }
+}
+
+/// A set of viewports drawn together, for split-screen.
+#[derive(Default)]
+pub struct SplitScreen {
+    viewports: Vec<Viewport>,
+}
+
+impl SplitScreen {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a viewport and returns its index.
+    pub fn add(&mut self, viewport: Viewport) -> usize {
+        self.viewports.push(viewport);
+        self.viewports.len() - 1
+    }
+
+    pub fn viewport(&self, index: usize) -> Option<&Viewport> {
+        self.viewports.get(index)
+    }
+
+    pub fn viewport_mut(&mut self, index: usize) -> Option<&mut Viewport> {
+        self.viewports.get_mut(index)
+    }
+
+    pub fn viewports(&self) -> &[Viewport] {
+        &self.viewports
+    }
+
+    /// Returns the index of the viewport containing the given point
+    /// in window coordinates, if any.  Later viewports are on top.
+    pub fn viewport_at(&self, point: (i32, i32)) -> Option<usize> {
+        let p = ggez::mint::Point2 {
+            x: point.0 as f32,
+            y: point.1 as f32,
+        };
+        self.viewports.iter().rposition(|v| v.rect.contains(p))
+    }
+
+    /// Updates every viewport's camera.
+    pub fn update(&mut self, dt: f32) {
+        for viewport in self.viewports.iter_mut() {
+            viewport.camera.update(dt);
+        }
+    }
+
+    /// Draws the scene once per viewport onto `target`, as with
+    /// `Viewport::draw()`.  `draw_scene` gets the viewport's camera
+    /// and index.
+    pub fn draw<F>(
+        &mut self,
+        ctx: &mut Context,
+        target: Option<&Canvas>,
+        mut draw_scene: F,
+    ) -> GameResult
+    where
+        F: FnMut(&mut Context, &Camera, usize) -> GameResult,
+    {
+        for (i, viewport) in self.viewports.iter_mut().enumerate() {
+            viewport.draw(ctx, target, |ctx, camera| draw_scene(ctx, camera, i))?;
+        }
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use nalgebra_glm::Vec2;
+    use crate::math::point2;
 
     #[test]
     fn test_coord_round_trip() {
@@ -157,32 +1469,434 @@ mod tests {
         let p1 = (200, 300);
         {
             let p1_world = c.screen_to_world_coords(p1);
-            assert_eq!(p1_world, Vec2::new(-7.5, -3.75));
-            let p1_screen = c.world_to_screen_coords(p1_world);
+            assert_eq!(p1_world, point2(-7.5, -3.75));
+            let p1_screen = c.world_to_screen_coords_i32(p1_world);
             assert_eq!(p1, p1_screen);
         }
 
-        let p2 = Vec2::new(20.0, 10.0);
+        let p2 = point2(20.0, 10.0);
         {
-            let p2_screen = c.world_to_screen_coords(p2);
+            let p2_screen = c.world_to_screen_coords_i32(p2);
             assert_eq!(p2_screen, (640, 80));
             let p2_world = c.screen_to_world_coords(p2_screen);
             assert_eq!(p2_world, p2);
         }
 
-        c.move_to(Vec2::new(5.0, 5.0));
+        c.move_to(point2(5.0, 5.0));
 
         {
             let p1_world = c.screen_to_world_coords(p1);
-            assert_eq!(p1_world, Vec2::new(-2.5, 1.25));
-            let p1_screen = c.world_to_screen_coords(p1_world);
+            assert_eq!(p1_world, point2(-2.5, 1.25));
+            let p1_screen = c.world_to_screen_coords_i32(p1_world);
             assert_eq!(p1, p1_screen);
         }
         {
-            let p2_screen = c.world_to_screen_coords(p2);
+            let p2_screen = c.world_to_screen_coords_i32(p2);
             assert_eq!(p2_screen, (560, 160));
             let p2_world = c.screen_to_world_coords(p2_screen);
             assert_eq!(p2_world, p2);
         }
     }
+
+    #[test]
+    fn test_sub_pixel_coords() {
+        // 16 pixels per unit.
+        let mut c = Camera::new(640, 480, 40.0, 30.0);
+        c.move_to(point2(0.01, 0.0));
+        assert_eq!(
+            c.world_to_screen_coords(point2(0.0, 0.0)),
+            point2(319.84, 240.0)
+        );
+        assert_eq!(c.world_to_screen_coords_i32(point2(0.0, 0.0)), (320, 240));
+        c.move_to(point2(-0.05, 0.0));
+        assert_eq!(
+            c.world_to_screen_coords(point2(0.0, 0.0)),
+            point2(320.8, 240.0)
+        );
+        assert_eq!(c.world_to_screen_coords_i32(point2(0.0, 0.0)), (321, 240));
+    }
+
+    #[test]
+    fn test_mouse_world_position() {
+        let mut input: InputState<(), ()> = InputState::new();
+        input.update_mouse_motion(200.5, 300.0, 0.0, 0.0);
+        let mut c = Camera::new(640, 480, 40.0, 30.0);
+        c.move_to(point2(5.0, 5.0));
+        c.set_zoom(2.0);
+        c.set_rotation(0.5);
+        let p = c.mouse_world_position(&input);
+        let back = c.world_to_screen_coords(p);
+        assert!((back.x - 200.5).abs() < 0.001);
+        assert!((back.y - 300.0).abs() < 0.001);
+
+        let v = Viewport::new(
+            Camera::new(320, 480, 20.0, 30.0),
+            Rect::new(320.0, 0.0, 320.0, 480.0),
+        );
+        assert_eq!(v.mouse_world_position(&input), None);
+        input.update_mouse_motion(480.0, 240.0, 0.0, 0.0);
+        assert_eq!(v.mouse_world_position(&input), Some(point2(0.0, 0.0)));
+    }
+
+    #[test]
+    fn test_virtual_resolution() {
+        let mut c = Camera::new(640, 480, 40.0, 30.0);
+        c.set_virtual_resolution(320.0, 240.0);
+        assert_eq!(c.screen_viewport(), Rect::new(0.0, 0.0, 640.0, 480.0));
+
+        // Wider window: bars down the sides.
+        c.resize(1000.0, 480.0);
+        assert_eq!(c.screen_viewport(), Rect::new(180.0, 0.0, 640.0, 480.0));
+        assert_eq!(c.world_to_screen_coords_i32(point2(20.0, 15.0)), (820, 0));
+        assert_eq!(c.screen_to_world_coords((500, 240)), point2(0.0, 0.0));
+        assert_eq!(c.screen_to_world_coords((180, 480)), point2(-20.0, -15.0));
+
+        // Taller window: bars top and bottom, and everything shrinks.
+        c.resize(320.0, 480.0);
+        assert_eq!(c.screen_viewport(), Rect::new(0.0, 120.0, 320.0, 240.0));
+        assert_eq!(c.world_to_screen_coords_i32(point2(20.0, 15.0)), (320, 120));
+
+        c.clear_virtual_resolution();
+        assert_eq!(c.screen_viewport(), Rect::new(0.0, 0.0, 320.0, 480.0));
+    }
+
+    #[test]
+    fn test_camera_builder() {
+        let expand = CameraBuilder::new(800, 400)
+            .view_size(40.0, 30.0)
+            .aspect_policy(AspectPolicy::Expand)
+            .build();
+        assert_eq!(expand.view_size(), (60.0, 30.0));
+        let mut crop = CameraBuilder::new(800, 400)
+            .aspect_policy(AspectPolicy::Crop)
+            .view_size(40.0, 30.0)
+            .build();
+        assert_eq!(crop.view_size(), (40.0, 20.0));
+        crop.resize(300.0, 400.0);
+        assert_eq!(crop.view_size(), (22.5, 30.0));
+        crop.set_aspect_policy(AspectPolicy::Stretch);
+        assert_eq!(crop.view_size(), (40.0, 30.0));
+
+        let mut by_height = CameraBuilder::new(800, 400).view_height(10.0).build();
+        assert_eq!(by_height.view_size(), (20.0, 10.0));
+        by_height.resize(400.0, 400.0);
+        assert_eq!(by_height.view_size(), (10.0, 10.0));
+
+        let mut by_pixel = CameraBuilder::new(800, 400).units_per_pixel(0.5).build();
+        assert_eq!(by_pixel.view_size(), (400.0, 200.0));
+        by_pixel.resize(1000.0, 1000.0);
+        assert_eq!(by_pixel.view_size(), (500.0, 500.0));
+        // Still half a unit per pixel.
+        assert_eq!(
+            by_pixel.screen_to_world_coords((502, 500)),
+            point2(1.0, 0.0)
+        );
+
+        let stretch = CameraBuilder::new(800, 400).view_size(40.0, 30.0).build();
+        assert_eq!(stretch.view_size(), (40.0, 30.0));
+    }
+
+    #[test]
+    fn test_follow() {
+        let mut c = Camera::new(640, 480, 40.0, 30.0);
+        c.follow(point2(10.0, -10.0));
+        c.update(0.1);
+        let first = c.location();
+        assert!(first.x > 0.0 && first.x < 10.0);
+        assert!(first.y < 0.0 && first.y > -10.0);
+        // Smoothing doesn't depend on the frame rate.
+        let mut c2 = Camera::new(640, 480, 40.0, 30.0);
+        c2.follow(point2(10.0, -10.0));
+        c2.update(0.05);
+        c2.update(0.05);
+        assert!((c2.location().x - first.x).abs() < 0.001);
+
+        for _ in 0..100 {
+            c.update(0.1);
+        }
+        assert!((c.location().x - 10.0).abs() < 0.001);
+
+        c.set_follow_speed(f32::INFINITY);
+        c.follow(point2(0.0, 0.0));
+        c.update(0.016);
+        assert_eq!(c.location(), point2(0.0, 0.0));
+
+        c.unfollow();
+        c.move_to(point2(3.0, 3.0));
+        c.update(1.0);
+        assert_eq!(c.location(), point2(3.0, 3.0));
+    }
+
+    #[test]
+    fn test_axis_follow() {
+        let mut c = Camera::new(640, 480, 40.0, 30.0);
+        c.set_follow_speed(f32::INFINITY);
+        c.set_axis_follow(AxisFollow::RatchetPositive, AxisFollow::Locked);
+        c.follow(point2(10.0, 10.0));
+        c.update(0.1);
+        assert_eq!(c.location(), point2(10.0, 0.0));
+        c.follow(point2(5.0, 10.0));
+        c.update(0.1);
+        assert_eq!(c.location(), point2(10.0, 0.0));
+
+        c.set_axis_follow(AxisFollow::Free, AxisFollow::OnLanding);
+        c.update(0.1);
+        assert_eq!(c.location(), point2(5.0, 10.0));
+        // Jumping doesn't move the camera up...
+        c.set_grounded(false);
+        c.follow(point2(6.0, 14.0));
+        c.update(0.1);
+        assert_eq!(c.location(), point2(6.0, 10.0));
+        // ...but landing somewhere higher does.
+        c.set_grounded(true);
+        c.follow(point2(7.0, 12.0));
+        c.update(0.1);
+        assert_eq!(c.location(), point2(7.0, 12.0));
+    }
+
+    #[test]
+    fn test_dead_zone() {
+        let mut c = Camera::new(640, 480, 40.0, 30.0);
+        c.set_follow_speed(f32::INFINITY);
+        c.set_dead_zone(4.0, 2.0);
+        c.follow(point2(1.5, -0.5));
+        c.update(0.1);
+        assert_eq!(c.location(), point2(0.0, 0.0));
+
+        // Only the axis that left the window moves.
+        c.follow(point2(5.0, 0.5));
+        c.update(0.1);
+        assert_eq!(c.location(), point2(3.0, 0.0));
+        c.follow(point2(5.0, -3.0));
+        c.update(0.1);
+        assert_eq!(c.location(), point2(3.0, -2.0));
+    }
+
+    #[test]
+    fn test_bounds() {
+        let mut c = Camera::new(640, 480, 40.0, 30.0);
+        c.set_bounds(Rect::new(-50.0, -20.0, 100.0, 40.0));
+        c.move_to(point2(100.0, 100.0));
+        assert_eq!(c.location(), point2(30.0, 5.0));
+        c.move_by(math::vector2(-200.0, -200.0));
+        assert_eq!(c.location(), point2(-30.0, -5.0));
+
+        // Narrower than the view, so it stays centered horizontally.
+        c.set_bounds(Rect::new(0.0, 0.0, 20.0, 100.0));
+        assert_eq!(c.location(), point2(10.0, 15.0));
+
+        c.clear_bounds();
+        c.move_to(point2(100.0, 100.0));
+        assert_eq!(c.location(), point2(100.0, 100.0));
+    }
+
+    #[test]
+    fn test_shake() {
+        let mut c = Camera::new(640, 480, 40.0, 30.0);
+        let origin = c.world_to_screen_coords(point2(0.0, 0.0));
+        c.add_trauma(0.5);
+        c.add_trauma(0.8);
+        assert_eq!(c.trauma(), 1.0);
+        let mut moved = false;
+        for _ in 0..10 {
+            c.update(0.05);
+            moved |= c.world_to_screen_coords(point2(0.0, 0.0)) != origin;
+            // The logical location stays put.
+            assert_eq!(c.location(), point2(0.0, 0.0));
+        }
+        assert!(moved);
+        assert!((c.trauma() - 0.5).abs() < 0.001);
+
+        c.update(1.0);
+        assert_eq!(c.trauma(), 0.0);
+        assert_eq!(c.world_to_screen_coords(point2(0.0, 0.0)), origin);
+    }
+
+    #[test]
+    fn test_parallax() {
+        let mut c = Camera::new(640, 480, 40.0, 30.0);
+        let near = ParallaxLayer::new((), 1.0);
+        let far = ParallaxLayer::new((), 0.5).at(point2(2.0, 0.0));
+        let sky = ParallaxLayer::new((), 0.0);
+        assert_eq!(near.screen_position(&c), point2(320.0, 240.0));
+        assert_eq!(far.screen_position(&c), point2(352.0, 240.0));
+
+        c.move_to(point2(10.0, 0.0));
+        assert_eq!(near.screen_position(&c), point2(160.0, 240.0));
+        assert_eq!(far.screen_position(&c), point2(272.0, 240.0));
+        assert_eq!(sky.screen_position(&c), point2(320.0, 240.0));
+    }
+
+    #[test]
+    fn test_split_screen() {
+        let mut split = SplitScreen::new();
+        let left = Viewport::new(
+            Camera::new(320, 480, 20.0, 30.0),
+            Rect::new(0.0, 0.0, 320.0, 480.0),
+        );
+        let mut right = Viewport::new(
+            Camera::new(320, 480, 20.0, 30.0),
+            Rect::new(320.0, 0.0, 320.0, 480.0),
+        );
+        right.camera.move_to(point2(100.0, 0.0));
+        assert_eq!(split.add(left), 0);
+        assert_eq!(split.add(right), 1);
+
+        assert_eq!(split.viewport_at((100, 100)), Some(0));
+        assert_eq!(split.viewport_at((400, 100)), Some(1));
+        assert_eq!(split.viewport_at((700, 100)), None);
+
+        let right = split.viewport(1).unwrap();
+        assert_eq!(
+            right.screen_to_world_coords((480, 240)),
+            Some(point2(100.0, 0.0))
+        );
+        assert_eq!(right.screen_to_world_coords((100, 240)), None);
+    }
+
+    #[test]
+    fn test_transform() {
+        let mut c = Camera::new(640, 480, 40.0, 30.0);
+        c.move_to(point2(5.0, 5.0));
+        c.set_rotation(0.5);
+        let m = c.transform();
+        for p in &[point2(0.0, 0.0), point2(20.0, 10.0), point2(-3.0, 7.5)] {
+            let x = m.x.x * p.x + m.y.x * p.y + m.w.x;
+            let y = m.x.y * p.x + m.y.y * p.y + m.w.y;
+            let s = c.world_to_screen_coords(*p);
+            assert!((x - s.x).abs() < 0.001);
+            assert!((y - s.y).abs() < 0.001);
+        }
+    }
+
+    #[test]
+    fn test_zoom() {
+        let mut c = Camera::new(640, 480, 40.0, 30.0);
+        c.set_zoom(2.0);
+        assert_eq!(c.world_to_screen_coords_i32(point2(10.0, 0.0)), (640, 240));
+        assert_eq!(c.screen_to_world_coords((640, 240)), point2(10.0, 0.0));
+    }
+
+    #[test]
+    fn test_fit_rect() {
+        let mut c = Camera::new(640, 480, 40.0, 30.0);
+        // Tall, so the height decides.
+        c.fit_rect(Rect::new(0.0, 0.0, 10.0, 50.0), 5.0);
+        assert_eq!(c.location(), point2(5.0, 25.0));
+        assert_eq!(c.zoom(), 0.5);
+        assert_eq!(c.world_to_screen_coords_i32(point2(5.0, 55.0)), (320, 0));
+
+        c.fit_rect(Rect::new(-5.0, -5.0, 10.0, 0.0), 0.0);
+        assert_eq!(c.zoom(), 4.0);
+    }
+
+    #[test]
+    fn test_camera_path() {
+        use std::cell::Cell;
+        use std::rc::Rc;
+
+        let finished = Rc::new(Cell::new(false));
+        let f = finished.clone();
+        let mut c = Camera::new(640, 480, 40.0, 30.0);
+        c.follow(point2(-100.0, -100.0));
+        c.play_path(
+            CameraPath::new()
+                .then(point2(10.0, 0.0), 2.0, 1.0, |t| t)
+                .then(point2(10.0, 10.0), 1.0, 1.0, |t| t * t)
+                .on_finish(move || f.set(true)),
+        );
+        c.update(0.5);
+        assert_eq!(c.location(), point2(5.0, 0.0));
+        assert_eq!(c.zoom(), 1.5);
+
+        c.pause_path();
+        c.update(0.5);
+        assert_eq!(c.location(), point2(5.0, 0.0));
+        c.resume_path();
+
+        // Into the second keyframe, which eases in.
+        c.update(1.0);
+        assert_eq!(c.location(), point2(10.0, 2.5));
+        assert!(!finished.get());
+        c.update(1.0);
+        assert_eq!(c.location(), point2(10.0, 10.0));
+        assert_eq!(c.zoom(), 1.0);
+        assert!(finished.get());
+        assert!(!c.is_playing_path());
+
+        // Then it goes back to following.
+        c.update(0.1);
+        assert!(c.location().x < 10.0);
+    }
+
+    #[test]
+    fn test_blend() {
+        let mut c = Camera::new(640, 480, 40.0, 30.0);
+        c.set_rotation(0.1);
+        let mut boss = Camera::new(640, 480, 40.0, 30.0);
+        boss.move_to(point2(20.0, 10.0));
+        boss.set_zoom(3.0);
+        // Shorter to turn clockwise through zero.
+        boss.set_rotation(std::f32::consts::PI * 2.0 - 0.3);
+
+        c.blend_to(&boss, 2.0, |t| t);
+        assert!(c.is_blending());
+        c.update(1.0);
+        assert_eq!(c.location(), point2(10.0, 5.0));
+        assert_eq!(c.zoom(), 2.0);
+        assert!((c.rotation() - -0.1).abs() < 0.001);
+        c.update(1.5);
+        assert!(!c.is_blending());
+        assert_eq!(c.location(), point2(20.0, 10.0));
+        assert_eq!(c.zoom(), 3.0);
+        assert_eq!(c.rotation(), boss.rotation());
+
+        // It takes priority over following.
+        c.follow(point2(0.0, 0.0));
+        c.blend_to(&Camera::new(640, 480, 40.0, 30.0), 1.0, |t| t);
+        c.update(0.5);
+        assert_eq!(c.location(), point2(10.0, 5.0));
+        c.stop_blend();
+        c.update(0.5);
+        assert!(c.location().x < 10.0);
+    }
+
+    #[test]
+    fn test_look_ahead() {
+        let mut c = Camera::new(640, 480, 40.0, 30.0);
+        c.set_follow_speed(f32::INFINITY);
+        c.set_look_ahead(0.5, 3.0, f32::INFINITY);
+        c.follow(point2(0.0, 0.0));
+        c.update(0.1);
+        assert_eq!(c.location(), point2(0.0, 0.0));
+        // Moving right at 4 units/sec leads by 2.
+        c.follow(point2(0.4, 0.0));
+        c.update(0.1);
+        assert!((c.location().x - 2.4).abs() < 0.001);
+        // Given velocities are capped too.
+        c.follow_with_velocity(point2(0.4, 0.0), math::vector2(0.0, -100.0));
+        c.update(0.1);
+        assert!((c.location().x - 0.4).abs() < 0.001);
+        assert!((c.location().y + 3.0).abs() < 0.001);
+        // And it comes back once the target stops.
+        c.follow(point2(0.4, 0.0));
+        c.update(0.1);
+        assert!((c.location().x - 0.4).abs() < 0.001);
+        assert!(c.location().y.abs() < 0.001);
+    }
+
+    #[test]
+    fn test_rotation() {
+        let mut c = Camera::new(640, 480, 40.0, 30.0);
+        c.move_to(point2(5.0, 5.0));
+        // Turning the view a quarter turn left puts things that were
+        // up from the center on the right of the screen.
+        c.set_rotation(std::f32::consts::FRAC_PI_2);
+        assert_eq!(c.world_to_screen_coords_i32(point2(5.0, 10.0)), (400, 240));
+
+        c.rotate_by(0.3);
+        let p = (200, 300);
+        let p_world = c.screen_to_world_coords(p);
+        assert_eq!(c.world_to_screen_coords_i32(p_world), p);
+    }
 }
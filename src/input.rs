@@ -5,21 +5,40 @@
 //! The goals are:
 //!
 //! * Have a layer of abstract key bindings rather than
-//! looking at concrete event types
+//!   looking at concrete event types
 //! * Use this to be able to abstract away differences
-//! between keyboards, joysticks and game controllers
-//! (rather based on Unity3D),
+//!   between keyboards, joysticks and game controllers
+//!   (rather based on Unity3D),
 //! * Do some tweening of input axes and stuff just for
-//! fun.
+//!   fun.
 //! * Take ggez's event-based input API, and present event- or
-//! state-based API so you can do whichever you want.
+//!   state-based API so you can do whichever you want.
+//!
+//! Feed key and mouse button events in through
+//! `InputState::update_input_down()` and `update_input_up()` with
+//! your `InputBinding`.  If you resolve them yourself, pass the
+//! physical input along with `update_effect_from()`, so that holding
+//! two keys bound to the same thing and letting go of one doesn't
+//! release it.
 
-// TODO: Handle mice, game pads, joysticks
+// TODO: Handle game pads, joysticks
 
-use ggez::event::KeyCode;
-use std::collections::HashMap;
+use ggez::event::winit_event::TouchPhase;
+use ggez::event::{Axis, GamepadId, KeyCode, KeyMods, MouseButton};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::hash::Hash;
 
+use crate::math::{self, Point2, Vector2};
+
+#[cfg(feature = "serialize")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "serialize")]
+use std::convert::TryFrom;
+
+pub mod recorder;
+pub mod rumble;
+pub mod touch;
+
 // Okay, but how does it actually work?
 // Basically we have to bind input events to buttons and axes.
 // Input events can be keys, mouse buttons/motion, or eventually
@@ -40,12 +59,148 @@ use std::hash::Hash;
 //
 // Easy way?  Hash map of event -> axis/button bindings.
 
+/// A physical input that can be bound to a logical axis or button.
+#[derive(Debug, Hash, Eq, PartialEq, Copy, Clone)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub enum InputType {
+    KeyEvent(KeyCode),
+    MouseButtonEvent(MouseButton),
+    /// A key pressed while holding exactly the given modifiers, like Ctrl+S.
+    KeyChord(
+        #[cfg_attr(feature = "serialize", serde(with = "mods_serde"))] KeyMods,
+        KeyCode,
+    ),
+    /// A mouse button pressed while holding exactly the given
+    /// modifiers, like Shift+Click.
+    MouseChord(
+        #[cfg_attr(feature = "serialize", serde(with = "mods_serde"))] KeyMods,
+        MouseButton,
+    ),
+}
+
+// KeyMods can't be serialized itself, so chords write theirs out as
+// flags, the same way binding files do.
+#[cfg(feature = "serialize")]
+mod mods_serde {
+    use super::{is_false, KeyMods};
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    #[derive(Serialize, Deserialize)]
+    struct Mods {
+        #[serde(default, skip_serializing_if = "is_false")]
+        shift: bool,
+        #[serde(default, skip_serializing_if = "is_false")]
+        ctrl: bool,
+        #[serde(default, skip_serializing_if = "is_false")]
+        alt: bool,
+        #[serde(default, skip_serializing_if = "is_false")]
+        logo: bool,
+    }
+
+    pub fn serialize<S: Serializer>(mods: &KeyMods, serializer: S) -> Result<S::Ok, S::Error> {
+        Mods {
+            shift: mods.contains(KeyMods::SHIFT),
+            ctrl: mods.contains(KeyMods::CTRL),
+            alt: mods.contains(KeyMods::ALT),
+            logo: mods.contains(KeyMods::LOGO),
+        }
+        .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<KeyMods, D::Error> {
+        let entry = Mods::deserialize(deserializer)?;
+        let mut mods = KeyMods::NONE;
+        mods.set(KeyMods::SHIFT, entry.shift);
+        mods.set(KeyMods::CTRL, entry.ctrl);
+        mods.set(KeyMods::ALT, entry.alt);
+        mods.set(KeyMods::LOGO, entry.logo);
+        Ok(mods)
+    }
+}
+
+impl InputType {
+    /// The chord of this input with the given modifiers, or the plain
+    /// input if there aren't any.
+    pub fn with_mods(self, mods: KeyMods) -> Self {
+        match self.without_mods() {
+            InputType::KeyEvent(key) if !mods.is_empty() => InputType::KeyChord(mods, key),
+            InputType::MouseButtonEvent(button) if !mods.is_empty() => {
+                InputType::MouseChord(mods, button)
+            }
+            plain => plain,
+        }
+    }
+
+    /// The plain input with the modifiers of a chord removed.
+    pub fn without_mods(self) -> Self {
+        match self {
+            InputType::KeyChord(_, key) => InputType::KeyEvent(key),
+            InputType::MouseChord(_, button) => InputType::MouseButtonEvent(button),
+            plain => plain,
+        }
+    }
+
+    // Orders inputs for display: keys before mouse buttons, plain
+    // inputs before chords.
+    fn display_order(self) -> (u8, u8, u32) {
+        let mods = self.mods().bits();
+        match self.without_mods() {
+            InputType::KeyEvent(key) => (0, mods, key as u32),
+            InputType::MouseButtonEvent(button) => {
+                let n = match button {
+                    MouseButton::Left => 0,
+                    MouseButton::Right => 1,
+                    MouseButton::Middle => 2,
+                    MouseButton::Other(n) => 3 + u32::from(n),
+                };
+                (1, mods, n)
+            }
+            _ => unreachable!("without_mods() returns a plain input"),
+        }
+    }
+
+    /// The modifiers a chord needs.
+    pub fn mods(self) -> KeyMods {
+        match self {
+            InputType::KeyChord(mods, _) | InputType::MouseChord(mods, _) => mods,
+            _ => KeyMods::NONE,
+        }
+    }
+}
+
+/// A direction of mouse motion that can drive a logical axis.
 #[derive(Debug, Hash, Eq, PartialEq, Copy, Clone)]
-enum InputType {
-    KeyEvent(KeyCode), // MouseButtonEvent,
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub enum MouseAxis {
+    X,
+    Y,
+}
+
+/// The modifier a modifier key sets, if it is one.
+fn key_mods(keycode: KeyCode) -> KeyMods {
+    match keycode {
+        KeyCode::LShift | KeyCode::RShift => KeyMods::SHIFT,
+        KeyCode::LControl | KeyCode::RControl => KeyMods::CTRL,
+        KeyCode::LAlt | KeyCode::RAlt => KeyMods::ALT,
+        KeyCode::LWin | KeyCode::RWin => KeyMods::LOGO,
+        _ => KeyMods::NONE,
+    }
+}
+
+/// Whether an analog axis bound as a button should be pressed, given
+/// whether it was already.
+fn analog_pressed(was_pressed: bool, value: f32, threshold: f32, hysteresis: f32) -> bool {
+    let value = if threshold < 0.0 { -value } else { value };
+    let threshold = threshold.abs();
+    if was_pressed {
+        value > threshold - hysteresis
+    } else {
+        value >= threshold
+    }
 }
 
 #[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub enum InputEffect<Axes, Buttons>
 where
     Axes: Eq + Hash + Clone,
@@ -55,7 +210,63 @@ where
     Button(Buttons),
 }
 
-#[derive(Debug, Copy, Clone)]
+/// How a logical axis responds to input, much like Unity's axis
+/// settings.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize), serde(default))]
+pub struct AxisConfig {
+    /// Speed in units per second that the axis
+    /// moves towards the target value.
+    pub acceleration: f32,
+    /// Speed in units per second that the axis will
+    /// fall back toward 0 if the input stops.
+    pub gravity: f32,
+    /// If true, pressing the opposite direction makes the axis
+    /// jump to 0 first instead of sliding through it.
+    pub snap: bool,
+    /// Exponent of the response curve applied by `get_axis()`.  1 is
+    /// linear, higher values give finer control near the center.
+    pub sensitivity: f32,
+    /// How much of its previous value a mouse-driven axis keeps each
+    /// 60th of a second, in [0, 1).  0 follows the mouse exactly,
+    /// higher values smooth out jitter.
+    pub smoothing: f32,
+    /// Flips the axis, for invert-Y controls.
+    pub invert: bool,
+    /// The range `get_axis()` maps its output to, for example
+    /// `(0.0, 1.0)` for trigger-style axes.  `(-1.0, 1.0)` by default.
+    pub range: (f32, f32),
+}
+
+impl Default for AxisConfig {
+    fn default() -> Self {
+        AxisConfig {
+            acceleration: 4.0,
+            gravity: 3.0,
+            snap: false,
+            sensitivity: 1.0,
+            smoothing: 0.0,
+            invert: false,
+            range: (-1.0, 1.0),
+        }
+    }
+}
+
+impl AxisConfig {
+    /// Applies the response curve, inversion and range to a raw
+    /// position in [-1, 1].
+    fn respond(&self, position: f32) -> f32 {
+        let curved = position.signum() * position.abs().powf(self.sensitivity);
+        let value = if self.invert { -curved } else { curved };
+        match self.range {
+            // Skip the math in the usual case so values come out exact.
+            (min, max) if min == -1.0 && max == 1.0 => value,
+            (min, max) => min + (value + 1.0) * 0.5 * (max - min),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
 struct AxisState {
     // Where the axis currently is, in [-1, 1]
     position: f32,
@@ -63,22 +274,27 @@ struct AxisState {
     // values are -1, 0, +1
     // (or a continuous range for analog devices I guess)
     direction: f32,
-    // Speed in units per second that the axis
-    // moves towards the target value.
-    acceleration: f32,
-    // Speed in units per second that the axis will
-    // fall back toward 0 if the input stops.
-    gravity: f32,
+    config: AxisConfig,
+    // Mouse motion this frame, already scaled by sensitivity.
+    // Mouse deltas are impulses rather than held states, so this
+    // gets used up every update() and the axis decays back to 0
+    // when the mouse stops.
+    impulse: f32,
+    mouse_driven: bool,
+    // Which inputs are holding the axis each way, so letting go
+    // of one of several keys bound to the same direction doesn't
+    // stop it.  None is anything fed in without a source, like
+    // update_axis_start(), which can only hold it once.
+    positive_held: HashSet<Option<InputType>>,
+    negative_held: HashSet<Option<InputType>>,
 }
 
-impl Default for AxisState {
-    fn default() -> Self {
-        AxisState {
-            position: 0.0,
-            direction: 0.0,
-            acceleration: 4.0,
-            gravity: 3.0,
+impl AxisState {
+    fn move_towards(&mut self, direction: f32) {
+        if self.config.snap && self.position * direction < 0.0 {
+            self.position = 0.0;
         }
+        self.direction = direction;
     }
 }
 
@@ -86,11 +302,146 @@ impl Default for AxisState {
 struct ButtonState {
     pressed: bool,
     pressed_last_frame: bool,
+    // Seconds since the button was last pressed, for input
+    // buffering.  None if it hasn't been or the press was consumed.
+    since_pressed: Option<f32>,
+    // How long the button has been held down, as of this frame
+    // and the one before.
+    held_for: f32,
+    held_for_last_frame: f32,
+    // Auto-repeat delay and interval, if the button repeats, and
+    // whether it repeated this frame.
+    repeat: Option<(f32, f32)>,
+    repeated: bool,
+    // How long presses are ignored for after one counts, how much
+    // of that is left, and whether the current press was ignored.
+    cooldown: f32,
+    cooldown_left: f32,
+    blocked: bool,
+}
+
+impl ButtonState {
+    // How many times the button has auto-repeated after being held
+    // for `held_for` seconds.
+    fn repeat_count(&self, held_for: f32) -> u32 {
+        match self.repeat {
+            Some((delay, interval)) if held_for >= delay => {
+                if interval > 0.0 {
+                    ((held_for - delay) / interval) as u32 + 1
+                } else {
+                    1
+                }
+            }
+            _ => 0,
+        }
+    }
+}
+
+/// Which gamepads are plugged into which player slots.  Generic over
+/// the device id so it can be tested without real gamepads.
+#[derive(Debug, Clone)]
+struct DeviceSlots<D> {
+    slots: Vec<Option<D>>,
+}
+
+impl<D> DeviceSlots<D>
+where
+    D: Eq + Copy,
+{
+    fn new(count: usize) -> Self {
+        DeviceSlots {
+            slots: vec![None; count],
+        }
+    }
+
+    fn resize(&mut self, count: usize) {
+        self.slots.resize(count, None);
+    }
+
+    fn player(&self, device: D) -> Option<usize> {
+        self.slots.iter().position(|s| *s == Some(device))
+    }
+
+    fn device(&self, player: usize) -> Option<D> {
+        self.slots.get(player).cloned().flatten()
+    }
+
+    /// Puts the device in the given slot, taking it out of any other
+    /// slot it was in.  Returns the device that was there before.
+    fn assign(&mut self, device: D, player: usize) -> Option<D> {
+        if player >= self.slots.len() {
+            self.slots.resize(player + 1, None);
+        }
+        self.remove(device);
+        let old = self.slots[player].take();
+        self.slots[player] = Some(device);
+        old
+    }
+
+    /// Puts the device in the first free slot, if it isn't in one
+    /// already.  Returns the slot and whether it's newly assigned.
+    fn connect(&mut self, device: D) -> Option<(usize, bool)> {
+        if let Some(player) = self.player(device) {
+            return Some((player, false));
+        }
+        let player = self.slots.iter().position(Option::is_none)?;
+        self.slots[player] = Some(device);
+        Some((player, true))
+    }
+
+    fn remove(&mut self, device: D) -> Option<usize> {
+        let player = self.player(device)?;
+        self.slots[player] = None;
+        Some(player)
+    }
+}
+
+/// A physical input device, as far as telling players apart and
+/// picking which button prompts to show goes.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum InputDevice {
+    Keyboard,
+    Mouse,
+    Touch,
+    Gamepad(GamepadId),
+}
+
+impl InputType {
+    /// The device this input comes from.
+    pub fn device(self) -> InputDevice {
+        match self {
+            InputType::KeyEvent(_) | InputType::KeyChord(_, _) => InputDevice::Keyboard,
+            InputType::MouseButtonEvent(_) | InputType::MouseChord(_, _) => InputDevice::Mouse,
+        }
+    }
+}
+
+/// Something that happened to a gamepad's player assignment.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum GamepadEvent {
+    /// The gamepad was assigned to the given player.
+    Connected(GamepadId, usize),
+    /// The gamepad was disconnected from the given player.
+    Disconnected(GamepadId, usize),
 }
 
 /// A struct that contains a mapping from physical input events
-/// (currently just `KeyCode`s) to whatever your logical Axis/Button
-/// types are.
+/// (keys, mouse buttons and chords of those with modifiers) to
+/// whatever your logical Axis/Button types are.
+///
+/// With the `serialize` feature enabled this can be serialized, so
+/// players' custom controls can be saved and loaded.  It's written as
+/// a list of bindings, so formats like TOML that only allow string
+/// keys work too.
+#[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "serialize",
+    derive(Serialize, Deserialize),
+    serde(
+        into = "BindingList<Axes, Buttons>",
+        try_from = "BindingList<Axes, Buttons>"
+    )
+)]
 pub struct InputBinding<Axes, Buttons>
 where
     Axes: Hash + Eq + Clone,
@@ -100,6 +451,15 @@ where
     // instead of BTreeMap. ♥?
     // Binding of keys to input values.
     bindings: HashMap<InputType, InputEffect<Axes, Buttons>>,
+    // How each axis should feel, if not the default.
+    axis_configs: HashMap<Axes, AxisConfig>,
+    // Mouse motion driving axes, and how many axis units a pixel is.
+    mouse_axes: HashMap<MouseAxis, (Axes, f32)>,
+    // Named pairs of axes read together as a 2D stick.
+    sticks: HashMap<String, (Axes, Axes)>,
+    // Analog gamepad axes acting as buttons, with their threshold
+    // and hysteresis.
+    gamepad_axes: HashMap<Axis, (Buttons, f32, f32)>,
 }
 
 impl<Axes, Buttons> InputBinding<Axes, Buttons>
@@ -110,6 +470,10 @@ where
     pub fn new() -> Self {
         InputBinding {
             bindings: HashMap::new(),
+            axis_configs: HashMap::new(),
+            mouse_axes: HashMap::new(),
+            sticks: HashMap::new(),
+            gamepad_axes: HashMap::new(),
         }
     }
 
@@ -133,12 +497,508 @@ where
         self
     }
 
+    /// Sets how the given logical axis responds to input.  Use
+    /// `InputState::apply_axis_configs()` to put it into effect.
+    pub fn configure_axis(mut self, axis: Axes, config: AxisConfig) -> Self {
+        self.axis_configs.insert(axis, config);
+        self
+    }
+
+    /// Makes relative mouse motion drive a logical axis, for
+    /// mouse-look style controls.  `sensitivity` is how far one pixel
+    /// of motion per frame moves the axis; set `AxisConfig::smoothing`
+    /// to smooth it out.  Feed it motion with
+    /// `InputState::update_mouse_motion_with()`.
+    pub fn bind_mouse_motion_to_axis(
+        mut self,
+        motion: MouseAxis,
+        axis: Axes,
+        sensitivity: f32,
+    ) -> Self {
+        self.mouse_axes.insert(motion, (axis, sensitivity));
+        self
+    }
+
+    /// Groups two logical axes into a named stick, so they can be
+    /// read together with `InputState::get_stick()`.
+    pub fn bind_axes_to_stick(mut self, stick: &str, x_axis: Axes, y_axis: Axes) -> Self {
+        self.sticks.insert(stick.to_owned(), (x_axis, y_axis));
+        self
+    }
+
+    /// Returns the X and Y axes of a named stick.
+    pub fn stick(&self, stick: &str) -> Option<(Axes, Axes)> {
+        self.sticks.get(stick).cloned()
+    }
+
+    /// Makes an analog gamepad axis, such as a trigger, act as a
+    /// logical button.  It's pressed once the axis reaches
+    /// `threshold`, and released once it drops `hysteresis` below
+    /// that, so it doesn't flicker when held right at the threshold.
+    /// A negative threshold presses when the axis goes below it
+    /// instead, for the other half of a stick.  Feed it axis values
+    /// with `InputState::update_gamepad_axis()`.
+    pub fn bind_gamepad_axis_to_button(
+        mut self,
+        axis: Axis,
+        button: Buttons,
+        threshold: f32,
+        hysteresis: f32,
+    ) -> Self {
+        self.gamepad_axes
+            .insert(axis, (button, threshold, hysteresis));
+        self
+    }
+
+    /// Flips the given logical axis.
+    pub fn invert_axis(mut self, axis: Axes) -> Self {
+        self.axis_configs.entry(axis).or_default().invert = true;
+        self
+    }
+
+    /// Maps the given logical axis' output to the range [min, max]
+    /// instead of [-1, 1].
+    pub fn remap_axis(mut self, axis: Axes, min: f32, max: f32) -> Self {
+        self.axis_configs.entry(axis).or_default().range = (min, max);
+        self
+    }
+
+    /// Returns the configuration of an axis.
+    pub fn axis_config(&self, axis: &Axes) -> AxisConfig {
+        self.axis_configs.get(axis).cloned().unwrap_or_default()
+    }
+
+    /// Adds a binding connecting any physical input to the given
+    /// logical axis.
+    pub fn bind_input_to_axis(mut self, input: InputType, axis: Axes, positive: bool) -> Self {
+        self.bindings
+            .insert(input, InputEffect::Axis(axis, positive));
+        self
+    }
+
+    /// Adds a binding connecting any physical input to the given
+    /// logical button, such as
+    /// `InputType::KeyChord(KeyMods::CTRL, KeyCode::S)`.
+    pub fn bind_input_to_button(mut self, input: InputType, button: Buttons) -> Self {
+        self.bindings.insert(input, InputEffect::Button(button));
+        self
+    }
+
     /// Takes an physical input type and turns it into a logical input type (keycode -> axis/button).
     pub fn resolve(&self, keycode: KeyCode) -> Option<InputEffect<Axes, Buttons>> {
-        self.bindings.get(&InputType::KeyEvent(keycode)).cloned()
+        self.resolve_input(InputType::KeyEvent(keycode))
+    }
+
+    /// Like `resolve()`, but for any kind of physical input.
+    pub fn resolve_input(&self, input: InputType) -> Option<InputEffect<Axes, Buttons>> {
+        self.bindings.get(&input).cloned()
+    }
+
+    /// Returns all the physical inputs bound to the given logical
+    /// button or axis direction, for showing hints like "Jump: Space"
+    /// in menus.  Keys come first, then mouse buttons, with plain
+    /// inputs before chords.
+    pub fn bindings_for(&self, action: &InputEffect<Axes, Buttons>) -> Vec<InputType> {
+        let mut inputs: Vec<InputType> = self
+            .bindings
+            .iter()
+            .filter(|(_, effect)| *effect == action)
+            .map(|(input, _)| *input)
+            .collect();
+        inputs.sort_by_key(|input| input.display_order());
+        inputs
+    }
+
+    /// Resolves a plain input pressed with the given modifiers held.
+    /// A chord bound to exactly those modifiers wins, otherwise
+    /// it falls back to the plain input.  Returns the input that
+    /// matched along with what it's bound to.
+    pub fn resolve_with_mods(
+        &self,
+        input: InputType,
+        mods: KeyMods,
+    ) -> Option<(InputType, InputEffect<Axes, Buttons>)> {
+        let chord = input.with_mods(mods);
+        let plain = input.without_mods();
+        self.resolve_input(chord)
+            .map(|effect| (chord, effect))
+            .or_else(|| self.resolve_input(plain).map(|effect| (plain, effect)))
+    }
+
+    /// Binds a physical input to a logical axis or button, returning
+    /// whatever it was bound to before.  Unlike the `bind_*` builder
+    /// methods this works on an existing binding, for changing
+    /// controls at runtime.
+    pub fn insert(
+        &mut self,
+        input: InputType,
+        effect: InputEffect<Axes, Buttons>,
+    ) -> Option<InputEffect<Axes, Buttons>> {
+        self.bindings.insert(input, effect)
+    }
+
+    /// Binds a physical input to a logical axis or button, unless
+    /// it's already bound to something else, in which case nothing
+    /// changes and the existing mapping is returned as the error.
+    /// Use this for rebinding UIs that want to ask before replacing
+    /// anything; `insert()` replaces it anyway.
+    pub fn try_bind_input(
+        &mut self,
+        input: InputType,
+        effect: InputEffect<Axes, Buttons>,
+    ) -> Result<(), InputEffect<Axes, Buttons>> {
+        match self.bindings.get(&input) {
+            Some(existing) if *existing != effect => Err(existing.clone()),
+            _ => {
+                self.bindings.insert(input, effect);
+                Ok(())
+            }
+        }
+    }
+
+    /// `try_bind_input()` for a key and an axis.
+    pub fn try_bind_key_to_axis(
+        &mut self,
+        keycode: KeyCode,
+        axis: Axes,
+        positive: bool,
+    ) -> Result<(), InputEffect<Axes, Buttons>> {
+        self.try_bind_input(
+            InputType::KeyEvent(keycode),
+            InputEffect::Axis(axis, positive),
+        )
+    }
+
+    /// `try_bind_input()` for a key and a button.
+    pub fn try_bind_key_to_button(
+        &mut self,
+        keycode: KeyCode,
+        button: Buttons,
+    ) -> Result<(), InputEffect<Axes, Buttons>> {
+        self.try_bind_input(InputType::KeyEvent(keycode), InputEffect::Button(button))
+    }
+
+    /// Returns the physical inputs this binding and `other` bind to
+    /// different actions, such as two players' keyboard layouts that
+    /// share a key, in display order.
+    pub fn conflicts_with(&self, other: &InputBinding<Axes, Buttons>) -> Vec<InputType> {
+        let mut inputs: Vec<InputType> = self
+            .bindings
+            .iter()
+            .filter(|(input, effect)| other.bindings.get(input).is_some_and(|e| e != *effect))
+            .map(|(input, _)| *input)
+            .collect();
+        inputs.sort_by_key(|input| input.display_order());
+        inputs
+    }
+}
+
+/// The serialized form of an `InputBinding`.
+#[cfg(feature = "serialize")]
+#[derive(Serialize, Deserialize)]
+struct BindingList<Axes, Buttons> {
+    bindings: Vec<BindingEntry<Axes, Buttons>>,
+    #[serde(default = "Vec::new", skip_serializing_if = "Vec::is_empty")]
+    axes: Vec<AxisConfigEntry<Axes>>,
+    #[serde(default = "Vec::new", skip_serializing_if = "Vec::is_empty")]
+    mouse_axes: Vec<MouseAxisEntry<Axes>>,
+    #[serde(default = "Vec::new", skip_serializing_if = "Vec::is_empty")]
+    sticks: Vec<StickEntry<Axes>>,
+    #[serde(default = "Vec::new", skip_serializing_if = "Vec::is_empty")]
+    gamepad_axes: Vec<GamepadAxisEntry<Buttons>>,
+}
+
+#[cfg(feature = "serialize")]
+#[derive(Serialize, Deserialize)]
+struct GamepadAxisEntry<Buttons> {
+    gamepad_axis: Axis,
+    button: Buttons,
+    threshold: f32,
+    hysteresis: f32,
+}
+
+#[cfg(feature = "serialize")]
+#[derive(Serialize, Deserialize)]
+struct StickEntry<Axes> {
+    name: String,
+    x_axis: Axes,
+    y_axis: Axes,
+}
+
+#[cfg(feature = "serialize")]
+#[derive(Serialize, Deserialize)]
+struct MouseAxisEntry<Axes> {
+    motion: MouseAxis,
+    axis: Axes,
+    sensitivity: f32,
+}
+
+#[cfg(feature = "serialize")]
+#[derive(Serialize, Deserialize)]
+struct AxisConfigEntry<Axes> {
+    axis: Axes,
+    #[serde(flatten)]
+    config: AxisConfig,
+}
+
+/// One binding, flattened out so it's easy to read and edit by hand,
+/// for example `{ key = "Left", axis = "Horz", positive = false }`
+/// or `{ key = "S", ctrl = true, button = "Save" }`.
+#[cfg(feature = "serialize")]
+#[derive(Serialize, Deserialize)]
+struct BindingEntry<Axes, Buttons> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    key: Option<KeyCode>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    mouse_button: Option<MouseButton>,
+    #[serde(default, skip_serializing_if = "is_false")]
+    shift: bool,
+    #[serde(default, skip_serializing_if = "is_false")]
+    ctrl: bool,
+    #[serde(default, skip_serializing_if = "is_false")]
+    alt: bool,
+    #[serde(default, skip_serializing_if = "is_false")]
+    logo: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    button: Option<Buttons>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    axis: Option<Axes>,
+    #[serde(default)]
+    positive: bool,
+}
+
+#[cfg(feature = "serialize")]
+fn is_false(b: &bool) -> bool {
+    !*b
+}
+
+#[cfg(feature = "serialize")]
+impl<Axes, Buttons> From<InputBinding<Axes, Buttons>> for BindingList<Axes, Buttons>
+where
+    Axes: Hash + Eq + Clone,
+    Buttons: Hash + Eq + Clone,
+{
+    fn from(binding: InputBinding<Axes, Buttons>) -> Self {
+        let bindings = binding
+            .bindings
+            .into_iter()
+            .map(|(input, effect)| {
+                let (key, mouse_button) = match input.without_mods() {
+                    InputType::KeyEvent(key) => (Some(key), None),
+                    InputType::MouseButtonEvent(button) => (None, Some(button)),
+                    _ => unreachable!("without_mods() returns a plain input"),
+                };
+                let mods = input.mods();
+                let (button, axis, positive) = match effect {
+                    InputEffect::Button(button) => (Some(button), None, false),
+                    InputEffect::Axis(axis, positive) => (None, Some(axis), positive),
+                };
+                BindingEntry {
+                    key,
+                    mouse_button,
+                    shift: mods.contains(KeyMods::SHIFT),
+                    ctrl: mods.contains(KeyMods::CTRL),
+                    alt: mods.contains(KeyMods::ALT),
+                    logo: mods.contains(KeyMods::LOGO),
+                    button,
+                    axis,
+                    positive,
+                }
+            })
+            .collect();
+        let axes = binding
+            .axis_configs
+            .into_iter()
+            .map(|(axis, config)| AxisConfigEntry { axis, config })
+            .collect();
+        let mouse_axes = binding
+            .mouse_axes
+            .into_iter()
+            .map(|(motion, (axis, sensitivity))| MouseAxisEntry {
+                motion,
+                axis,
+                sensitivity,
+            })
+            .collect();
+        let sticks = binding
+            .sticks
+            .into_iter()
+            .map(|(name, (x_axis, y_axis))| StickEntry {
+                name,
+                x_axis,
+                y_axis,
+            })
+            .collect();
+        let gamepad_axes = binding
+            .gamepad_axes
+            .into_iter()
+            .map(
+                |(gamepad_axis, (button, threshold, hysteresis))| GamepadAxisEntry {
+                    gamepad_axis,
+                    button,
+                    threshold,
+                    hysteresis,
+                },
+            )
+            .collect();
+        BindingList {
+            bindings,
+            axes,
+            mouse_axes,
+            sticks,
+            gamepad_axes,
+        }
+    }
+}
+
+#[cfg(feature = "serialize")]
+impl<Axes, Buttons> TryFrom<BindingList<Axes, Buttons>> for InputBinding<Axes, Buttons>
+where
+    Axes: Hash + Eq + Clone,
+    Buttons: Hash + Eq + Clone,
+{
+    type Error = String;
+
+    fn try_from(list: BindingList<Axes, Buttons>) -> Result<Self, String> {
+        let mut binding = InputBinding::new();
+        for entry in list.bindings {
+            let input = match (entry.key, entry.mouse_button) {
+                (Some(key), None) => InputType::KeyEvent(key),
+                (None, Some(button)) => InputType::MouseButtonEvent(button),
+                _ => return Err("binding needs exactly one of key or mouse_button".to_owned()),
+            };
+            let mut mods = KeyMods::NONE;
+            mods.set(KeyMods::SHIFT, entry.shift);
+            mods.set(KeyMods::CTRL, entry.ctrl);
+            mods.set(KeyMods::ALT, entry.alt);
+            mods.set(KeyMods::LOGO, entry.logo);
+            let input = input.with_mods(mods);
+            let effect = match (entry.button, entry.axis) {
+                (Some(button), None) => InputEffect::Button(button),
+                (None, Some(axis)) => InputEffect::Axis(axis, entry.positive),
+                _ => return Err("binding needs exactly one of button or axis".to_owned()),
+            };
+            binding.bindings.insert(input, effect);
+        }
+        for entry in list.mouse_axes {
+            binding
+                .mouse_axes
+                .insert(entry.motion, (entry.axis, entry.sensitivity));
+        }
+        for entry in list.axes {
+            binding.axis_configs.insert(entry.axis, entry.config);
+        }
+        for entry in list.sticks {
+            binding
+                .sticks
+                .insert(entry.name, (entry.x_axis, entry.y_axis));
+        }
+        for entry in list.gamepad_axes {
+            binding.gamepad_axes.insert(
+                entry.gamepad_axis,
+                (entry.button, entry.threshold, entry.hysteresis),
+            );
+        }
+        Ok(binding)
+    }
+}
+
+impl<Axes, Buttons> Default for InputBinding<Axes, Buttons>
+where
+    Axes: Hash + Eq + Clone,
+    Buttons: Hash + Eq + Clone,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A named set of bindings that can be pushed onto an `InputState`,
+/// such as one for gameplay and one for a pause menu on top of it.
+/// Only the top context resolves input, unless it's marked as
+/// pass-through, in which case input it doesn't bind falls through
+/// to the context below.
+///
+/// Input a context does bind is consumed, so contexts below don't
+/// see it.  For shared-keyboard multiplayer, where players' bindings
+/// are meant to overlap, give each player a pass-through context
+/// that doesn't consume, and mark any actions that should stay
+/// exclusive with `consume()`.
+#[derive(Debug, Clone)]
+pub struct InputContext<Axes, Buttons>
+where
+    Axes: Hash + Eq + Clone,
+    Buttons: Hash + Eq + Clone,
+{
+    name: String,
+    binding: InputBinding<Axes, Buttons>,
+    pass_through: bool,
+    consuming: bool,
+    // Actions that consume input even if the context doesn't.
+    consumed: Vec<InputEffect<Axes, Buttons>>,
+}
+
+impl<Axes, Buttons> InputContext<Axes, Buttons>
+where
+    Axes: Hash + Eq + Clone,
+    Buttons: Hash + Eq + Clone,
+{
+    pub fn new(name: &str, binding: InputBinding<Axes, Buttons>) -> Self {
+        InputContext {
+            name: name.to_owned(),
+            binding,
+            pass_through: false,
+            consuming: true,
+            consumed: Vec::new(),
+        }
+    }
+
+    /// Sets whether input this context doesn't bind falls through to
+    /// the context below it.
+    pub fn pass_through(mut self, pass_through: bool) -> Self {
+        self.pass_through = pass_through;
+        self
+    }
+
+    /// Sets whether input this context binds is kept from the
+    /// contexts below it.  On by default.
+    pub fn consuming(mut self, consuming: bool) -> Self {
+        self.consuming = consuming;
+        self
+    }
+
+    /// Marks an action as consuming the input that triggers it,
+    /// even if the context as a whole doesn't.
+    pub fn consume(mut self, action: InputEffect<Axes, Buttons>) -> Self {
+        if !self.consumed.contains(&action) {
+            self.consumed.push(action);
+        }
+        self
+    }
+
+    /// Returns whether triggering the given action keeps the input
+    /// from the contexts below.
+    pub fn consumes(&self, action: &InputEffect<Axes, Buttons>) -> bool {
+        self.consuming || self.consumed.contains(action)
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn binding(&self) -> &InputBinding<Axes, Buttons> {
+        &self.binding
+    }
+
+    pub fn binding_mut(&mut self) -> &mut InputBinding<Axes, Buttons> {
+        &mut self.binding
     }
 }
 
+/// The bound inputs one physical input matched and the actions they
+/// triggered, topmost context first.
+pub type ResolvedInputs<Axes, Buttons> = Vec<(InputType, InputEffect<Axes, Buttons>)>;
+
 #[derive(Debug)]
 pub struct InputState<Axes, Buttons>
 where
@@ -149,6 +1009,35 @@ where
     axes: HashMap<Axes, AxisState>,
     // Input states for buttons
     buttons: HashMap<Buttons, ButtonState>,
+    // Mouse state.  The deltas accumulate over a frame and
+    // get reset in update().
+    mouse_position: Point2,
+    mouse_delta: Vector2,
+    scroll_delta: Vector2,
+    mouse_buttons: HashMap<MouseButton, ButtonState>,
+    // Gamepad to player assignments, and changes to them that
+    // haven't been polled yet.
+    gamepads: DeviceSlots<GamepadId>,
+    gamepad_events: VecDeque<GamepadEvent>,
+    // The action waiting to be bound to the next physical input,
+    // if we're rebinding controls.
+    listening: Option<InputEffect<Axes, Buttons>>,
+    // Plain inputs currently held down through update_input_down(),
+    // and the bindings each one triggered.
+    held_inputs: HashMap<InputType, ResolvedInputs<Axes, Buttons>>,
+    // Stack of binding sets, the last one is on top.
+    contexts: Vec<InputContext<Axes, Buttons>>,
+    sticks: HashMap<String, (Axes, Axes)>,
+    // Gamepad axes currently pressing their bound buttons.
+    pressed_gamepad_axes: HashSet<(GamepadId, Axis)>,
+    // Touches currently on the screen, by id.
+    touches: HashMap<u64, Point2>,
+    // The most recently used device, overall and per player, and
+    // whether anything at all was pressed this frame.
+    last_device: Option<InputDevice>,
+    player_devices: HashMap<usize, InputDevice>,
+    keyboard_player: usize,
+    any_pressed: bool,
 }
 
 impl<Axes, Buttons> InputState<Axes, Buttons>
@@ -160,6 +1049,22 @@ where
         InputState {
             axes: HashMap::new(),
             buttons: HashMap::new(),
+            mouse_position: math::point2(0.0, 0.0),
+            mouse_delta: math::vector2(0.0, 0.0),
+            scroll_delta: math::vector2(0.0, 0.0),
+            mouse_buttons: HashMap::new(),
+            gamepads: DeviceSlots::new(4),
+            gamepad_events: VecDeque::new(),
+            listening: None,
+            held_inputs: HashMap::new(),
+            sticks: HashMap::new(),
+            pressed_gamepad_axes: HashSet::new(),
+            contexts: Vec::new(),
+            touches: HashMap::new(),
+            last_device: None,
+            player_devices: HashMap::new(),
+            keyboard_player: 0,
+            any_pressed: false,
         }
     }
 
@@ -169,26 +1074,33 @@ where
     /// So, it will do things like move the axes and so on.
     pub fn update(&mut self, dt: f32) {
         for (_axis, axis_status) in self.axes.iter_mut() {
-            if axis_status.direction != 0.0 {
+            if axis_status.mouse_driven && axis_status.direction == 0.0 {
+                // Ease towards this frame's mouse motion.
+                let keep = axis_status.config.smoothing.powf(dt * 60.0);
+                let target = axis_status.impulse.clamp(-1.0, 1.0);
+                axis_status.position = target + (axis_status.position - target) * keep;
+                if axis_status.impulse == 0.0 && axis_status.position.abs() < 0.001 {
+                    axis_status.position = 0.0;
+                    axis_status.mouse_driven = false;
+                }
+            } else if axis_status.direction != 0.0 {
                 // Accelerate the axis towards the
                 // input'ed direction.
-                let vel = axis_status.acceleration * dt;
-                let pending_position = axis_status.position
-                    + if axis_status.direction > 0.0 {
-                        vel
-                    } else {
-                        -vel
-                    };
-                axis_status.position = if pending_position > 1.0 {
-                    1.0
-                } else if pending_position < -1.0 {
-                    -1.0
+                // Analog inputs can have a direction between -1 and 1,
+                // so stop once we get there.
+                let vel = axis_status.config.acceleration * dt;
+                let target = axis_status.direction.clamp(-1.0, 1.0);
+                axis_status.position = if axis_status.position < target {
+                    f32::min(axis_status.position + vel, target)
                 } else {
-                    pending_position
-                }
+                    f32::max(axis_status.position - vel, target)
+                };
             } else {
                 // Gravitate back towards 0.
-                let abs_dx = f32::min(axis_status.gravity * dt, f32::abs(axis_status.position));
+                let abs_dx = f32::min(
+                    axis_status.config.gravity * dt,
+                    f32::abs(axis_status.position),
+                );
                 let dx = if axis_status.position > 0.0 {
                     -abs_dx
                 } else {
@@ -196,14 +1108,38 @@ where
                 };
                 axis_status.position += dx;
             }
+            axis_status.impulse = 0.0;
         }
         for (_button, button_status) in self.buttons.iter_mut() {
             button_status.pressed_last_frame = button_status.pressed;
+            if let Some(t) = button_status.since_pressed.as_mut() {
+                *t += dt;
+            }
+            button_status.held_for_last_frame = button_status.held_for;
+            if button_status.pressed {
+                button_status.held_for += dt;
+            } else {
+                button_status.held_for = 0.0;
+            }
+            button_status.cooldown_left = (button_status.cooldown_left - dt).max(0.0);
+            button_status.repeated = button_status.repeat_count(button_status.held_for)
+                > button_status.repeat_count(button_status.held_for_last_frame)
+                && button_status.cooldown_left <= 0.0;
+            if button_status.repeated {
+                button_status.cooldown_left = button_status.cooldown;
+            }
+        }
+        for (_button, button_status) in self.mouse_buttons.iter_mut() {
+            button_status.pressed_last_frame = button_status.pressed;
         }
+        self.mouse_delta = math::vector2(0.0, 0.0);
+        self.scroll_delta = math::vector2(0.0, 0.0);
+        self.any_pressed = false;
     }
 
     /// This method should get called by your key_down_event handler.
     pub fn update_button_down(&mut self, button: Buttons) {
+        self.update_device_used(InputDevice::Keyboard, true);
         self.update_effect(InputEffect::Button(button), true);
     }
 
@@ -212,8 +1148,11 @@ where
         self.update_effect(InputEffect::Button(button), false);
     }
 
-    /// This method should get called by your key_up_event handler.
+    /// This method should get called by your key_down_event handler.
+    /// Starting an axis that's already going the same way does
+    /// nothing, so key repeats are fine.
     pub fn update_axis_start(&mut self, axis: Axes, positive: bool) {
+        self.update_device_used(InputDevice::Keyboard, true);
         self.update_effect(InputEffect::Axis(axis, positive), true);
     }
 
@@ -221,107 +1160,796 @@ where
         self.update_effect(InputEffect::Axis(axis, positive), false);
     }
 
-    /// Takes an InputEffect and actually applies it.
+    /// Sets where an axis is moving towards directly, for analog
+    /// inputs like sticks.  `value` is clamped to [-1, 1], and 0
+    /// lets the axis fall back to the center.
+    pub fn update_axis_analog(&mut self, axis: Axes, value: f32) {
+        self.axes.entry(axis).or_default().direction = value.clamp(-1.0, 1.0);
+    }
+
+    /// Feeds in a touch event.  ggez doesn't have a handler for
+    /// these, so call it from wherever you get winit's `Touch` events.
+    pub fn update_touch(&mut self, id: u64, phase: TouchPhase, x: f32, y: f32) {
+        match phase {
+            TouchPhase::Started | TouchPhase::Moved => {
+                self.touches.insert(id, math::point2(x, y));
+                self.update_device_used(InputDevice::Touch, phase == TouchPhase::Started);
+            }
+            TouchPhase::Ended | TouchPhase::Cancelled => {
+                self.touches.remove(&id);
+            }
+        }
+    }
+
+    /// Returns where the given touch is, if it's still on the screen.
+    pub fn get_touch(&self, id: u64) -> Option<Point2> {
+        self.touches.get(&id).cloned()
+    }
+
+    /// Iterates over all the touches currently on the screen.
+    pub fn get_touches(&self) -> impl Iterator<Item = (u64, Point2)> + '_ {
+        self.touches.iter().map(|(id, pos)| (*id, *pos))
+    }
+
+    /// Presses a plain key or mouse button with the given modifiers
+    /// held, applying whatever it's bound to in `binding`.  Call this
+    /// from your key_down_event and mouse_button_down_event handlers
+    /// if you want chords like Ctrl+S to work.  Returns the effect it
+    /// triggered, if any.
+    pub fn update_input_down(
+        &mut self,
+        binding: &InputBinding<Axes, Buttons>,
+        input: InputType,
+        mods: KeyMods,
+    ) -> Option<InputEffect<Axes, Buttons>> {
+        let input = input.without_mods();
+        let resolved = binding.resolve_with_mods(input, mods);
+        self.press_resolved(input, resolved.into_iter().collect())
+    }
+
+    /// Like `update_input_down()`, but resolves the input through the
+    /// context stack instead of a single binding.  If contexts that
+    /// don't consume it let it through to others that bind it too,
+    /// all their actions fire; this returns the topmost one.
+    pub fn update_context_input_down(
+        &mut self,
+        input: InputType,
+        mods: KeyMods,
+    ) -> Option<InputEffect<Axes, Buttons>> {
+        let input = input.without_mods();
+        let resolved = self.resolve_all_in_contexts(input, mods);
+        self.press_resolved(input, resolved)
+    }
+
+    fn press_resolved(
+        &mut self,
+        input: InputType,
+        resolved: ResolvedInputs<Axes, Buttons>,
+    ) -> Option<InputEffect<Axes, Buttons>> {
+        if let Some(held) = self.held_inputs.get(&input) {
+            // Key repeat, it's already down.
+            return held.first().map(|(_, effect)| effect.clone());
+        }
+        self.update_device_used(input.device(), true);
+        let first = resolved.first().map(|(_, effect)| effect.clone())?;
+        for (_, effect) in &resolved {
+            self.apply_effect(effect.clone(), true, Some(input));
+        }
+        self.held_inputs.insert(input, resolved);
+        Some(first)
+    }
+
+    /// Pushes a context onto the stack.  It'll take priority over
+    /// the ones below it.
+    pub fn push_context(&mut self, context: InputContext<Axes, Buttons>) {
+        self.contexts.push(context);
+    }
+
+    /// Pops the top context off the stack.  Anything held down
+    /// through it will still be released properly.
+    pub fn pop_context(&mut self) -> Option<InputContext<Axes, Buttons>> {
+        self.contexts.pop()
+    }
+
+    /// The context on top of the stack.
+    pub fn current_context(&self) -> Option<&InputContext<Axes, Buttons>> {
+        self.contexts.last()
+    }
+
+    pub fn current_context_mut(&mut self) -> Option<&mut InputContext<Axes, Buttons>> {
+        self.contexts.last_mut()
+    }
+
+    /// Returns the physical inputs bound to different actions by
+    /// contexts that are active together (the top one, and any
+    /// below it that pass-through contexts let input fall to), where
+    /// the upper context consumes the input.  The lower context's
+    /// action for these can never fire.
+    pub fn conflicts(&self) -> Vec<InputType> {
+        let mut active = Vec::new();
+        for context in self.contexts.iter().rev() {
+            active.push(context);
+            if !context.pass_through {
+                break;
+            }
+        }
+        let mut inputs = Vec::new();
+        for (i, upper) in active.iter().enumerate() {
+            for lower in &active[i + 1..] {
+                for input in upper.binding.conflicts_with(&lower.binding) {
+                    let consumed = upper
+                        .binding
+                        .resolve_input(input)
+                        .is_some_and(|effect| upper.consumes(&effect));
+                    if consumed && !inputs.contains(&input) {
+                        inputs.push(input);
+                    }
+                }
+            }
+        }
+        inputs.sort_by_key(|input| input.display_order());
+        inputs
+    }
+
+    /// Resolves a physical input through the context stack: the top
+    /// context gets it first, then contexts below it for as long as
+    /// they're pass-through.
+    pub fn resolve_in_contexts(
+        &self,
+        input: InputType,
+        mods: KeyMods,
+    ) -> Option<(InputType, InputEffect<Axes, Buttons>)> {
+        self.resolve_all_in_contexts(input, mods).into_iter().next()
+    }
+
+    /// Like `resolve_in_contexts()`, but keeps going past contexts
+    /// that bind the input without consuming it, returning every
+    /// action it triggers from the top down.
+    pub fn resolve_all_in_contexts(
+        &self,
+        input: InputType,
+        mods: KeyMods,
+    ) -> ResolvedInputs<Axes, Buttons> {
+        let mut resolved = Vec::new();
+        for context in self.contexts.iter().rev() {
+            match context.binding.resolve_with_mods(input, mods) {
+                Some((matched, effect)) => {
+                    let consumed = context.consumes(&effect);
+                    resolved.push((matched, effect));
+                    if consumed {
+                        break;
+                    }
+                }
+                None if !context.pass_through => break,
+                None => (),
+            }
+        }
+        resolved
+    }
+
+    /// Releases a plain key or mouse button pressed with
+    /// `update_input_down()` or `update_context_input_down()`, releasing whatever it triggered even if
+    /// the modifiers have changed since.  Releasing a modifier key
+    /// also releases any chords that needed it.
+    pub fn update_input_up(&mut self, input: InputType) {
+        let input = input.without_mods();
+        for (_, effect) in self.held_inputs.remove(&input).unwrap_or_default() {
+            self.apply_effect(effect, false, Some(input));
+        }
+        if let InputType::KeyEvent(keycode) = input {
+            let released = key_mods(keycode);
+            if !released.is_empty() {
+                let broken: Vec<_> = self
+                    .held_inputs
+                    .iter()
+                    .filter(|(_, effects)| {
+                        effects
+                            .iter()
+                            .any(|(matched, _)| matched.mods().intersects(released))
+                    })
+                    .map(|(held, _)| *held)
+                    .collect();
+                for held in broken {
+                    for (_, effect) in self.held_inputs.remove(&held).unwrap_or_default() {
+                        self.apply_effect(effect, false, Some(held));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Takes an InputEffect and actually applies it.  Unlike the
+    /// other `update_*` methods, this doesn't record which device
+    /// it came from.
+    ///
+    /// Everything applied this way counts as one input, so if two
+    /// keys hold an axis the same way, stopping either stops it.
+    /// Use `update_effect_from()` for effects resolved from a
+    /// physical input.
     pub fn update_effect(&mut self, effect: InputEffect<Axes, Buttons>, started: bool) {
+        self.apply_effect(effect, started, None);
+    }
+
+    /// Like `update_effect()`, for an effect `source` was resolved
+    /// to.  An axis keeps going while any input holding it that way
+    /// is still down, and repeats from the same input don't stack.
+    pub fn update_effect_from(
+        &mut self,
+        effect: InputEffect<Axes, Buttons>,
+        started: bool,
+        source: InputType,
+    ) {
+        if started {
+            self.update_device_used(source.device(), true);
+        }
+        self.apply_effect(effect, started, Some(source));
+    }
+
+    /// Applies an effect on behalf of `source`, the input holding it.
+    fn apply_effect(
+        &mut self,
+        effect: InputEffect<Axes, Buttons>,
+        started: bool,
+        source: Option<InputType>,
+    ) {
         match effect {
             InputEffect::Axis(axis, positive) => {
                 let f = || AxisState::default();
                 let axis_status = self.axes.entry(axis).or_insert_with(f);
+                let direction_float = if positive { 1.0 } else { -1.0 };
+                let (held, other_held) = if positive {
+                    (&mut axis_status.positive_held, &axis_status.negative_held)
+                } else {
+                    (&mut axis_status.negative_held, &axis_status.positive_held)
+                };
                 if started {
-                    let direction_float = if positive { 1.0 } else { -1.0 };
-                    axis_status.direction = direction_float;
-                } else if (positive && axis_status.direction > 0.0)
-                    || (!positive && axis_status.direction < 0.0)
-                {
-                    axis_status.direction = 0.0;
+                    held.insert(source);
+                    axis_status.move_towards(direction_float);
+                } else {
+                    held.remove(&source);
+                    // Only let go once nothing is holding this way,
+                    // then go back to the other way if that's still held.
+                    let released = held.is_empty();
+                    if released && axis_status.direction * direction_float > 0.0 {
+                        if !other_held.is_empty() {
+                            axis_status.move_towards(-direction_float);
+                        } else {
+                            axis_status.direction = 0.0;
+                        }
+                    }
                 }
             }
             InputEffect::Button(button) => {
                 let f = || ButtonState::default();
                 let button_status = self.buttons.entry(button).or_insert_with(f);
+                if started && !button_status.pressed {
+                    button_status.since_pressed = Some(0.0);
+                    button_status.blocked = button_status.cooldown_left > 0.0;
+                    if !button_status.blocked {
+                        button_status.cooldown_left = button_status.cooldown;
+                    }
+                    self.any_pressed = true;
+                }
                 button_status.pressed = started;
             }
         }
     }
 
-    pub fn get_axis(&self, axis: Axes) -> f32 {
-        let d = AxisState::default();
-        let axis_status = self.axes.get(&axis).unwrap_or(&d);
-        axis_status.position
+    /// Sets how an axis responds to input.
+    pub fn set_axis_config(&mut self, axis: Axes, config: AxisConfig) {
+        self.axes.entry(axis).or_default().config = config;
+    }
+
+    /// Uses all the axis configurations and sticks from the given
+    /// binding.
+    pub fn apply_axis_configs(&mut self, binding: &InputBinding<Axes, Buttons>) {
+        for (axis, config) in &binding.axis_configs {
+            self.set_axis_config(axis.clone(), *config);
+        }
+        for (stick, (x_axis, y_axis)) in &binding.sticks {
+            self.set_stick(stick, x_axis.clone(), y_axis.clone());
+        }
+    }
+
+    /// Groups two axes into a named stick.
+    pub fn set_stick(&mut self, stick: &str, x_axis: Axes, y_axis: Axes) {
+        self.sticks.insert(stick.to_owned(), (x_axis, y_axis));
+    }
+
+    /// Returns a named stick's position.  It's kept within the unit
+    /// circle, so pressing two keys to go diagonally isn't faster
+    /// than going straight.  Zero if there's no such stick.
+    pub fn get_stick(&self, stick: &str) -> Vector2 {
+        match self.sticks.get(stick) {
+            Some((x_axis, y_axis)) => {
+                let x = self.get_axis(x_axis.clone());
+                let y = self.get_axis(y_axis.clone());
+                let len = (x * x + y * y).sqrt();
+                if len > 1.0 {
+                    math::vector2(x / len, y / len)
+                } else {
+                    math::vector2(x, y)
+                }
+            }
+            None => math::vector2(0.0, 0.0),
+        }
+    }
+
+    /// Returns the axis position, smoothed and with its response
+    /// curve applied.  This is in [-1, 1] unless the axis has been
+    /// remapped.
+    pub fn get_axis(&self, axis: Axes) -> f32 {
+        let d = AxisState::default();
+        let axis_status = self.axes.get(&axis).unwrap_or(&d);
+        axis_status.config.respond(axis_status.position)
+    }
+
+    pub fn get_axis_raw(&self, axis: Axes) -> f32 {
+        let d = AxisState::default();
+        let axis_status = self.axes.get(&axis).unwrap_or(&d);
+        axis_status.direction
+    }
+
+    fn get_button(&self, button: Buttons) -> ButtonState {
+        let d = ButtonState::default();
+        let button_status = self.buttons.get(&button).unwrap_or(&d);
+        *button_status
+    }
+
+    pub fn get_button_down(&self, axis: Buttons) -> bool {
+        self.get_button(axis).pressed
+    }
+
+    pub fn get_button_up(&self, axis: Buttons) -> bool {
+        !self.get_button(axis).pressed
+    }
+
+    /// Returns whether or not the button was pressed this frame,
+    /// only returning true if the press happened this frame.
+    ///
+    /// Basically, `get_button_down()` and `get_button_up()` are level
+    /// triggers, this and `get_button_released()` are edge triggered.
+    /// Returns true on the frame the button is pressed, and again
+    /// each time it auto-repeats if it's set to.  Presses while the
+    /// button is cooling down don't count.
+    pub fn get_button_pressed(&self, axis: Buttons) -> bool {
+        let b = self.get_button(axis);
+        b.pressed && ((!b.pressed_last_frame && !b.blocked) || b.repeated)
+    }
+
+    pub fn get_button_released(&self, axis: Buttons) -> bool {
+        let b = self.get_button(axis);
+        !b.pressed && b.pressed_last_frame
+    }
+
+    /// Returns how long the button has been held down, in seconds,
+    /// or 0 if it isn't down.  Only counts time passed to `update()`.
+    pub fn get_button_hold_time(&self, button: Buttons) -> f32 {
+        let b = self.get_button(button);
+        if b.pressed {
+            b.held_for
+        } else {
+            0.0
+        }
+    }
+
+    /// Makes `get_button_pressed()` fire again while the button is
+    /// held: first after `delay` seconds, then every `interval`
+    /// seconds, like key repeat.  Handy for menus and moving
+    /// tile by tile.
+    pub fn set_button_repeat(&mut self, button: Buttons, delay: f32, interval: f32) {
+        self.buttons.entry(button).or_default().repeat = Some((delay, interval));
+    }
+
+    /// Makes `get_button_pressed()` ignore presses for `secs` seconds
+    /// after one counts, for things like rate of fire.  0 turns the
+    /// cooldown off.
+    pub fn set_cooldown(&mut self, button: Buttons, secs: f32) {
+        self.buttons.entry(button).or_default().cooldown = secs;
+    }
+
+    /// Returns how many seconds are left before the button's next
+    /// press will count.
+    pub fn cooldown_remaining(&self, button: Buttons) -> f32 {
+        self.get_button(button).cooldown_left
+    }
+
+    /// Turns auto-repeat back off for a button.
+    pub fn clear_button_repeat(&mut self, button: Buttons) {
+        if let Some(b) = self.buttons.get_mut(&button) {
+            b.repeat = None;
+            b.repeated = false;
+        }
+    }
+
+    /// Returns true on the one frame where the button has been held
+    /// for `secs` seconds, for charge attacks or long presses.
+    /// Edge triggered, like `get_button_pressed()`.
+    pub fn get_button_held_for(&self, button: Buttons, secs: f32) -> bool {
+        let b = self.get_button(button);
+        b.pressed && b.held_for >= secs && b.held_for_last_frame < secs
+    }
+
+    /// Returns whether the button was pressed within the last
+    /// `window` seconds and that press hasn't been consumed yet.
+    /// This lets a jump pressed just before landing still count once
+    /// you land; call `consume_button_press()` once you act on it.
+    pub fn get_button_pressed_buffered(&self, button: Buttons, window: f32) -> bool {
+        self.get_button(button)
+            .since_pressed
+            .is_some_and(|t| t <= window)
+    }
+
+    /// Forgets the button's last press, so
+    /// `get_button_pressed_buffered()` won't return it again.  The
+    /// button still counts as held, for `get_button_hold_time()`.
+    pub fn consume_button_press(&mut self, button: Buttons) {
+        if let Some(button_status) = self.buttons.get_mut(&button) {
+            button_status.since_pressed = None;
+        }
+    }
+
+    /// `get_button_pressed_buffered()` and `consume_button_press()`
+    /// in one.
+    pub fn take_button_pressed_buffered(&mut self, button: Buttons, window: f32) -> bool {
+        let pressed = self.get_button_pressed_buffered(button.clone(), window);
+        if pressed {
+            self.consume_button_press(button);
+        }
+        pressed
+    }
+
+    /// This method should get called by your mouse_motion_event handler,
+    /// with the same arguments.
+    pub fn update_mouse_motion(&mut self, x: f32, y: f32, dx: f32, dy: f32) {
+        self.update_device_used(InputDevice::Mouse, false);
+        self.mouse_position = math::point2(x, y);
+        self.mouse_delta = math::vector2(self.mouse_delta.x + dx, self.mouse_delta.y + dy);
+    }
+
+    /// Like `update_mouse_motion()`, but also feeds the motion to any
+    /// axes `binding` binds mouse motion to.
+    pub fn update_mouse_motion_with(
+        &mut self,
+        binding: &InputBinding<Axes, Buttons>,
+        x: f32,
+        y: f32,
+        dx: f32,
+        dy: f32,
+    ) {
+        self.update_mouse_motion(x, y, dx, dy);
+        for (motion, (axis, sensitivity)) in &binding.mouse_axes {
+            let delta = match motion {
+                MouseAxis::X => dx,
+                MouseAxis::Y => dy,
+            };
+            self.add_axis_impulse(axis.clone(), delta * sensitivity);
+        }
+    }
+
+    /// Nudges a mouse-driven axis by `amount` for this frame.
+    fn add_axis_impulse(&mut self, axis: Axes, amount: f32) {
+        let axis_status = self.axes.entry(axis).or_default();
+        axis_status.impulse += amount;
+        axis_status.mouse_driven = true;
+    }
+
+    /// Presses or releases the button `binding` binds an analog
+    /// gamepad axis to, if any.  Call it from your gamepad_axis_event
+    /// handler.
+    pub fn update_gamepad_axis(
+        &mut self,
+        binding: &InputBinding<Axes, Buttons>,
+        axis: Axis,
+        value: f32,
+        id: GamepadId,
+    ) {
+        if let Some((button, pressed)) = self.gamepad_axis_change(binding, axis, value, id) {
+            self.update_effect(InputEffect::Button(button), pressed);
+        }
+    }
+
+    /// Works out whether a gamepad axis moving presses or releases
+    /// the button it's bound to, and remembers that it did.  Returns
+    /// the button and whether it's now pressed, if it changed.
+    fn gamepad_axis_change(
+        &mut self,
+        binding: &InputBinding<Axes, Buttons>,
+        axis: Axis,
+        value: f32,
+        id: GamepadId,
+    ) -> Option<(Buttons, bool)> {
+        let (button, threshold, hysteresis) = binding.gamepad_axes.get(&axis)?.clone();
+        let was_pressed = self.pressed_gamepad_axes.contains(&(id, axis));
+        let pressed = analog_pressed(was_pressed, value, threshold, hysteresis);
+        if pressed == was_pressed {
+            return None;
+        }
+        if pressed {
+            self.pressed_gamepad_axes.insert((id, axis));
+            self.update_device_used(InputDevice::Gamepad(id), true);
+        } else {
+            self.pressed_gamepad_axes.remove(&(id, axis));
+        }
+        Some((button, pressed))
+    }
+
+    /// This method should get called by your mouse_wheel_event handler.
+    pub fn update_mouse_scroll(&mut self, x: f32, y: f32) {
+        self.scroll_delta = math::vector2(self.scroll_delta.x + x, self.scroll_delta.y + y);
+    }
+
+    /// This method should get called by your mouse_button_down_event handler.
+    pub fn update_mouse_button_down(&mut self, button: MouseButton) {
+        self.update_device_used(InputDevice::Mouse, true);
+        self.mouse_buttons.entry(button).or_default().pressed = true;
+    }
+
+    /// This method should get called by your mouse_button_up_event handler.
+    pub fn update_mouse_button_up(&mut self, button: MouseButton) {
+        self.mouse_buttons.entry(button).or_default().pressed = false;
+    }
+
+    /// The last known position of the mouse cursor, in screen coordinates.
+    pub fn get_mouse_position(&self) -> Point2 {
+        self.mouse_position
+    }
+
+    /// How far the mouse has moved since the last `update()`.
+    pub fn get_mouse_delta(&self) -> Vector2 {
+        self.mouse_delta
+    }
+
+    /// How far the mouse wheel has scrolled since the last `update()`.
+    pub fn get_scroll_delta(&self) -> Vector2 {
+        self.scroll_delta
+    }
+
+    fn get_mouse_button(&self, button: MouseButton) -> ButtonState {
+        self.mouse_buttons.get(&button).cloned().unwrap_or_default()
+    }
+
+    pub fn get_mouse_button_down(&self, button: MouseButton) -> bool {
+        self.get_mouse_button(button).pressed
+    }
+
+    pub fn get_mouse_button_up(&self, button: MouseButton) -> bool {
+        !self.get_mouse_button(button).pressed
+    }
+
+    /// Edge-triggered like `get_button_pressed()`.
+    pub fn get_mouse_button_pressed(&self, button: MouseButton) -> bool {
+        let b = self.get_mouse_button(button);
+        b.pressed && !b.pressed_last_frame
+    }
+
+    pub fn get_mouse_button_released(&self, button: MouseButton) -> bool {
+        let b = self.get_mouse_button(button);
+        !b.pressed && b.pressed_last_frame
+    }
+
+    /// Sets how many player slots gamepads get assigned to; the
+    /// default is 4.  Shrinking it drops any gamepads in the removed
+    /// slots without generating events.
+    pub fn set_player_slots(&mut self, count: usize) {
+        self.gamepads.resize(count);
+    }
+
+    /// Assigns a gamepad to a player, unassigning whatever gamepad
+    /// that player had before.
+    pub fn assign_gamepad(&mut self, id: GamepadId, player: usize) {
+        if let Some(old_player) = self.gamepads.player(id) {
+            if old_player == player {
+                return;
+            }
+            self.gamepad_events
+                .push_back(GamepadEvent::Disconnected(id, old_player));
+        }
+        if let Some(old) = self.gamepads.assign(id, player) {
+            self.gamepad_events
+                .push_back(GamepadEvent::Disconnected(old, player));
+        }
+        self.gamepad_events
+            .push_back(GamepadEvent::Connected(id, player));
+    }
+
+    /// Tells the input state a gamepad has been plugged in, assigning
+    /// it to the first free player slot.  Returns the player, or
+    /// `None` if all slots are full.  Gamepads that already have a
+    /// slot keep it.
+    ///
+    /// ggez doesn't tell us when gamepads are plugged in, so call this
+    /// from your gamepad event handlers: a gamepad we haven't seen
+    /// before then gets treated as newly connected.
+    pub fn connect_gamepad(&mut self, id: GamepadId) -> Option<usize> {
+        let (player, new) = self.gamepads.connect(id)?;
+        if new {
+            self.gamepad_events
+                .push_back(GamepadEvent::Connected(id, player));
+        }
+        Some(player)
+    }
+
+    /// Tells the input state a gamepad has been unplugged, freeing
+    /// its player slot.  Returns the player it belonged to.
+    pub fn disconnect_gamepad(&mut self, id: GamepadId) -> Option<usize> {
+        let player = self.gamepads.remove(id)?;
+        self.gamepad_events
+            .push_back(GamepadEvent::Disconnected(id, player));
+        Some(player)
     }
 
-    pub fn get_axis_raw(&self, axis: Axes) -> f32 {
-        let d = AxisState::default();
-        let axis_status = self.axes.get(&axis).unwrap_or(&d);
-        axis_status.direction
+    /// Returns the player a gamepad is assigned to, if any.  See
+    /// `connect_gamepad()`.
+    pub fn gamepad_player(&self, id: GamepadId) -> Option<usize> {
+        self.gamepads.player(id)
     }
 
-    fn get_button(&self, button: Buttons) -> ButtonState {
-        let d = ButtonState::default();
-        let button_status = self.buttons.get(&button).unwrap_or(&d);
-        *button_status
+    /// Returns the gamepad assigned to a player, if any.
+    pub fn player_gamepad(&self, player: usize) -> Option<GamepadId> {
+        self.gamepads.device(player)
     }
 
-    pub fn get_button_down(&self, axis: Buttons) -> bool {
-        self.get_button(axis).pressed
+    /// Records that a device was just used, and whether it pressed
+    /// something.  Keyboard, mouse and touch input passed to the
+    /// `update_*` methods is recorded automatically, except through
+    /// `update_effect()`; call this from your gamepad handlers, or for
+    /// input you handle yourself.
+    pub fn update_device_used(&mut self, device: InputDevice, pressed: bool) {
+        self.last_device = Some(device);
+        let player = match device {
+            InputDevice::Gamepad(id) => self.gamepads.player(id),
+            _ => Some(self.keyboard_player),
+        };
+        if let Some(player) = player {
+            self.player_devices.insert(player, device);
+        }
+        self.any_pressed |= pressed;
     }
 
-    pub fn get_button_up(&self, axis: Buttons) -> bool {
-        !self.get_button(axis).pressed
+    /// Sets which player the keyboard, mouse and touch screen belong
+    /// to, for `last_device_for_player()`.  Player 0 by default.
+    pub fn set_keyboard_player(&mut self, player: usize) {
+        self.keyboard_player = player;
     }
 
-    /// Returns whether or not the button was pressed this frame,
-    /// only returning true if the press happened this frame.
-    ///
-    /// Basically, `get_button_down()` and `get_button_up()` are level
-    /// triggers, this and `get_button_released()` are edge triggered.
-    pub fn get_button_pressed(&self, axis: Buttons) -> bool {
-        let b = self.get_button(axis);
-        b.pressed && !b.pressed_last_frame
+    /// The device that most recently produced input.
+    pub fn last_device(&self) -> Option<InputDevice> {
+        self.last_device
     }
 
-    pub fn get_button_released(&self, axis: Buttons) -> bool {
-        let b = self.get_button(axis);
-        !b.pressed && b.pressed_last_frame
+    /// The device that most recently produced input for a player,
+    /// for deciding whether to show keyboard or gamepad prompts.
+    pub fn last_device_for_player(&self, player: usize) -> Option<InputDevice> {
+        self.player_devices.get(&player).cloned()
+    }
+
+    /// Returns true if anything at all was pressed this frame, bound
+    /// or not, for "press any key" screens.
+    pub fn any_input_pressed(&self) -> bool {
+        self.any_pressed
     }
 
-    pub fn mouse_position() {
-        unimplemented!()
+    /// Returns the next unhandled change to the gamepad assignments.
+    pub fn poll_gamepad_event(&mut self) -> Option<GamepadEvent> {
+        self.gamepad_events.pop_front()
     }
 
-    pub fn mouse_scroll_delta() {
-        unimplemented!()
+    /// Starts listening for a physical input to bind to the given
+    /// action, for "press a key to bind" menus.  Until `capture()`
+    /// or `capture_gamepad_axis()` catches something or
+    /// `stop_listening()` is called, your event handlers should offer
+    /// their input to those first.
+    pub fn start_listening(&mut self, action: InputEffect<Axes, Buttons>) {
+        self.listening = Some(action);
     }
 
-    pub fn get_mouse_button() {
-        unimplemented!()
+    pub fn stop_listening(&mut self) {
+        self.listening = None;
     }
 
-    pub fn get_mouse_button_down() {
-        unimplemented!()
+    pub fn is_listening(&self) -> bool {
+        self.listening.is_some()
     }
 
-    pub fn get_mouse_button_up() {
-        unimplemented!()
+    /// If we're listening for an input to bind, binds the given one
+    /// to the action in `binding` and stops listening.  Returns the
+    /// input and action that were bound, or `None` if we weren't
+    /// listening, in which case handle the input as usual:
+    ///
+    /// ```rust,ignore
+    /// fn key_down_event(&mut self, ctx: &mut Context, keycode: KeyCode, keymods: KeyMods, _repeat: bool) {
+    ///     let input = InputType::KeyEvent(keycode);
+    ///     if self.input.capture(&mut self.bindings, input).is_none() {
+    ///         self.input.update_input_down(&self.bindings, input, keymods);
+    ///     }
+    /// }
+    /// ```
+    pub fn capture(
+        &mut self,
+        binding: &mut InputBinding<Axes, Buttons>,
+        input: InputType,
+    ) -> Option<(InputType, InputEffect<Axes, Buttons>)> {
+        let action = self.listening.take()?;
+        binding.insert(input, action.clone());
+        Some((input, action))
+    }
+
+    /// Like `capture()`, for analog gamepad axes such as triggers:
+    /// call it from your gamepad_axis_event handler.  If we're
+    /// listening for a button and the axis has moved at least
+    /// `threshold` from the center, binds that direction of the axis
+    /// to the button with `InputBinding::bind_gamepad_axis_to_button()`
+    /// and stops listening.
+    ///
+    /// Returns the axis, whether it was pushed in the positive
+    /// direction, and the action.  Axes can't be bound to logical
+    /// axes this way, so while listening for one of those this does
+    /// nothing; use `InputBinding::bind_axes_to_stick()` for sticks.
+    pub fn capture_gamepad_axis(
+        &mut self,
+        binding: &mut InputBinding<Axes, Buttons>,
+        axis: Axis,
+        value: f32,
+        threshold: f32,
+        hysteresis: f32,
+    ) -> Option<(Axis, bool, InputEffect<Axes, Buttons>)> {
+        let button = match &self.listening {
+            Some(InputEffect::Button(button)) if value.abs() >= threshold => button.clone(),
+            _ => return None,
+        };
+        let action = self.listening.take()?;
+        let positive = value >= 0.0;
+        let signed_threshold = if positive { threshold } else { -threshold };
+        binding
+            .gamepad_axes
+            .insert(axis, (button, signed_threshold, hysteresis));
+        Some((axis, positive, action))
     }
 
     pub fn reset_input_state(&mut self) {
         for (_axis, axis_status) in self.axes.iter_mut() {
             axis_status.position = 0.0;
             axis_status.direction = 0.0;
+            axis_status.impulse = 0.0;
+            axis_status.mouse_driven = false;
+            axis_status.positive_held.clear();
+            axis_status.negative_held.clear();
         }
 
         for (_button, button_status) in self.buttons.iter_mut() {
             button_status.pressed = false;
             button_status.pressed_last_frame = false;
+            button_status.since_pressed = None;
+            button_status.held_for = 0.0;
+            button_status.held_for_last_frame = 0.0;
+            button_status.repeated = false;
+            button_status.cooldown_left = 0.0;
+            button_status.blocked = false;
         }
+        self.held_inputs.clear();
+        self.touches.clear();
+        self.pressed_gamepad_axes.clear();
+
+        self.mouse_buttons.clear();
+        self.mouse_delta = math::vector2(0.0, 0.0);
+        self.scroll_delta = math::vector2(0.0, 0.0);
+    }
+}
+
+impl<Axes, Buttons> Default for InputState<Axes, Buttons>
+where
+    Axes: Eq + Hash + Clone,
+    Buttons: Eq + Hash + Clone,
+{
+    fn default() -> Self {
+        Self::new()
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use ggez::event::*;
 
     #[derive(Hash, Eq, PartialEq, Copy, Clone, Debug)]
     enum Buttons {
@@ -338,7 +1966,7 @@ mod tests {
     }
 
     fn make_input_binding() -> InputBinding<Axes, Buttons> {
-        let ib = InputBinding::<Axes, Buttons>::new()
+        InputBinding::<Axes, Buttons>::new()
             .bind_key_to_button(KeyCode::Z, Buttons::A)
             .bind_key_to_button(KeyCode::X, Buttons::B)
             .bind_key_to_button(KeyCode::Return, Buttons::Start)
@@ -347,8 +1975,7 @@ mod tests {
             .bind_key_to_axis(KeyCode::Up, Axes::Vert, true)
             .bind_key_to_axis(KeyCode::Down, Axes::Vert, false)
             .bind_key_to_axis(KeyCode::Left, Axes::Horz, false)
-            .bind_key_to_axis(KeyCode::Right, Axes::Horz, true);
-        ib
+            .bind_key_to_axis(KeyCode::Right, Axes::Horz, true)
     }
 
     #[test]
@@ -443,6 +2070,47 @@ mod tests {
         assert_eq!(im.get_axis_raw(Axes::Vert), 0.0);
     }
 
+    #[test]
+    fn test_axis_held_by_several_inputs() {
+        let ib = make_input_binding().bind_key_to_axis(KeyCode::W, Axes::Vert, true);
+        let mut im: InputState<Axes, Buttons> = InputState::new();
+        let up = InputType::KeyEvent(KeyCode::Up);
+        let w = InputType::KeyEvent(KeyCode::W);
+        let down = InputType::KeyEvent(KeyCode::Down);
+
+        // Key repeats don't need matching releases.
+        im.update_input_down(&ib, up, KeyMods::NONE);
+        im.update_input_down(&ib, up, KeyMods::NONE);
+        im.update_input_up(up);
+        assert_eq!(im.get_axis_raw(Axes::Vert), 0.0);
+
+        // Two keys holding the same direction, releasing one of
+        // them keeps it going.
+        im.update_input_down(&ib, up, KeyMods::NONE);
+        im.update_input_down(&ib, w, KeyMods::NONE);
+        im.update_input_down(&ib, w, KeyMods::NONE);
+        im.update_input_up(up);
+        assert_eq!(im.get_axis_raw(Axes::Vert), 1.0);
+        // Letting go of the opposite direction goes back to this one.
+        im.update_input_down(&ib, down, KeyMods::NONE);
+        assert_eq!(im.get_axis_raw(Axes::Vert), -1.0);
+        im.update_input_up(down);
+        assert_eq!(im.get_axis_raw(Axes::Vert), 1.0);
+        im.update_input_up(w);
+        assert_eq!(im.get_axis_raw(Axes::Vert), 0.0);
+
+        // Same again, resolving the keys ourselves.
+        let up_effect = ib.resolve(KeyCode::Up).unwrap();
+        let w_effect = ib.resolve(KeyCode::W).unwrap();
+        im.update_effect_from(up_effect, true, up);
+        im.update_effect_from(up_effect, true, up);
+        im.update_effect_from(w_effect, true, w);
+        im.update_effect_from(up_effect, false, up);
+        assert_eq!(im.get_axis_raw(Axes::Vert), 1.0);
+        im.update_effect_from(w_effect, false, w);
+        assert_eq!(im.get_axis_raw(Axes::Vert), 0.0);
+    }
+
     #[test]
     fn test_button_edge_transitions() {
         let mut im: InputState<Axis, Buttons> = InputState::new();
@@ -471,4 +2139,621 @@ mod tests {
         assert!(!im.get_button_pressed(Buttons::A));
         assert!(!im.get_button_released(Buttons::A));
     }
+
+    #[test]
+    fn test_mouse_state() {
+        let mut im: InputState<Axes, Buttons> = InputState::new();
+        im.update_mouse_motion(10.0, 20.0, 10.0, 20.0);
+        im.update_mouse_motion(15.0, 18.0, 5.0, -2.0);
+        im.update_mouse_scroll(0.0, 1.0);
+        assert_eq!(im.get_mouse_position().x, 15.0);
+        assert_eq!(im.get_mouse_position().y, 18.0);
+        assert_eq!(im.get_mouse_delta().x, 15.0);
+        assert_eq!(im.get_mouse_delta().y, 18.0);
+        assert_eq!(im.get_scroll_delta().y, 1.0);
+
+        im.update_mouse_button_down(MouseButton::Left);
+        assert!(im.get_mouse_button_down(MouseButton::Left));
+        assert!(im.get_mouse_button_pressed(MouseButton::Left));
+        assert!(im.get_mouse_button_up(MouseButton::Right));
+
+        // Deltas are per-frame, position sticks around.
+        im.update(0.1);
+        assert_eq!(im.get_mouse_delta().x, 0.0);
+        assert_eq!(im.get_scroll_delta().y, 0.0);
+        assert_eq!(im.get_mouse_position().x, 15.0);
+        assert!(im.get_mouse_button_down(MouseButton::Left));
+        assert!(!im.get_mouse_button_pressed(MouseButton::Left));
+
+        im.update_mouse_button_up(MouseButton::Left);
+        assert!(im.get_mouse_button_released(MouseButton::Left));
+    }
+
+    #[test]
+    fn test_device_slots() {
+        let mut slots: DeviceSlots<u32> = DeviceSlots::new(2);
+        assert_eq!(slots.connect(10), Some((0, true)));
+        assert_eq!(slots.connect(11), Some((1, true)));
+        assert_eq!(slots.connect(10), Some((0, false)));
+        // Full
+        assert_eq!(slots.connect(12), None);
+
+        // Pad 10 drops out, a new one takes its place.
+        assert_eq!(slots.remove(10), Some(0));
+        assert_eq!(slots.device(0), None);
+        assert_eq!(slots.connect(12), Some((0, true)));
+
+        // Manually swap players around.
+        assert_eq!(slots.assign(11, 0), Some(12));
+        assert_eq!(slots.player(11), Some(0));
+        assert_eq!(slots.device(1), None);
+        assert_eq!(slots.player(12), None);
+    }
+
+    #[cfg(feature = "serialize")]
+    #[test]
+    fn test_binding_serialization() {
+        use serde::{Deserialize, Serialize};
+
+        #[derive(Hash, Eq, PartialEq, Copy, Clone, Debug, Serialize, Deserialize)]
+        enum Buttons {
+            Jump,
+        }
+
+        #[derive(Hash, Eq, PartialEq, Copy, Clone, Debug, Serialize, Deserialize)]
+        enum Axes {
+            Horz,
+            Vert,
+        }
+
+        let ib = InputBinding::<Axes, Buttons>::new()
+            .bind_key_to_button(KeyCode::Space, Buttons::Jump)
+            .bind_key_to_axis(KeyCode::Left, Axes::Horz, false)
+            .bind_key_to_axis(KeyCode::Right, Axes::Horz, true)
+            .bind_input_to_button(
+                InputType::MouseChord(KeyMods::CTRL | KeyMods::ALT, MouseButton::Right),
+                Buttons::Jump,
+            )
+            .configure_axis(
+                Axes::Horz,
+                AxisConfig {
+                    snap: true,
+                    ..AxisConfig::default()
+                },
+            )
+            .bind_axes_to_stick("move", Axes::Horz, Axes::Vert)
+            .bind_gamepad_axis_to_button(Axis::RightZ, Buttons::Jump, 0.5, 0.1);
+        let s = toml::to_string(&ib).unwrap();
+        let loaded: InputBinding<Axes, Buttons> = toml::from_str(&s).unwrap();
+        assert_eq!(loaded.bindings, ib.bindings);
+        assert_eq!(loaded.axis_configs, ib.axis_configs);
+        assert_eq!(loaded.sticks, ib.sticks);
+        assert_eq!(loaded.gamepad_axes, ib.gamepad_axes);
+    }
+
+    #[test]
+    fn test_rebinding() {
+        let mut ib = make_input_binding();
+        let mut im: InputState<Axes, Buttons> = InputState::new();
+        let space = InputType::KeyEvent(KeyCode::Space);
+
+        assert_eq!(im.capture(&mut ib, space), None);
+        im.start_listening(InputEffect::Button(Buttons::A));
+        assert!(im.is_listening());
+        assert_eq!(
+            im.capture(&mut ib, space),
+            Some((space, InputEffect::Button(Buttons::A)))
+        );
+        assert!(!im.is_listening());
+        assert_eq!(
+            ib.resolve(KeyCode::Space),
+            Some(InputEffect::Button(Buttons::A))
+        );
+        // The old binding is still there too.
+        assert_eq!(
+            ib.resolve(KeyCode::Z),
+            Some(InputEffect::Button(Buttons::A))
+        );
+
+        im.start_listening(InputEffect::Axis(Axes::Horz, true));
+        im.stop_listening();
+        assert_eq!(im.capture(&mut ib, space), None);
+    }
+
+    #[test]
+    fn test_rebinding_gamepad_axis() {
+        let mut ib = make_input_binding();
+        let mut im: InputState<Axes, Buttons> = InputState::new();
+        assert_eq!(
+            im.capture_gamepad_axis(&mut ib, Axis::LeftStickX, -1.0, 0.5, 0.1),
+            None
+        );
+
+        im.start_listening(InputEffect::Button(Buttons::B));
+        // Drift under the threshold doesn't count.
+        assert_eq!(
+            im.capture_gamepad_axis(&mut ib, Axis::LeftStickX, -0.25, 0.5, 0.1),
+            None
+        );
+        assert!(im.is_listening());
+        assert_eq!(
+            im.capture_gamepad_axis(&mut ib, Axis::LeftStickX, -0.75, 0.5, 0.1),
+            Some((Axis::LeftStickX, false, InputEffect::Button(Buttons::B)))
+        );
+        assert!(!im.is_listening());
+        assert_eq!(
+            ib.gamepad_axes.get(&Axis::LeftStickX),
+            Some(&(Buttons::B, -0.5, 0.1))
+        );
+
+        // Logical axes can't be bound to gamepad axes this way.
+        im.start_listening(InputEffect::Axis(Axes::Horz, true));
+        assert_eq!(
+            im.capture_gamepad_axis(&mut ib, Axis::RightZ, 1.0, 0.5, 0.1),
+            None
+        );
+        assert!(im.is_listening());
+    }
+
+    #[test]
+    fn test_chords() {
+        let ib = make_input_binding()
+            .bind_input_to_button(
+                InputType::KeyChord(KeyMods::CTRL, KeyCode::Z),
+                Buttons::Select,
+            )
+            .bind_input_to_button(
+                InputType::MouseChord(KeyMods::SHIFT, MouseButton::Left),
+                Buttons::B,
+            );
+        let mut im: InputState<Axes, Buttons> = InputState::new();
+        let z = InputType::KeyEvent(KeyCode::Z);
+        let ctrl = InputType::KeyEvent(KeyCode::LControl);
+
+        // Plain Z still works.
+        im.update_input_down(&ib, z, KeyMods::NONE);
+        assert!(im.get_button_down(Buttons::A));
+        im.update_input_up(z);
+        assert!(im.get_button_up(Buttons::A));
+
+        // Ctrl+Z, releasing ctrl first.
+        im.update_input_down(&ib, ctrl, KeyMods::CTRL);
+        assert_eq!(
+            im.update_input_down(&ib, z, KeyMods::CTRL),
+            Some(InputEffect::Button(Buttons::Select))
+        );
+        assert!(im.get_button_down(Buttons::Select));
+        assert!(im.get_button_up(Buttons::A));
+        im.update_input_up(ctrl);
+        assert!(im.get_button_up(Buttons::Select));
+        // Z is still physically down but shouldn't do anything now.
+        im.update_input_up(z);
+        assert!(im.get_button_up(Buttons::Select));
+        assert!(im.get_button_up(Buttons::A));
+
+        // Ctrl+Z, releasing Z first; modifiers have to match exactly.
+        im.update_input_down(&ib, z, KeyMods::CTRL);
+        im.update_input_up(z);
+        assert!(im.get_button_up(Buttons::Select));
+        im.update_input_down(&ib, z, KeyMods::CTRL | KeyMods::SHIFT);
+        assert!(im.get_button_down(Buttons::A));
+        im.update_input_up(z);
+
+        // Shift+Click
+        let click = InputType::MouseButtonEvent(MouseButton::Left);
+        im.update_input_down(&ib, click, KeyMods::SHIFT);
+        assert!(im.get_button_down(Buttons::B));
+        im.update_input_up(click);
+        assert!(im.get_button_up(Buttons::B));
+    }
+
+    #[test]
+    fn test_buffered_press() {
+        let mut im: InputState<Axes, Buttons> = InputState::new();
+        assert!(!im.get_button_pressed_buffered(Buttons::A, 0.2));
+
+        // Tap the button, a couple frames pass.
+        im.update_button_down(Buttons::A);
+        im.update(0.05);
+        im.update_button_up(Buttons::A);
+        im.update(0.05);
+        assert!(!im.get_button_pressed(Buttons::A));
+        assert!(im.get_button_pressed_buffered(Buttons::A, 0.2));
+        assert!(!im.get_button_pressed_buffered(Buttons::A, 0.05));
+
+        // Consumed presses only count once.
+        assert!(im.take_button_pressed_buffered(Buttons::A, 0.2));
+        assert!(!im.get_button_pressed_buffered(Buttons::A, 0.2));
+
+        // And old ones expire.
+        im.update_button_down(Buttons::A);
+        im.update(0.3);
+        assert!(!im.get_button_pressed_buffered(Buttons::A, 0.2));
+    }
+
+    #[test]
+    fn test_hold_time() {
+        let mut im: InputState<Axes, Buttons> = InputState::new();
+        im.update_button_down(Buttons::B);
+        assert_eq!(im.get_button_hold_time(Buttons::B), 0.0);
+        let mut fired = 0;
+        for _ in 0..10 {
+            im.update(0.1);
+            if im.get_button_held_for(Buttons::B, 0.45) {
+                fired += 1;
+                assert!(im.get_button_hold_time(Buttons::B) >= 0.45);
+            }
+        }
+        assert_eq!(fired, 1);
+        assert!(im.get_button_hold_time(Buttons::B) > 0.9);
+
+        im.update_button_up(Buttons::B);
+        assert_eq!(im.get_button_hold_time(Buttons::B), 0.0);
+        im.update(0.1);
+        im.update_button_down(Buttons::B);
+        im.update(0.1);
+        assert!(im.get_button_hold_time(Buttons::B) < 0.15);
+    }
+
+    #[test]
+    fn test_consume_then_hold() {
+        // Jump on the buffered press, then charge while it's held.
+        let mut im: InputState<Axes, Buttons> = InputState::new();
+        im.update_button_down(Buttons::B);
+        im.update(0.25);
+        assert!(im.take_button_pressed_buffered(Buttons::B, 0.5));
+        assert_eq!(im.get_button_hold_time(Buttons::B), 0.25);
+        im.update(0.25);
+        assert!(im.get_button_held_for(Buttons::B, 0.5));
+        assert!(!im.get_button_pressed_buffered(Buttons::B, 0.5));
+    }
+
+    #[test]
+    fn test_button_repeat() {
+        let mut im: InputState<Axes, Buttons> = InputState::new();
+        im.set_button_repeat(Buttons::A, 0.5, 0.2);
+        im.update_button_down(Buttons::A);
+        let mut fired = vec![im.get_button_pressed(Buttons::A)];
+        for _ in 0..10 {
+            im.update(0.1);
+            fired.push(im.get_button_pressed(Buttons::A));
+        }
+        // Pressed, then repeats at 0.5, 0.7 and 0.9 seconds.
+        let frames: Vec<_> = (0..fired.len()).filter(|i| fired[*i]).collect();
+        assert_eq!(frames, vec![0, 5, 7, 9]);
+
+        im.update_button_up(Buttons::A);
+        assert!(!im.get_button_pressed(Buttons::A));
+        im.clear_button_repeat(Buttons::A);
+        im.update_button_down(Buttons::A);
+        for _ in 0..10 {
+            im.update(0.1);
+            assert!(!im.get_button_pressed(Buttons::A));
+        }
+    }
+
+    #[test]
+    fn test_cooldown() {
+        let mut im: InputState<Axes, Buttons> = InputState::new();
+        im.set_cooldown(Buttons::A, 0.25);
+        im.update_button_down(Buttons::A);
+        assert!(im.get_button_pressed(Buttons::A));
+        assert_eq!(im.cooldown_remaining(Buttons::A), 0.25);
+        im.update_button_up(Buttons::A);
+        im.update(0.1);
+
+        // Too soon.
+        im.update_button_down(Buttons::A);
+        assert!(!im.get_button_pressed(Buttons::A));
+        assert!(im.get_button_down(Buttons::A));
+        im.update_button_up(Buttons::A);
+        im.update(0.2);
+        assert_eq!(im.cooldown_remaining(Buttons::A), 0.0);
+
+        im.update_button_down(Buttons::A);
+        assert!(im.get_button_pressed(Buttons::A));
+
+        // Auto-repeat respects it too.
+        im.update_button_up(Buttons::A);
+        im.reset_input_state();
+        im.set_button_repeat(Buttons::A, 0.1, 0.1);
+        im.update_button_down(Buttons::A);
+        let mut fired = 1;
+        for _ in 0..10 {
+            im.update(0.1);
+            if im.get_button_pressed(Buttons::A) {
+                fired += 1;
+            }
+        }
+        assert_eq!(fired, 4);
+    }
+
+    #[test]
+    fn test_axis_config() {
+        let ib = make_input_binding().configure_axis(
+            Axes::Horz,
+            AxisConfig {
+                acceleration: 10.0,
+                snap: true,
+                sensitivity: 2.0,
+                ..AxisConfig::default()
+            },
+        );
+        let mut im: InputState<Axes, Buttons> = InputState::new();
+        im.apply_axis_configs(&ib);
+
+        im.update_axis_start(Axes::Horz, true);
+        im.update(0.05);
+        // Position is 0.5, the curve makes it 0.25.
+        assert!((im.get_axis(Axes::Horz) - 0.25).abs() < 0.001);
+
+        // Reversing snaps to 0 rather than sliding through.
+        im.update_axis_start(Axes::Horz, false);
+        assert_eq!(im.get_axis(Axes::Horz), 0.0);
+        im.update(0.05);
+        assert!((im.get_axis(Axes::Horz) + 0.25).abs() < 0.001);
+
+        // Other axes are untouched.
+        assert_eq!(ib.axis_config(&Axes::Vert), AxisConfig::default());
+    }
+
+    #[test]
+    fn test_axis_invert_and_remap() {
+        let ib = make_input_binding()
+            .invert_axis(Axes::Vert)
+            .remap_axis(Axes::Horz, 0.0, 1.0);
+        let mut im: InputState<Axes, Buttons> = InputState::new();
+        im.apply_axis_configs(&ib);
+        assert_eq!(im.get_axis(Axes::Horz), 0.5);
+        assert_eq!(im.get_axis(Axes::Vert), 0.0);
+
+        im.update_axis_start(Axes::Vert, true);
+        im.update_axis_start(Axes::Horz, true);
+        im.update(1.0);
+        assert_eq!(im.get_axis(Axes::Vert), -1.0);
+        assert_eq!(im.get_axis(Axes::Horz), 1.0);
+        im.update_axis_start(Axes::Horz, false);
+        im.update(1.0);
+        assert_eq!(im.get_axis(Axes::Horz), 0.0);
+    }
+
+    #[test]
+    fn test_input_contexts() {
+        let menu = InputBinding::new()
+            .bind_key_to_button(KeyCode::Return, Buttons::Select)
+            .bind_key_to_button(KeyCode::Z, Buttons::B);
+        let mut im: InputState<Axes, Buttons> = InputState::new();
+        let z = InputType::KeyEvent(KeyCode::Z);
+        let up = InputType::KeyEvent(KeyCode::Up);
+        im.push_context(InputContext::new("gameplay", make_input_binding()));
+
+        // Hold up while the menu opens.
+        im.update_context_input_down(up, KeyMods::NONE);
+        im.push_context(InputContext::new("menu", menu));
+        assert_eq!(im.current_context().unwrap().name(), "menu");
+        assert_eq!(
+            im.update_context_input_down(z, KeyMods::NONE),
+            Some(InputEffect::Button(Buttons::B))
+        );
+        assert!(im.get_button_up(Buttons::A));
+        im.update_input_up(z);
+        // X is only bound in gameplay, which is blocked.
+        let x = InputType::KeyEvent(KeyCode::X);
+        assert_eq!(im.update_context_input_down(x, KeyMods::NONE), None);
+
+        // Releasing up while the menu is open still releases it.
+        im.update_input_up(up);
+        assert_eq!(im.get_axis_raw(Axes::Vert), 0.0);
+
+        // Now let gameplay keys through the menu.
+        let menu = im.pop_context().unwrap().pass_through(true);
+        im.push_context(menu);
+        im.update_context_input_down(up, KeyMods::NONE);
+        assert!(im.get_axis_raw(Axes::Vert) > 0.0);
+        im.update_context_input_down(z, KeyMods::NONE);
+        assert!(im.get_button_down(Buttons::B));
+        assert!(im.get_button_up(Buttons::A));
+    }
+
+    #[test]
+    fn test_consuming_contexts() {
+        // Two players sharing a keyboard, both bound to Return.
+        let p1 = InputBinding::new()
+            .bind_key_to_button(KeyCode::Return, Buttons::Start)
+            .bind_key_to_button(KeyCode::Space, Buttons::A);
+        let p2 = InputBinding::new()
+            .bind_key_to_button(KeyCode::Return, Buttons::Select)
+            .bind_key_to_button(KeyCode::Space, Buttons::B);
+        let mut im: InputState<Axes, Buttons> = InputState::new();
+        im.push_context(InputContext::new("p2", p2));
+        im.push_context(
+            InputContext::new("p1", p1)
+                .pass_through(true)
+                .consuming(false)
+                .consume(InputEffect::Button(Buttons::A)),
+        );
+        let ret = InputType::KeyEvent(KeyCode::Return);
+        let space = InputType::KeyEvent(KeyCode::Space);
+        assert_eq!(im.conflicts(), vec![space]);
+
+        // Return isn't consumed, so both players get it.
+        assert_eq!(
+            im.update_context_input_down(ret, KeyMods::NONE),
+            Some(InputEffect::Button(Buttons::Start))
+        );
+        assert!(im.get_button_down(Buttons::Start));
+        assert!(im.get_button_down(Buttons::Select));
+        im.update_input_up(ret);
+        assert!(im.get_button_up(Buttons::Start));
+        assert!(im.get_button_up(Buttons::Select));
+
+        // Space is, so only the first does.
+        im.update_context_input_down(space, KeyMods::NONE);
+        assert!(im.get_button_down(Buttons::A));
+        assert!(im.get_button_up(Buttons::B));
+    }
+
+    #[test]
+    fn test_device_tracking() {
+        let ib = make_input_binding();
+        let mut im: InputState<Axes, Buttons> = InputState::new();
+        assert_eq!(im.last_device(), None);
+        assert!(!im.any_input_pressed());
+
+        // Unbound keys count as any input.
+        im.update_input_down(&ib, InputType::KeyEvent(KeyCode::Q), KeyMods::NONE);
+        assert!(im.any_input_pressed());
+        assert_eq!(im.last_device(), Some(InputDevice::Keyboard));
+        assert_eq!(im.last_device_for_player(0), Some(InputDevice::Keyboard));
+        im.update(0.1);
+        assert!(!im.any_input_pressed());
+
+        // Moving the mouse switches devices but isn't a press.
+        im.set_keyboard_player(1);
+        im.update_mouse_motion(1.0, 1.0, 1.0, 1.0);
+        assert!(!im.any_input_pressed());
+        assert_eq!(im.last_device(), Some(InputDevice::Mouse));
+        assert_eq!(im.last_device_for_player(1), Some(InputDevice::Mouse));
+        assert_eq!(im.last_device_for_player(0), Some(InputDevice::Keyboard));
+
+        // Buttons and axes fed in directly count as the keyboard.
+        im.update_button_down(Buttons::Start);
+        assert!(im.any_input_pressed());
+        assert_eq!(im.last_device(), Some(InputDevice::Keyboard));
+        assert_eq!(im.last_device_for_player(1), Some(InputDevice::Keyboard));
+        im.update_mouse_motion(1.0, 1.0, 1.0, 1.0);
+        im.update_axis_start(Axes::Horz, true);
+        assert_eq!(im.last_device(), Some(InputDevice::Keyboard));
+    }
+
+    #[test]
+    fn test_bindings_for() {
+        let ib = make_input_binding()
+            .bind_input_to_button(
+                InputType::MouseButtonEvent(MouseButton::Left),
+                Buttons::Select,
+            )
+            .bind_input_to_button(
+                InputType::KeyChord(KeyMods::CTRL, KeyCode::A),
+                Buttons::Select,
+            );
+        assert_eq!(
+            ib.bindings_for(&InputEffect::Button(Buttons::Select)),
+            vec![
+                InputType::KeyEvent(KeyCode::LShift),
+                InputType::KeyEvent(KeyCode::RShift),
+                InputType::KeyChord(KeyMods::CTRL, KeyCode::A),
+                InputType::MouseButtonEvent(MouseButton::Left),
+            ]
+        );
+        assert_eq!(
+            ib.bindings_for(&InputEffect::Axis(Axes::Vert, false)),
+            vec![InputType::KeyEvent(KeyCode::Down)]
+        );
+        assert_eq!(ib.bindings_for(&InputEffect::Button(Buttons::B)).len(), 1);
+    }
+
+    #[test]
+    fn test_binding_conflicts() {
+        let mut ib = make_input_binding();
+        assert_eq!(
+            ib.try_bind_key_to_button(KeyCode::Z, Buttons::B),
+            Err(InputEffect::Button(Buttons::A))
+        );
+        assert_eq!(
+            ib.resolve(KeyCode::Z),
+            Some(InputEffect::Button(Buttons::A))
+        );
+        assert_eq!(ib.try_bind_key_to_button(KeyCode::Z, Buttons::A), Ok(()));
+        assert_eq!(
+            ib.try_bind_key_to_axis(KeyCode::D, Axes::Horz, true),
+            Ok(())
+        );
+
+        let other = InputBinding::new()
+            .bind_key_to_button(KeyCode::Z, Buttons::A)
+            .bind_key_to_button(KeyCode::D, Buttons::B)
+            .bind_key_to_button(KeyCode::X, Buttons::Start);
+        assert_eq!(
+            ib.conflicts_with(&other),
+            vec![
+                InputType::KeyEvent(KeyCode::D),
+                InputType::KeyEvent(KeyCode::X)
+            ]
+        );
+
+        let mut im: InputState<Axes, Buttons> = InputState::new();
+        im.push_context(InputContext::new("p1", ib));
+        im.push_context(InputContext::new("p2", other));
+        assert!(im.conflicts().is_empty());
+        let top = im.pop_context().unwrap().pass_through(true);
+        im.push_context(top);
+        assert_eq!(im.conflicts().len(), 2);
+    }
+
+    #[test]
+    fn test_mouse_axes() {
+        let ib = make_input_binding()
+            .bind_mouse_motion_to_axis(MouseAxis::X, Axes::Horz, 0.1)
+            .configure_axis(
+                Axes::Horz,
+                AxisConfig {
+                    smoothing: 0.5,
+                    ..AxisConfig::default()
+                },
+            );
+        let mut im: InputState<Axes, Buttons> = InputState::new();
+        im.apply_axis_configs(&ib);
+
+        im.update_mouse_motion_with(&ib, 5.0, 0.0, 5.0, 0.0);
+        im.update(1.0 / 60.0);
+        // Halfway to 0.5
+        assert!((im.get_axis(Axes::Horz) - 0.25).abs() < 0.001);
+        assert_eq!(im.get_axis(Axes::Vert), 0.0);
+
+        // The mouse stopping lets the axis decay to 0.
+        for _ in 0..20 {
+            im.update(1.0 / 60.0);
+        }
+        assert_eq!(im.get_axis(Axes::Horz), 0.0);
+    }
+
+    #[test]
+    fn test_analog_buttons() {
+        let presses: Vec<_> = [0.2, 0.55, 0.45, 0.35, 0.45, 0.6]
+            .iter()
+            .scan(false, |pressed, v| {
+                *pressed = analog_pressed(*pressed, *v, 0.5, 0.1);
+                Some(*pressed)
+            })
+            .collect();
+        assert_eq!(presses, vec![false, true, true, false, false, true]);
+
+        // The negative half of a stick.
+        assert!(analog_pressed(false, -0.8, -0.5, 0.1));
+        assert!(!analog_pressed(false, 0.8, -0.5, 0.1));
+    }
+
+    #[test]
+    fn test_sticks() {
+        let ib = make_input_binding().bind_axes_to_stick("move", Axes::Horz, Axes::Vert);
+        assert_eq!(ib.stick("move"), Some((Axes::Horz, Axes::Vert)));
+        let mut im: InputState<Axes, Buttons> = InputState::new();
+        im.apply_axis_configs(&ib);
+
+        im.update_axis_start(Axes::Horz, true);
+        im.update(1.0);
+        let v = math::vector2_to_mint(im.get_stick("move"));
+        assert_eq!((v.x, v.y), (1.0, 0.0));
+
+        // Diagonals are no longer than straight lines.
+        im.update_axis_start(Axes::Vert, true);
+        im.update(1.0);
+        let v = math::vector2_to_mint(im.get_stick("move"));
+        assert!((v.x - 0.5f32.sqrt()).abs() < 0.001);
+        assert!((v.y - 0.5f32.sqrt()).abs() < 0.001);
+
+        let v = math::vector2_to_mint(im.get_stick("look"));
+        assert_eq!((v.x, v.y), (0.0, 0.0));
+    }
 }
@@ -0,0 +1,469 @@
+//! Records the logical input fed to an `InputState` so it can be
+//! played back later, frame for frame.  Good for attract-mode demos,
+//! automated gameplay tests, and capturing how to reproduce a bug.
+//!
+//! Feed your input through an `InputRecorder` instead of straight
+//! into the `InputState`, and call `next_frame()` once per update:
+//!
+//! ```rust,ignore
+//! // In your key handlers:
+//! let input = InputType::KeyEvent(keycode);
+//! self.recorder.apply_input_down(&mut self.input, &self.bindings, input, keymods);
+//! // ...
+//! self.recorder.apply_input_up(&mut self.input, input);
+//!
+//! // In your update handler:
+//! self.input.update(dt);
+//! self.recorder.next_frame();
+//! ```
+//!
+//! Everything else you'd feed an `InputState` goes through the
+//! recorder too: effects you resolve yourself through `apply()` or
+//! `apply_from()`, analog axes, the mouse and touches through
+//! `apply_input()`, and input that depends on an `InputBinding`
+//! through `apply_mouse_motion_with()` and `apply_gamepad_axis()`.
+//! Anything passed straight to the `InputState` isn't recorded.
+//! Keys and buttons are recorded as the effects they triggered along
+//! with the input itself, so playback doesn't need the bindings and
+//! still knows which of several held keys was let go.
+//!
+//! Then hand the finished `InputRecording` to an `InputPlayer` and
+//! call its `update()` once per frame in place of your event handlers.
+//! Gamepad ids can't be saved, so gamepad axes are recorded as the
+//! button presses they caused, and which gamepad was used last isn't
+//! reproduced.
+//! With the `serialize` feature enabled, recordings can be serialized.
+//!
+//! Playback only reproduces a game's behavior if the game is
+//! otherwise deterministic: use a fixed timestep and a seeded
+//! `random::Random`.
+
+use std::hash::Hash;
+
+use ggez::event::winit_event::TouchPhase;
+use ggez::event::{Axis, GamepadId, MouseButton};
+#[cfg(feature = "serialize")]
+use serde::{Deserialize, Serialize};
+
+use ggez::event::KeyMods;
+
+use super::{InputBinding, InputEffect, InputState, InputType, MouseAxis};
+
+/// One thing fed to an `InputState`, matching its `update_*()`
+/// methods.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub enum RecordedInput<Axes, Buttons>
+where
+    Axes: Eq + Hash + Clone,
+    Buttons: Eq + Hash + Clone,
+{
+    /// `update_effect()`, and whether the effect started.
+    Effect(InputEffect<Axes, Buttons>, bool),
+    /// `update_effect_from()`: an effect, whether it started, and the
+    /// physical input it came from.
+    EffectFrom(InputEffect<Axes, Buttons>, bool, InputType),
+    /// `update_axis_analog()`.
+    AxisAnalog(Axes, f32),
+    /// A mouse-driven axis being nudged by mouse motion.
+    AxisImpulse(Axes, f32),
+    /// `update_mouse_motion()`: position and how far it moved.
+    MouseMotion(f32, f32, f32, f32),
+    /// `update_mouse_button_down()` or `update_mouse_button_up()`.
+    MouseButton(MouseButton, bool),
+    /// `update_mouse_scroll()`.
+    MouseScroll(f32, f32),
+    /// `update_touch()`: id, phase and position.
+    Touch(u64, TouchPhase, f32, f32),
+}
+
+impl<Axes, Buttons> RecordedInput<Axes, Buttons>
+where
+    Axes: Eq + Hash + Clone,
+    Buttons: Eq + Hash + Clone,
+{
+    fn apply_to(&self, state: &mut InputState<Axes, Buttons>) {
+        match self.clone() {
+            RecordedInput::Effect(effect, started) => state.update_effect(effect, started),
+            RecordedInput::EffectFrom(effect, started, source) => {
+                state.update_effect_from(effect, started, source)
+            }
+            RecordedInput::AxisAnalog(axis, value) => state.update_axis_analog(axis, value),
+            RecordedInput::AxisImpulse(axis, amount) => state.add_axis_impulse(axis, amount),
+            RecordedInput::MouseMotion(x, y, dx, dy) => state.update_mouse_motion(x, y, dx, dy),
+            RecordedInput::MouseButton(button, true) => state.update_mouse_button_down(button),
+            RecordedInput::MouseButton(button, false) => state.update_mouse_button_up(button),
+            RecordedInput::MouseScroll(x, y) => state.update_mouse_scroll(x, y),
+            RecordedInput::Touch(id, phase, x, y) => state.update_touch(id, phase, x, y),
+        }
+    }
+}
+
+/// One input event and the frame it happened on.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct InputRecord<Axes, Buttons>
+where
+    Axes: Eq + Hash + Clone,
+    Buttons: Eq + Hash + Clone,
+{
+    pub frame: u64,
+    pub input: RecordedInput<Axes, Buttons>,
+}
+
+/// A complete recorded input log.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct InputRecording<Axes, Buttons>
+where
+    Axes: Eq + Hash + Clone,
+    Buttons: Eq + Hash + Clone,
+{
+    /// The events, in order.
+    pub records: Vec<InputRecord<Axes, Buttons>>,
+    /// How many frames long the recording is.
+    pub frames: u64,
+}
+
+/// Captures input into an `InputRecording`.
+#[derive(Debug, Clone)]
+pub struct InputRecorder<Axes, Buttons>
+where
+    Axes: Eq + Hash + Clone,
+    Buttons: Eq + Hash + Clone,
+{
+    recording: InputRecording<Axes, Buttons>,
+}
+
+impl<Axes, Buttons> InputRecorder<Axes, Buttons>
+where
+    Axes: Eq + Hash + Clone,
+    Buttons: Eq + Hash + Clone,
+{
+    pub fn new() -> Self {
+        InputRecorder {
+            recording: InputRecording {
+                records: Vec::new(),
+                frames: 0,
+            },
+        }
+    }
+
+    /// The frame input is currently being recorded for.
+    pub fn frame(&self) -> u64 {
+        self.recording.frames
+    }
+
+    /// Records an input event on the current frame.
+    pub fn record(&mut self, input: RecordedInput<Axes, Buttons>) {
+        self.recording.records.push(InputRecord {
+            frame: self.recording.frames,
+            input,
+        });
+    }
+
+    /// Records an input event and applies it to the given state.
+    pub fn apply_input(
+        &mut self,
+        state: &mut InputState<Axes, Buttons>,
+        input: RecordedInput<Axes, Buttons>,
+    ) {
+        input.apply_to(state);
+        self.record(input);
+    }
+
+    /// Records an `InputEffect` and applies it to the given state,
+    /// like `InputState::update_effect()`.
+    pub fn apply(
+        &mut self,
+        state: &mut InputState<Axes, Buttons>,
+        effect: InputEffect<Axes, Buttons>,
+        started: bool,
+    ) {
+        self.apply_input(state, RecordedInput::Effect(effect, started));
+    }
+
+    /// Records an `InputEffect` resolved from `source` and applies it
+    /// to the given state, like `InputState::update_effect_from()`.
+    pub fn apply_from(
+        &mut self,
+        state: &mut InputState<Axes, Buttons>,
+        effect: InputEffect<Axes, Buttons>,
+        started: bool,
+        source: InputType,
+    ) {
+        self.apply_input(state, RecordedInput::EffectFrom(effect, started, source));
+    }
+
+    /// Presses a key or mouse button, like
+    /// `InputState::update_input_down()`, recording whatever it
+    /// triggered.
+    pub fn apply_input_down(
+        &mut self,
+        state: &mut InputState<Axes, Buttons>,
+        binding: &InputBinding<Axes, Buttons>,
+        input: InputType,
+        mods: KeyMods,
+    ) -> Option<InputEffect<Axes, Buttons>> {
+        let input = input.without_mods();
+        let repeat = state.held_inputs.contains_key(&input);
+        let first = state.update_input_down(binding, input, mods);
+        if !repeat {
+            let held = state.held_inputs.get(&input).cloned().unwrap_or_default();
+            for (_, effect) in held {
+                self.record(RecordedInput::EffectFrom(effect, true, input));
+            }
+        }
+        first
+    }
+
+    /// Releases a key or mouse button, like
+    /// `InputState::update_input_up()`, recording whatever it
+    /// released.
+    pub fn apply_input_up(&mut self, state: &mut InputState<Axes, Buttons>, input: InputType) {
+        let before = state.held_inputs.clone();
+        state.update_input_up(input);
+        for (source, effects) in before {
+            if !state.held_inputs.contains_key(&source) {
+                for (_, effect) in effects {
+                    self.record(RecordedInput::EffectFrom(effect, false, source));
+                }
+            }
+        }
+    }
+
+    /// Records mouse motion and applies it to the given state, like
+    /// `InputState::update_mouse_motion_with()`.
+    pub fn apply_mouse_motion_with(
+        &mut self,
+        state: &mut InputState<Axes, Buttons>,
+        binding: &InputBinding<Axes, Buttons>,
+        x: f32,
+        y: f32,
+        dx: f32,
+        dy: f32,
+    ) {
+        self.apply_input(state, RecordedInput::MouseMotion(x, y, dx, dy));
+        for (motion, (axis, sensitivity)) in &binding.mouse_axes {
+            let delta = match motion {
+                MouseAxis::X => dx,
+                MouseAxis::Y => dy,
+            };
+            self.apply_input(
+                state,
+                RecordedInput::AxisImpulse(axis.clone(), delta * sensitivity),
+            );
+        }
+    }
+
+    /// Applies a gamepad axis to the given state, like
+    /// `InputState::update_gamepad_axis()`, recording the button
+    /// press or release it causes, if any.
+    pub fn apply_gamepad_axis(
+        &mut self,
+        state: &mut InputState<Axes, Buttons>,
+        binding: &InputBinding<Axes, Buttons>,
+        axis: Axis,
+        value: f32,
+        id: GamepadId,
+    ) {
+        if let Some((button, pressed)) = state.gamepad_axis_change(binding, axis, value, id) {
+            self.apply(state, InputEffect::Button(button), pressed);
+        }
+    }
+
+    /// Moves on to the next frame.
+    pub fn next_frame(&mut self) {
+        self.recording.frames += 1;
+    }
+
+    /// Stops recording and returns what was recorded.
+    pub fn finish(self) -> InputRecording<Axes, Buttons> {
+        self.recording
+    }
+}
+
+impl<Axes, Buttons> Default for InputRecorder<Axes, Buttons>
+where
+    Axes: Eq + Hash + Clone,
+    Buttons: Eq + Hash + Clone,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Plays an `InputRecording` back into an `InputState`.
+#[derive(Debug, Clone)]
+pub struct InputPlayer<Axes, Buttons>
+where
+    Axes: Eq + Hash + Clone,
+    Buttons: Eq + Hash + Clone,
+{
+    recording: InputRecording<Axes, Buttons>,
+    frame: u64,
+    next_record: usize,
+}
+
+impl<Axes, Buttons> InputPlayer<Axes, Buttons>
+where
+    Axes: Eq + Hash + Clone,
+    Buttons: Eq + Hash + Clone,
+{
+    pub fn new(recording: InputRecording<Axes, Buttons>) -> Self {
+        InputPlayer {
+            recording,
+            frame: 0,
+            next_record: 0,
+        }
+    }
+
+    /// The frame that will be played next.
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    /// Whether every frame of the recording has been played.
+    pub fn is_finished(&self) -> bool {
+        self.frame >= self.recording.frames
+    }
+
+    /// Applies all the input events for the current frame to the
+    /// given state and moves on to the next frame.  Call this once
+    /// per frame, where you'd otherwise handle input events.
+    pub fn update(&mut self, state: &mut InputState<Axes, Buttons>) {
+        while let Some(record) = self.recording.records.get(self.next_record) {
+            if record.frame > self.frame {
+                break;
+            }
+            record.input.apply_to(state);
+            self.next_record += 1;
+        }
+        self.frame += 1;
+    }
+
+    /// Starts playing from the beginning again.
+    pub fn rewind(&mut self) {
+        self.frame = 0;
+        self.next_record = 0;
+    }
+
+    /// Gets the recording back.
+    pub fn into_recording(self) -> InputRecording<Axes, Buttons> {
+        self.recording
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ggez::event::KeyCode;
+
+    #[derive(Hash, Eq, PartialEq, Copy, Clone, Debug)]
+    #[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+    enum Buttons {
+        A,
+    }
+
+    #[derive(Hash, Eq, PartialEq, Copy, Clone, Debug)]
+    #[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+    enum Axes {
+        Horz,
+        Vert,
+    }
+
+    fn snapshot(state: &InputState<Axes, Buttons>) -> (bool, f32, f32, bool, f32, usize) {
+        (
+            state.get_button_pressed(Buttons::A),
+            state.get_axis(Axes::Horz),
+            state.get_axis(Axes::Vert),
+            state.get_mouse_button_down(MouseButton::Left),
+            state.get_mouse_position().x,
+            state.get_touches().count(),
+        )
+    }
+
+    #[test]
+    fn test_record_and_replay() {
+        let binding = InputBinding::new()
+            .bind_key_to_axis(KeyCode::Up, Axes::Vert, true)
+            .bind_key_to_axis(KeyCode::W, Axes::Vert, true);
+        let up = InputType::KeyEvent(KeyCode::Up);
+        let w = InputType::KeyEvent(KeyCode::W);
+        let mut live: InputState<Axes, Buttons> = InputState::new();
+        let mut recorder = InputRecorder::new();
+        let mut live_log = Vec::new();
+        for frame in 0..20 {
+            match frame {
+                2 => recorder.apply(&mut live, InputEffect::Button(Buttons::A), true),
+                3 => {
+                    recorder.apply(&mut live, InputEffect::Button(Buttons::A), false);
+                    recorder.apply(&mut live, InputEffect::Axis(Axes::Horz, true), true);
+                }
+                5 => {
+                    recorder.apply_input(&mut live, RecordedInput::AxisAnalog(Axes::Vert, 0.5));
+                    recorder.apply_input(
+                        &mut live,
+                        RecordedInput::MouseButton(MouseButton::Left, true),
+                    );
+                    recorder
+                        .apply_input(&mut live, RecordedInput::MouseMotion(10.0, 20.0, 1.0, 2.0));
+                }
+                7 => {
+                    recorder.apply_input(&mut live, RecordedInput::AxisAnalog(Axes::Vert, 0.0));
+                    recorder.apply_input(
+                        &mut live,
+                        RecordedInput::Touch(1, TouchPhase::Started, 5.0, 5.0),
+                    );
+                }
+                10 => recorder.apply(&mut live, InputEffect::Axis(Axes::Horz, true), false),
+                // Two keys holding the same way, let go one at a time.
+                12 => {
+                    recorder.apply_input_down(&mut live, &binding, up, KeyMods::NONE);
+                    recorder.apply_input_down(&mut live, &binding, w, KeyMods::NONE);
+                }
+                13 => {
+                    recorder.apply_input_down(&mut live, &binding, w, KeyMods::NONE);
+                    recorder.apply_input_up(&mut live, up);
+                }
+                16 => recorder.apply_input_up(&mut live, w),
+                _ => (),
+            }
+            live_log.push(snapshot(&live));
+            live.update(0.1);
+            recorder.next_frame();
+        }
+        let recording = recorder.finish();
+        assert_eq!(recording.frames, 20);
+        assert_eq!(recording.records.len(), 13);
+
+        let mut replayed: InputState<Axes, Buttons> = InputState::new();
+        let mut player = InputPlayer::new(recording);
+        let mut replay_log = Vec::new();
+        while !player.is_finished() {
+            player.update(&mut replayed);
+            replay_log.push(snapshot(&replayed));
+            replayed.update(0.1);
+        }
+        assert_eq!(live_log, replay_log);
+        // W kept the axis going after Up was let go.
+        assert!(live_log[15].2 > live_log[13].2);
+        assert!(live_log[19].2 < live_log[16].2);
+    }
+
+    #[cfg(feature = "serialize")]
+    #[test]
+    fn test_recording_serialization() {
+        let mut recorder: InputRecorder<Axes, Buttons> = InputRecorder::new();
+        recorder.record(RecordedInput::EffectFrom(
+            InputEffect::Button(Buttons::A),
+            true,
+            InputType::KeyChord(KeyMods::CTRL | KeyMods::SHIFT, KeyCode::S),
+        ));
+        recorder.next_frame();
+        let recording = recorder.finish();
+        let json = serde_json::to_string(&recording).unwrap();
+        let loaded: InputRecording<Axes, Buttons> = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded, recording);
+    }
+}
@@ -0,0 +1,266 @@
+//! Gamepad rumble, by player rather than by device.
+//!
+//! Ask a `Rumble` to shake a player's gamepad for a while and it
+//! works out which gamepad that is from the `InputState`'s player
+//! assignments, queues up requests, and stops the motors when time
+//! runs out.  The actual shaking is done by a `RumbleBackend`;
+//! `GilrsRumble` is one that uses gilrs' force feedback.
+//!
+//! ```rust,ignore
+//! self.rumble.rumble(player, 0.8, 0.25);
+//! // Once per frame:
+//! self.rumble.update(dt);
+//! self.rumble.apply(&self.input, &mut self.rumble_backend)?;
+//! ```
+
+use std::collections::{HashMap, VecDeque};
+use std::hash::Hash;
+
+use ggez::event::GamepadId;
+use ggez::input::gamepad::{self, gilrs};
+use ggez::{Context, GameError, GameResult};
+
+use super::InputState;
+
+/// Something that can actually make a gamepad rumble.
+pub trait RumbleBackend {
+    /// Sets how hard a gamepad's motors should run, in [0, 1].
+    /// 0 stops them.
+    fn set_rumble(&mut self, gamepad: GamepadId, strength: f32) -> GameResult;
+
+    /// Called once per `Rumble::apply()`, for backends that need
+    /// to do housekeeping.
+    fn update(&mut self) -> GameResult {
+        Ok(())
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct RumbleRequest {
+    strength: f32,
+    remaining: f32,
+}
+
+#[derive(Debug, Clone, Default)]
+struct PlayerRumble {
+    current: Option<RumbleRequest>,
+    queue: VecDeque<RumbleRequest>,
+}
+
+/// Per-player rumble requests.  See the module docs.
+#[derive(Debug, Clone, Default)]
+pub struct Rumble {
+    players: HashMap<usize, PlayerRumble>,
+    // What we last told the backend for each gamepad.
+    applied: HashMap<GamepadId, f32>,
+}
+
+impl Rumble {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Rumbles a player's gamepad at the given strength, in [0, 1],
+    /// for `duration` seconds.  If it's already rumbling, this
+    /// starts once the current rumble and any others queued before
+    /// it are done.
+    pub fn rumble(&mut self, player: usize, strength: f32, duration: f32) {
+        let request = RumbleRequest {
+            strength: strength.clamp(0.0, 1.0),
+            remaining: duration,
+        };
+        let p = self.players.entry(player).or_default();
+        if p.current.is_none() {
+            p.current = Some(request);
+        } else {
+            p.queue.push_back(request);
+        }
+    }
+
+    /// Rumbles a player's gamepad right away, dropping anything
+    /// playing or queued.
+    pub fn rumble_now(&mut self, player: usize, strength: f32, duration: f32) {
+        self.stop(player);
+        self.rumble(player, strength, duration);
+    }
+
+    /// Stops a player's rumble and clears their queue.
+    pub fn stop(&mut self, player: usize) {
+        self.players.remove(&player);
+    }
+
+    /// Stops everyone's rumble.
+    pub fn stop_all(&mut self) {
+        self.players.clear();
+    }
+
+    /// How hard a player's gamepad should currently be rumbling.
+    pub fn strength(&self, player: usize) -> f32 {
+        self.players
+            .get(&player)
+            .and_then(|p| p.current)
+            .map_or(0.0, |r| r.strength)
+    }
+
+    /// Counts down the current rumbles, moving on to queued ones
+    /// as they finish.
+    pub fn update(&mut self, dt: f32) {
+        for p in self.players.values_mut() {
+            let mut dt = dt;
+            while let Some(current) = p.current.as_mut() {
+                if current.remaining > dt {
+                    current.remaining -= dt;
+                    break;
+                }
+                dt -= current.remaining;
+                p.current = p.queue.pop_front();
+            }
+        }
+        self.players.retain(|_, p| p.current.is_some());
+    }
+
+    /// Tells the backend what each assigned gamepad should be doing,
+    /// stopping gamepads that no longer belong to a rumbling player.
+    pub fn apply<Axes, Buttons, B>(
+        &mut self,
+        input: &InputState<Axes, Buttons>,
+        backend: &mut B,
+    ) -> GameResult
+    where
+        Axes: Hash + Eq + Clone,
+        Buttons: Hash + Eq + Clone,
+        B: RumbleBackend,
+    {
+        let mut wanted: HashMap<GamepadId, f32> = HashMap::new();
+        for (player, p) in &self.players {
+            if let (Some(id), Some(current)) = (input.player_gamepad(*player), p.current) {
+                wanted.insert(id, current.strength);
+            }
+        }
+        for (id, _) in self.applied.iter() {
+            if !wanted.contains_key(id) {
+                backend.set_rumble(*id, 0.0)?;
+            }
+        }
+        for (id, strength) in wanted.iter() {
+            if self.applied.get(id) != Some(strength) {
+                backend.set_rumble(*id, *strength)?;
+            }
+        }
+        self.applied = wanted;
+        backend.update()
+    }
+}
+
+/// A `RumbleBackend` using gilrs' force feedback.
+///
+/// ggez doesn't give access to its own gilrs instance, so this opens
+/// a second one.  Gamepads are matched up between the two by
+/// `register_gamepad()`, which you should call when you first see a
+/// gamepad, for example alongside `InputState::connect_gamepad()`.
+pub struct GilrsRumble {
+    gilrs: gilrs::Gilrs,
+    effects: HashMap<GamepadId, gilrs::ff::Effect>,
+}
+
+impl GilrsRumble {
+    pub fn new() -> GameResult<Self> {
+        Ok(GilrsRumble {
+            gilrs: gilrs::Gilrs::new()?,
+            effects: HashMap::new(),
+        })
+    }
+
+    /// Finds the gamepad in our gilrs instance and prepares a rumble
+    /// effect for it.  Does nothing for gamepads without force
+    /// feedback, or ones that are already registered.
+    pub fn register_gamepad(&mut self, ctx: &Context, id: GamepadId) -> GameResult {
+        if self.effects.contains_key(&id) {
+            return Ok(());
+        }
+        let pad = gamepad::gamepad(ctx, id);
+        // Prefer the gamepad with the same id, in case several of the
+        // same model are plugged in, else any with the same uuid.
+        let uuid = pad.uuid();
+        let same_id = self
+            .gilrs
+            .gamepads()
+            .find(|(gid, g)| *gid == pad.id() && g.uuid() == uuid);
+        let found = same_id.or_else(|| self.gilrs.gamepads().find(|(_, g)| g.uuid() == uuid));
+        let gid = match found {
+            Some((gid, g)) if g.is_ff_supported() => gid,
+            _ => return Ok(()),
+        };
+        let effect = gilrs::ff::EffectBuilder::new()
+            .add_effect(gilrs::ff::BaseEffect {
+                kind: gilrs::ff::BaseEffectType::Strong {
+                    magnitude: u16::MAX,
+                },
+                scheduling: gilrs::ff::Replay {
+                    play_for: gilrs::ff::Ticks::from_ms(50),
+                    ..Default::default()
+                },
+                envelope: Default::default(),
+            })
+            .gamepads(&[gid])
+            .repeat(gilrs::ff::Repeat::Infinitely)
+            .finish(&mut self.gilrs)
+            .map_err(ff_error)?;
+        self.effects.insert(id, effect);
+        Ok(())
+    }
+}
+
+fn ff_error(e: gilrs::ff::Error) -> GameError {
+    GameError::GamepadError(format!("Force feedback error: {}", e))
+}
+
+impl RumbleBackend for GilrsRumble {
+    fn set_rumble(&mut self, gamepad: GamepadId, strength: f32) -> GameResult {
+        if let Some(effect) = self.effects.get(&gamepad) {
+            if strength > 0.0 {
+                effect.set_gain(strength).map_err(ff_error)?;
+                effect.play().map_err(ff_error)?;
+            } else {
+                effect.stop().map_err(ff_error)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn update(&mut self) -> GameResult {
+        // Nobody reads this instance's events, don't let them pile up.
+        while self.gilrs.next_event().is_some() {}
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rumble_queue() {
+        let mut rumble = Rumble::new();
+        rumble.rumble(1, 0.5, 0.2);
+        rumble.rumble(1, 1.0, 0.1);
+        rumble.rumble(2, 2.0, 0.15);
+        assert_eq!(rumble.strength(0), 0.0);
+        assert_eq!(rumble.strength(1), 0.5);
+        assert_eq!(rumble.strength(2), 1.0);
+
+        rumble.update(0.16);
+        assert_eq!(rumble.strength(1), 0.5);
+        assert_eq!(rumble.strength(2), 0.0);
+        // Time left over carries into the next queued rumble.
+        rumble.update(0.08);
+        assert_eq!(rumble.strength(1), 1.0);
+        rumble.update(0.08);
+        assert_eq!(rumble.strength(1), 0.0);
+
+        rumble.rumble(1, 0.5, 1.0);
+        rumble.rumble_now(1, 0.25, 0.1);
+        rumble.update(0.2);
+        assert_eq!(rumble.strength(1), 0.0);
+    }
+}
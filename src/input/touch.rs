@@ -0,0 +1,301 @@
+//! On-screen controls for touch screens.
+//!
+//! A `VirtualGamepad` maps regions of the screen to logical buttons
+//! and axes, so touch input goes through the same `InputState` as
+//! everything else.  Feed touches to the `InputState` with
+//! `update_touch()`, then once per frame:
+//!
+//! ```rust,ignore
+//! self.pad.update(&mut self.input);
+//! self.input.update(dt);
+//! // ...and in draw():
+//! self.pad.draw(ctx)?;
+//! ```
+
+use std::hash::Hash;
+
+use ggez::graphics::{self, Color, DrawMode, DrawParam, Rect};
+use ggez::{Context, GameResult};
+
+use super::{InputEffect, InputState};
+use crate::math::{self, Point2};
+
+#[derive(Debug, Clone)]
+enum Control<Axes, Buttons> {
+    Button {
+        rect: Rect,
+        button: Buttons,
+        pressed: bool,
+    },
+    Stick {
+        center: Point2,
+        radius: f32,
+        x_axis: Axes,
+        y_axis: Axes,
+        // The touch currently holding the stick, and where it is
+        // relative to the center, in [-1, 1].
+        touch: Option<u64>,
+        offset: (f32, f32),
+    },
+}
+
+/// A set of on-screen buttons and sticks.  See the module docs.
+#[derive(Debug, Clone)]
+pub struct VirtualGamepad<Axes, Buttons>
+where
+    Axes: Hash + Eq + Clone,
+    Buttons: Hash + Eq + Clone,
+{
+    controls: Vec<Control<Axes, Buttons>>,
+    /// Color controls are drawn with.
+    pub color: Color,
+    /// Color controls are drawn with while touched.
+    pub active_color: Color,
+}
+
+impl<Axes, Buttons> VirtualGamepad<Axes, Buttons>
+where
+    Axes: Hash + Eq + Clone,
+    Buttons: Hash + Eq + Clone,
+{
+    pub fn new() -> Self {
+        VirtualGamepad {
+            controls: Vec::new(),
+            color: Color::new(1.0, 1.0, 1.0, 0.3),
+            active_color: Color::new(1.0, 1.0, 1.0, 0.6),
+        }
+    }
+
+    /// Adds a button that's held down while any touch is in `rect`.
+    pub fn with_button(mut self, rect: Rect, button: Buttons) -> Self {
+        self.controls.push(Control::Button {
+            rect,
+            button,
+            pressed: false,
+        });
+        self
+    }
+
+    /// Adds a stick: a touch starting within `radius` of `center`
+    /// grabs it and drives the two axes until it's lifted.  Like
+    /// screen coordinates, the Y axis is positive downwards.
+    pub fn with_stick(mut self, center: Point2, radius: f32, x_axis: Axes, y_axis: Axes) -> Self {
+        self.controls.push(Control::Stick {
+            center,
+            radius,
+            x_axis,
+            y_axis,
+            touch: None,
+            offset: (0.0, 0.0),
+        });
+        self
+    }
+
+    /// Applies the current touches to the input state's buttons and
+    /// axes.  Call it once per frame, before `InputState::update()`.
+    pub fn update(&mut self, input: &mut InputState<Axes, Buttons>) {
+        for control in self.controls.iter_mut() {
+            match control {
+                Control::Button {
+                    rect,
+                    button,
+                    pressed,
+                } => {
+                    let now_pressed = input
+                        .get_touches()
+                        .any(|(_, pos)| rect.contains(math::point2_to_mint(pos)));
+                    if now_pressed != *pressed {
+                        *pressed = now_pressed;
+                        // Not update_button_down(), which would count
+                        // this as keyboard input.
+                        input.update_effect(InputEffect::Button(button.clone()), now_pressed);
+                    }
+                }
+                Control::Stick {
+                    center,
+                    radius,
+                    x_axis,
+                    y_axis,
+                    touch,
+                    offset,
+                } => {
+                    let was_held = touch.is_some();
+                    let held = touch.and_then(|id| input.get_touch(id));
+                    let grabbed = match held {
+                        Some(pos) => Some(pos),
+                        None => {
+                            *touch = input
+                                .get_touches()
+                                .find(|(_, pos)| distance(*pos, *center) <= *radius)
+                                .map(|(id, _)| id);
+                            touch.and_then(|id| input.get_touch(id))
+                        }
+                    };
+                    *offset = match grabbed {
+                        Some(pos) => {
+                            let dx = (pos.x - center.x) / *radius;
+                            let dy = (pos.y - center.y) / *radius;
+                            let len = (dx * dx + dy * dy).sqrt();
+                            if len > 1.0 {
+                                (dx / len, dy / len)
+                            } else {
+                                (dx, dy)
+                            }
+                        }
+                        None => (0.0, 0.0),
+                    };
+                    // Leave the axes alone while the stick is idle, so
+                    // keyboard or gamepad input on them isn't cancelled;
+                    // only center them once when the touch lets go.
+                    if grabbed.is_some() || was_held {
+                        input.update_axis_analog(x_axis.clone(), offset.0);
+                        input.update_axis_analog(y_axis.clone(), offset.1);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns whether any touch is currently on one of the controls.
+    pub fn is_active(&self) -> bool {
+        self.controls.iter().any(|control| match control {
+            Control::Button { pressed, .. } => *pressed,
+            Control::Stick { touch, .. } => touch.is_some(),
+        })
+    }
+
+    /// Draws the controls, in screen coordinates.
+    pub fn draw(&self, ctx: &mut Context) -> GameResult {
+        if self.controls.is_empty() {
+            return Ok(());
+        }
+        let mut mb = graphics::MeshBuilder::new();
+        for control in &self.controls {
+            match control {
+                Control::Button { rect, pressed, .. } => {
+                    let color = if *pressed {
+                        self.active_color
+                    } else {
+                        self.color
+                    };
+                    mb.rectangle(DrawMode::fill(), *rect, color);
+                }
+                Control::Stick {
+                    center,
+                    radius,
+                    touch,
+                    offset,
+                    ..
+                } => {
+                    let center = math::point2_to_mint(*center);
+                    mb.circle(DrawMode::stroke(2.0), center, *radius, 0.5, self.color);
+                    let knob = [center.x + offset.0 * radius, center.y + offset.1 * radius];
+                    let color = if touch.is_some() {
+                        self.active_color
+                    } else {
+                        self.color
+                    };
+                    mb.circle(DrawMode::fill(), knob, radius * 0.4, 0.5, color);
+                }
+            }
+        }
+        let mesh = mb.build(ctx)?;
+        graphics::draw(ctx, &mesh, DrawParam::default())
+    }
+}
+
+impl<Axes, Buttons> Default for VirtualGamepad<Axes, Buttons>
+where
+    Axes: Hash + Eq + Clone,
+    Buttons: Hash + Eq + Clone,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn distance(a: Point2, b: Point2) -> f32 {
+    let dx = a.x - b.x;
+    let dy = a.y - b.y;
+    (dx * dx + dy * dy).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ggez::event::winit_event::TouchPhase;
+
+    #[derive(Hash, Eq, PartialEq, Copy, Clone, Debug)]
+    enum Buttons {
+        Jump,
+    }
+
+    #[derive(Hash, Eq, PartialEq, Copy, Clone, Debug)]
+    enum Axes {
+        Horz,
+        Vert,
+    }
+
+    #[test]
+    fn test_virtual_gamepad() {
+        let mut pad = VirtualGamepad::new()
+            .with_button(Rect::new(500.0, 400.0, 100.0, 100.0), Buttons::Jump)
+            .with_stick(math::point2(100.0, 400.0), 50.0, Axes::Horz, Axes::Vert);
+        let mut input: InputState<Axes, Buttons> = InputState::new();
+
+        // Touch the button
+        input.update_touch(1, TouchPhase::Started, 550.0, 450.0);
+        pad.update(&mut input);
+        assert!(input.get_button_pressed(Buttons::Jump));
+        assert!(pad.is_active());
+
+        // Grab the stick and drag it way right.
+        input.update_touch(2, TouchPhase::Started, 110.0, 400.0);
+        pad.update(&mut input);
+        input.update_touch(2, TouchPhase::Moved, 300.0, 400.0);
+        pad.update(&mut input);
+        assert_eq!(input.get_axis_raw(Axes::Horz), 1.0);
+        assert_eq!(input.get_axis_raw(Axes::Vert), 0.0);
+        input.update(1.0);
+        assert_eq!(input.get_axis(Axes::Horz), 1.0);
+
+        // Let go of both
+        input.update_touch(1, TouchPhase::Ended, 550.0, 450.0);
+        input.update_touch(2, TouchPhase::Ended, 300.0, 400.0);
+        pad.update(&mut input);
+        assert!(input.get_button_released(Buttons::Jump));
+        assert_eq!(input.get_axis_raw(Axes::Horz), 0.0);
+        assert!(!pad.is_active());
+
+        // A touch outside the stick doesn't grab it.
+        input.update_touch(3, TouchPhase::Started, 300.0, 400.0);
+        pad.update(&mut input);
+        assert_eq!(input.get_axis_raw(Axes::Horz), 0.0);
+    }
+
+    #[test]
+    fn test_idle_stick_leaves_axes_alone() {
+        let mut pad = VirtualGamepad::new().with_stick(
+            math::point2(100.0, 400.0),
+            50.0,
+            Axes::Horz,
+            Axes::Vert,
+        );
+        let mut input: InputState<Axes, Buttons> = InputState::new();
+
+        // Keyboard input on the same axis survives an untouched stick.
+        input.update_axis_start(Axes::Horz, true);
+        pad.update(&mut input);
+        assert_eq!(input.get_axis_raw(Axes::Horz), 1.0);
+
+        // Letting go of the stick centers it once, then leaves it be.
+        input.update_touch(1, TouchPhase::Started, 100.0, 400.0);
+        pad.update(&mut input);
+        input.update_touch(1, TouchPhase::Ended, 100.0, 400.0);
+        pad.update(&mut input);
+        assert_eq!(input.get_axis_raw(Axes::Horz), 0.0);
+        input.update_axis_start(Axes::Horz, false);
+        pad.update(&mut input);
+        assert_eq!(input.get_axis_raw(Axes::Horz), -1.0);
+    }
+}
@@ -1,8 +1,21 @@
+#[cfg(feature = "euclid")]
+pub extern crate euclid;
 extern crate ggez;
+#[cfg(feature = "glam")]
+pub extern crate glam;
+#[cfg(feature = "nalgebra-glm")]
 pub extern crate nalgebra_glm;
 extern crate rand;
+#[cfg(feature = "serialize")]
+extern crate serde;
 
+pub mod bitmap_font;
 pub mod camera;
 pub mod input;
+pub mod math;
 pub mod particle;
+pub mod perf;
+pub mod postfx;
+pub mod random;
 pub mod scene;
+pub mod ui;
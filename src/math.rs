@@ -0,0 +1,130 @@
+//! The 2D point and vector types used by this crate's APIs.
+//!
+//! ggez itself speaks `mint`, which is great for interop but has no
+//! actual math operations, so everyone picks their own math crate.
+//! Rather than forcing one on you, the `Point2` and `Vector2` types
+//! here are aliases selected with cargo features:
+//!
+//! * `nalgebra-glm` (the default): both are `nalgebra_glm::Vec2`
+//! * `euclid`: `euclid::Point2D<f32>` and `euclid::Vector2D<f32>`
+//! * `glam`: both are `glam::Vec2`
+//! * none of those: the plain `mint` types ggez uses
+//!
+//! If more than one is enabled, `euclid` wins over `glam`, which wins
+//! over `nalgebra-glm`, so to use either of the others you want
+//! `default-features = false` anyway.
+//!
+//! All of these have public `x` and `y` fields, so code that only
+//! touches those works with any backend.  The functions here do
+//! construction and conversion to and from ggez's `mint` types.
+
+use ggez::mint;
+
+#[cfg(feature = "euclid")]
+mod types {
+    pub type Point2 = euclid::Point2D<f32>;
+    pub type Vector2 = euclid::Vector2D<f32>;
+
+    pub fn point2(x: f32, y: f32) -> Point2 {
+        Point2::new(x, y)
+    }
+
+    pub fn vector2(x: f32, y: f32) -> Vector2 {
+        Vector2::new(x, y)
+    }
+}
+
+#[cfg(all(feature = "glam", not(feature = "euclid")))]
+mod types {
+    pub type Point2 = glam::Vec2;
+    pub type Vector2 = glam::Vec2;
+
+    pub fn point2(x: f32, y: f32) -> Point2 {
+        Point2::new(x, y)
+    }
+
+    pub fn vector2(x: f32, y: f32) -> Vector2 {
+        Vector2::new(x, y)
+    }
+}
+
+#[cfg(all(
+    feature = "nalgebra-glm",
+    not(any(feature = "euclid", feature = "glam"))
+))]
+mod types {
+    pub type Point2 = nalgebra_glm::Vec2;
+    pub type Vector2 = nalgebra_glm::Vec2;
+
+    pub fn point2(x: f32, y: f32) -> Point2 {
+        Point2::new(x, y)
+    }
+
+    pub fn vector2(x: f32, y: f32) -> Vector2 {
+        Vector2::new(x, y)
+    }
+}
+
+#[cfg(not(any(feature = "nalgebra-glm", feature = "euclid", feature = "glam")))]
+mod types {
+    use ggez::mint;
+
+    pub type Point2 = mint::Point2<f32>;
+    pub type Vector2 = mint::Vector2<f32>;
+
+    pub fn point2(x: f32, y: f32) -> Point2 {
+        Point2 { x, y }
+    }
+
+    pub fn vector2(x: f32, y: f32) -> Vector2 {
+        Vector2 { x, y }
+    }
+}
+
+/// A point in 2D space.
+pub type Point2 = types::Point2;
+/// A vector in 2D space.
+pub type Vector2 = types::Vector2;
+
+/// Creates a new `Point2`.
+pub fn point2(x: f32, y: f32) -> Point2 {
+    types::point2(x, y)
+}
+
+/// Creates a new `Vector2`.
+pub fn vector2(x: f32, y: f32) -> Vector2 {
+    types::vector2(x, y)
+}
+
+/// Converts a `Point2` into the `mint` type ggez's `DrawParam` wants.
+pub fn point2_to_mint(p: Point2) -> mint::Point2<f32> {
+    mint::Point2 { x: p.x, y: p.y }
+}
+
+/// Converts ggez's `mint` point into a `Point2`.
+pub fn point2_from_mint(p: mint::Point2<f32>) -> Point2 {
+    point2(p.x, p.y)
+}
+
+/// Converts a `Vector2` into the `mint` type ggez's `DrawParam` wants.
+pub fn vector2_to_mint(v: Vector2) -> mint::Vector2<f32> {
+    mint::Vector2 { x: v.x, y: v.y }
+}
+
+/// Converts ggez's `mint` vector into a `Vector2`.
+pub fn vector2_from_mint(v: mint::Vector2<f32>) -> Vector2 {
+    vector2(v.x, v.y)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mint_round_trip() {
+        let p = point2(1.5, -2.0);
+        assert_eq!(point2_from_mint(point2_to_mint(p)), p);
+        let v = vector2(-3.0, 4.25);
+        assert_eq!(vector2_from_mint(vector2_to_mint(v)), v);
+    }
+}
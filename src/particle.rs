@@ -16,6 +16,7 @@
 
 use std::marker::Sized;
 
+use std::collections::VecDeque;
 use std::f32;
 
 use ggez::graphics;
@@ -26,6 +27,13 @@ use ggez::{Context, GameResult};
 use rand;
 use rand::Rng;
 
+use crate::random::Random;
+#[cfg(feature = "serialize")]
+use serde::{Deserialize, Serialize};
+
+pub mod presets;
+pub mod trail;
+
 enum ValueGenerator<T> {
     Fixed(T),
 
@@ -34,13 +42,10 @@ enum ValueGenerator<T> {
 }
 
 impl ValueGenerator<f32> {
-    pub fn get_value(&self) -> f32 {
+    pub fn get_value<R: Rng>(&self, rng: &mut R) -> f32 {
         match *self {
             ValueGenerator::Fixed(x) => x,
-            ValueGenerator::UniformRange(ref low, ref high) => {
-                let mut rng = rand::thread_rng();
-                rng.gen_range(*low, *high)
-            }
+            ValueGenerator::UniformRange(low, high) => range(rng, low, high),
         }
     }
 }
@@ -48,51 +53,87 @@ impl ValueGenerator<f32> {
 // Apparently implementing SampleRange for our own type
 // isn't something we should do, so we just define this by hand...
 impl ValueGenerator<Vector2<f32>> {
-    fn get_value(&self) -> Vector2<f32> {
+    fn get_value<R: Rng>(&self, rng: &mut R) -> Vector2<f32> {
         match *self {
             ValueGenerator::Fixed(x) => x,
             ValueGenerator::UniformRange(low, high) => {
-                let mut rng = rand::thread_rng();
-                let x = rng.gen_range(low.x, high.x);
-                let y = rng.gen_range(low.y, high.y);
+                let x = range(rng, low.x, high.x);
+                let y = range(rng, low.y, high.y);
                 Vector2 { x, y }
             }
         }
     }
 }
 
-impl ValueGenerator<Point2<f32>> {
-    fn get_value(&self) -> Point2<f32> {
+impl ValueGenerator<graphics::Color> {
+    fn get_value<R: Rng>(&self, rng: &mut R) -> graphics::Color {
         match *self {
             ValueGenerator::Fixed(x) => x,
             ValueGenerator::UniformRange(low, high) => {
-                let mut rng = rand::thread_rng();
-                let x = rng.gen_range(low.x, high.x);
-                let y = rng.gen_range(low.y, high.y);
-                Point2 { x, y }
+                let r = range(rng, low.r, high.r);
+                let g = range(rng, low.g, high.g);
+                let b = range(rng, low.b, high.b);
+                let a = range(rng, low.a, high.a);
+                graphics::Color::new(r, g, b, a)
             }
         }
     }
 }
 
-impl ValueGenerator<graphics::Color> {
-    fn get_value(&self) -> graphics::Color {
+// Like `Rng::gen_range()`, but returns `low` for an empty range
+// rather than panicking.
+fn range<R: Rng>(rng: &mut R, low: f32, high: f32) -> f32 {
+    if low >= high {
+        low
+    } else {
+        rng.gen_range(low, high)
+    }
+}
+
+/// Rotates a vector clockwise on screen by `angle` radians.
+fn rotate(v: Vector2<f32>, angle: f32) -> Vector2<f32> {
+    let (sin, cos) = angle.sin_cos();
+    Vector2 {
+        x: v.x * cos - v.y * sin,
+        y: v.x * sin + v.y * cos,
+    }
+}
+
+// Like a `ValueGenerator<Vector2<f32>>`, but can also pick a speed
+// in a range of directions.
+enum VelocityGenerator {
+    Components(ValueGenerator<Vector2<f32>>),
+    Cone {
+        direction: f32,
+        spread: f32,
+        min_speed: f32,
+        max_speed: f32,
+    },
+}
+
+impl VelocityGenerator {
+    fn get_value<R: Rng>(&self, rng: &mut R) -> Vector2<f32> {
         match *self {
-            ValueGenerator::Fixed(x) => x,
-            ValueGenerator::UniformRange(low, high) => {
-                let mut rng = rand::thread_rng();
-                let (lowr, lowg, lowb) = low.into();
-                let (hir, hig, hib) = high.into();
-                let r = rng.gen_range(lowr, hir);
-                let g = rng.gen_range(lowg, hig);
-                let b = rng.gen_range(lowb, hib);
-                (r, g, b).into()
+            VelocityGenerator::Components(ref generator) => generator.get_value(rng),
+            VelocityGenerator::Cone {
+                direction,
+                spread,
+                min_speed,
+                max_speed,
+            } => {
+                let half = spread / 2.0;
+                let angle = range(rng, direction - half, direction + half);
+                let speed = range(rng, min_speed, max_speed);
+                Vector2 {
+                    x: angle.cos() * speed,
+                    y: angle.sin() * speed,
+                }
             }
         }
     }
 }
 
-pub type EasingFn = Fn(f32) -> f32;
+pub type EasingFn = dyn Fn(f32) -> f32;
 
 /// Linear interpolation; assumes input value is in the range 0-1 and
 /// returns it interpolated to the given bounds.
@@ -173,6 +214,14 @@ impl Interpolate for graphics::Color {
 pub enum Transition<T: Copy> {
     Fixed(T),
     Range(T, T),
+    /// Like `Range`, but with the time passed through an easing
+    /// function first, such as one from the `ezing` crate.
+    Eased(T, T, fn(f32) -> f32),
+    /// Goes through any number of `(time, value)` stops in order,
+    /// interpolating between neighbours, like a color gradient that
+    /// goes from yellow to orange to red to black.  Holds the first
+    /// and last values before and after them.
+    Stops(Vec<(f32, T)>),
 }
 
 impl<T: Interpolate + Copy> Transition<T> {
@@ -184,12 +233,39 @@ impl<T: Interpolate + Copy> Transition<T> {
         Transition::Range(from, to)
     }
 
+    pub fn eased(from: T, to: T, easing: fn(f32) -> f32) -> Self {
+        Transition::Eased(from, to, easing)
+    }
+
+    /// Makes a `Transition::Stops`, sorting the stops by time.
+    ///
+    /// Panics if there are none.
+    pub fn stops(stops: &[(f32, T)]) -> Self {
+        assert!(!stops.is_empty(), "Transition needs at least one stop");
+        let mut stops = stops.to_vec();
+        stops.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Transition::Stops(stops)
+    }
+
     /// t should be between 0.0 and 1.0
     /// or should it take the current value and a delta-t???
     pub fn get(&self, t: f32) -> T {
         match *self {
             Transition::Fixed(value) => value,
             Transition::Range(from, to) => T::interp_between(t, from, to),
+            Transition::Eased(from, to, easing) => T::interp_between(easing(t), from, to),
+            Transition::Stops(ref stops) => {
+                let next = stops.iter().position(|&(time, _)| time > t);
+                match next {
+                    Some(0) => stops[0].1,
+                    Some(i) => {
+                        let (t1, v1) = stops[i - 1];
+                        let (t2, v2) = stops[i];
+                        T::interp_between((t - t1) / (t2 - t1), v1, v2)
+                    }
+                    None => stops[stops.len() - 1].1,
+                }
+            }
         }
     }
 }
@@ -245,6 +321,8 @@ impl<T: Interpolate + Copy> Transition<T> {
 // Though if the user defines their own worldspace coordinate system
 // that could get a bit sticky.  :/
 
+/// A newly emitted particle.  Once it's in a `ParticleSystem` it's
+/// stored spread across `Particles`.
 struct Particle {
     pos: Point2<f32>,
     vel: Vector2<f32>,
@@ -252,8 +330,9 @@ struct Particle {
     size: f32,
     angle: f32,
     ang_vel: f32,
-    age: f32,
     max_age: f32,
+    // Which frame of the flipbook, if there is one.
+    frame: u32,
 }
 
 // Aha.  We have a 2x2 matrix of cases here: A particle can have a property
@@ -296,18 +375,121 @@ impl Particle {
         max_age: f32,
     ) -> Self {
         Particle {
-            pos: pos,
-            vel: vel,
-            color: color,
-            size: size,
-            angle: angle,
+            pos,
+            vel,
+            color,
+            size,
+            angle,
             ang_vel: 0.0,
-            age: 0.0,
-            max_age: max_age,
+            max_age,
+            frame: 0,
+        }
+    }
+}
+
+/// All of a system's particles, with each property in its own `Vec`
+/// so the update loops run over tightly packed numbers.
+///
+/// The first `living` of each are alive, in the order they were
+/// emitted.  The rest are dead ones kept around so new particles
+/// reuse their slots and trails rather than allocating.
+#[derive(Debug, Default)]
+struct Particles {
+    pos: Vec<Point2<f32>>,
+    vel: Vec<Vector2<f32>>,
+    color: Vec<graphics::Color>,
+    size: Vec<f32>,
+    angle: Vec<f32>,
+    ang_vel: Vec<f32>,
+    age: Vec<f32>,
+    max_age: Vec<f32>,
+    frame: Vec<u32>,
+    // Recent positions, oldest first, if particles leave trails.
+    trail: Vec<VecDeque<Point2<f32>>>,
+    living: usize,
+}
+
+impl Particles {
+    fn reserve_exact(&mut self, additional: usize) {
+        self.pos.reserve_exact(additional);
+        self.vel.reserve_exact(additional);
+        self.color.reserve_exact(additional);
+        self.size.reserve_exact(additional);
+        self.angle.reserve_exact(additional);
+        self.ang_vel.reserve_exact(additional);
+        self.age.reserve_exact(additional);
+        self.max_age.reserve_exact(additional);
+        self.frame.reserve_exact(additional);
+        self.trail.reserve_exact(additional);
+    }
+
+    fn push(&mut self, particle: Particle) {
+        let i = self.living;
+        if i < self.pos.len() {
+            self.pos[i] = particle.pos;
+            self.vel[i] = particle.vel;
+            self.color[i] = particle.color;
+            self.size[i] = particle.size;
+            self.angle[i] = particle.angle;
+            self.ang_vel[i] = particle.ang_vel;
+            self.age[i] = 0.0;
+            self.max_age[i] = particle.max_age;
+            self.frame[i] = particle.frame;
+            self.trail[i].clear();
+        } else {
+            self.pos.push(particle.pos);
+            self.vel.push(particle.vel);
+            self.color.push(particle.color);
+            self.size.push(particle.size);
+            self.angle.push(particle.angle);
+            self.ang_vel.push(particle.ang_vel);
+            self.age.push(0.0);
+            self.max_age.push(particle.max_age);
+            self.frame.push(particle.frame);
+            self.trail.push(VecDeque::new());
+        }
+        self.living += 1;
+    }
+
+    fn swap(&mut self, a: usize, b: usize) {
+        self.pos.swap(a, b);
+        self.vel.swap(a, b);
+        self.color.swap(a, b);
+        self.size.swap(a, b);
+        self.angle.swap(a, b);
+        self.ang_vel.swap(a, b);
+        self.age.swap(a, b);
+        self.max_age.swap(a, b);
+        self.frame.swap(a, b);
+        self.trail.swap(a, b);
+    }
+
+    fn is_dead(&self, i: usize) -> bool {
+        self.age[i] >= self.max_age[i]
+    }
+
+    /// Moves the particles that are still alive to the front, in the
+    /// same order, leaving the dead ones after them.
+    fn recycle_dead(&mut self) {
+        let mut living = 0;
+        for i in 0..self.living {
+            if !self.is_dead(i) {
+                self.swap(i, living);
+                living += 1;
+            }
         }
+        self.living = living;
     }
 }
 
+/// How far back along the emitter's movement this update the `i`th
+/// of `count` particles emitted in it should start, as a fraction of
+/// the movement, so they're evenly spaced and the last is right at
+/// the emitter.
+fn spawn_lag(i: usize, count: usize) -> f32 {
+    (count - 1 - i) as f32 / count as f32
+}
+
 // This probably isn't actually needed as a separate type,
 // at least at this point,
 // but it makes things clearer for the moment...  Hmm.
@@ -328,18 +510,26 @@ macro_rules! prop {
             self.system.$name = ValueGenerator::UniformRange(start, end);
             self
         }
-    }
+    };
 }
 
 impl ParticleSystemBuilder {
     pub fn new(ctx: &mut Context) -> Self {
         let system = ParticleSystem::new(ctx);
-        ParticleSystemBuilder { system: system }
+        ParticleSystemBuilder { system }
     }
     pub fn build(self) -> ParticleSystem {
         self.system
     }
 
+    /// Seeds the system's random number generator, so it does the
+    /// same thing every time it's updated the same way.  Otherwise
+    /// it's seeded differently every run.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.system.rng = Random::with_seed(seed);
+        self
+    }
+
     /// Set maximum number of particles.
     pub fn count(mut self, count: usize) -> Self {
         self.system.max_particles = count;
@@ -349,12 +539,44 @@ impl ParticleSystemBuilder {
 
     prop!(start_color, start_color_range, graphics::Color);
     prop!(start_size, start_size_range, f32);
+    // In radians per second.
     prop!(start_ang_vel, start_ang_vel_range, f32);
     // These two need some work, 'cause, shapes.
     prop!(start_position, start_position_range, Point2<f32>);
-    prop!(start_velocity, start_velocity_range, Vector2<f32>);
     prop!(start_max_age, start_max_age_range, f32);
 
+    pub fn start_velocity(mut self, start_velocity: Vector2<f32>) -> Self {
+        self.system.start_velocity =
+            VelocityGenerator::Components(ValueGenerator::Fixed(start_velocity));
+        self
+    }
+
+    pub fn start_velocity_range(mut self, start: Vector2<f32>, end: Vector2<f32>) -> Self {
+        self.system.start_velocity =
+            VelocityGenerator::Components(ValueGenerator::UniformRange(start, end));
+        self
+    }
+
+    /// Sends particles off at a speed between `min_speed` and
+    /// `max_speed`, in a direction within `spread / 2` radians either
+    /// side of `direction`.  A `spread` of 2π sends them every which
+    /// way.
+    pub fn start_velocity_cone(
+        mut self,
+        direction: f32,
+        spread: f32,
+        min_speed: f32,
+        max_speed: f32,
+    ) -> Self {
+        self.system.start_velocity = VelocityGenerator::Cone {
+            direction,
+            spread,
+            min_speed,
+            max_speed,
+        };
+        self
+    }
+
     pub fn acceleration(mut self, accel: Vector2<f32>) -> Self {
         self.system.acceleration = accel;
         self
@@ -366,13 +588,41 @@ impl ParticleSystemBuilder {
         self
     }
 
+    /// Stops emitting after this many seconds, for one-off effects
+    /// like explosions.  By default systems emit forever.
+    pub fn emitter_lifetime(mut self, seconds: f32) -> Self {
+        self.system.emitter_lifetime = Some(seconds);
+        self
+    }
+
     pub fn delta_size(mut self, trans: Transition<f32>) -> Self {
         self.system.delta_size = trans;
         self
     }
 
+    /// Sets particles' color over their life, replacing their
+    /// `start_color`.
     pub fn delta_color(mut self, trans: Transition<graphics::Color>) -> Self {
-        self.system.delta_color = trans;
+        self.system.delta_color = Some(trans);
+        self
+    }
+
+    /// Overrides the alpha of particles' color over their life.
+    pub fn delta_alpha(mut self, trans: Transition<f32>) -> Self {
+        self.system.delta_alpha = Some(trans);
+        self
+    }
+
+    /// Scales each particle's starting angular velocity over its life.
+    pub fn delta_ang_vel(mut self, trans: Transition<f32>) -> Self {
+        self.system.delta_ang_vel = Some(trans);
+        self
+    }
+
+    /// How quickly particles slow down over their life; each second
+    /// they lose about this fraction of their velocity.
+    pub fn linear_damping(mut self, trans: Transition<f32>) -> Self {
+        self.system.linear_damping = trans;
         self
     }
 
@@ -380,11 +630,107 @@ impl ParticleSystemBuilder {
         self.system.start_shape = shape;
         self
     }
+
+    pub fn simulation_space(mut self, space: SimulationSpace) -> Self {
+        self.system.simulation_space = space;
+        self
+    }
+
+    /// Gives new particles this fraction of the emitter's velocity,
+    /// so a moving emitter throws them along with it.  Only does
+    /// anything in `SimulationSpace::World`; in local space they
+    /// move with the emitter anyway.
+    pub fn inherit_velocity(mut self, fraction: f32) -> Self {
+        self.system.inherit_velocity = fraction;
+        self
+    }
+
+    /// Adds a system that emits `burst` particles wherever one of
+    /// this system's particles dies, like the sparks from a firework.
+    /// It's updated and drawn along with this one.  It should usually
+    /// have an emission rate of 0, so it only emits in bursts.
+    pub fn sub_emitter(mut self, system: ParticleSystem, burst: usize) -> Self {
+        self.system.sub_emitters.push(SubEmitter { system, burst });
+        self
+    }
+
+    /// Makes each particle leave a trail through its last
+    /// `max_points` positions, `width` wide at the particle and
+    /// fading out behind it; see `trail::Trail`.
+    pub fn trails(mut self, max_points: usize, width: f32) -> Self {
+        self.system.trails = Some((max_points, width));
+        self
+    }
+
+    /// Treats the image as a flipbook: a grid of `columns` by `rows`
+    /// frames, read left to right and top to bottom.
+    pub fn flipbook(mut self, columns: u32, rows: u32, mode: FlipbookMode) -> Self {
+        self.system.flipbook = Some(Flipbook {
+            columns: columns.max(1),
+            rows: rows.max(1),
+            mode,
+        });
+        self
+    }
+
+    /// Sets how particles are blended with what's underneath them,
+    /// for instance `BlendMode::Add` for fire and glows.
+    pub fn blend_mode(self, mode: BlendMode) -> Self {
+        graphics::Drawable::set_blend_mode(&mut *self.system.sprite_batch.borrow_mut(), Some(mode));
+        self
+    }
+
+    /// Sets which particles are drawn over which; by default newer
+    /// ones go on top.
+    pub fn draw_order(mut self, order: DrawOrder) -> Self {
+        self.system.draw_order = order;
+        self
+    }
+
+    /// Draws particles with the given image, centered, instead of
+    /// small white squares.
+    pub fn image(self, image: graphics::Image) -> Self {
+        self.system.sprite_batch.borrow_mut().set_image(image);
+        self
+    }
+}
+
+/// Which particles get drawn over which, which matters for
+/// alpha-blended effects like smoke.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum DrawOrder {
+    /// Newer particles over older ones.
+    #[default]
+    NewestOnTop,
+    /// Older particles over newer ones, so smoke that's risen
+    /// stays in front of fresh smoke.
+    OldestOnTop,
+    /// Particles further down the screen over ones further up, for
+    /// top-down games.
+    ByY,
+}
+
+/// Where particles live once they've been emitted, which matters
+/// when the emitter moves with `ParticleSystem::set_position()`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub enum SimulationSpace {
+    /// Particles stay relative to the emitter, so moving it moves
+    /// the whole effect, like the glow around a lantern.
+    #[default]
+    Local,
+    /// Particles are left where they were emitted, so moving the
+    /// emitter leaves a trail behind it, like smoke from a torch.
+    /// Particles emitted during one update are spread along the way
+    /// the emitter moved, so fast emitters don't leave puffs.
+    World,
 }
 
 /// Defines where a new particle should be created.
 /// TODO: This basic idea should be used for both initial position
 /// and initial velocity...  Uniform, direction, cone, line...
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub enum EmissionShape {
     // Source point
     Point(Point2<f32>),
@@ -392,13 +738,18 @@ pub enum EmissionShape {
     Line(Point2<f32>, Point2<f32>),
     // Center point and radius
     Circle(Point2<f32>, f32),
+    // Center point, inner and outer radius.  Unlike `Circle`, points
+    // are spread evenly over the area.
+    Ring(Point2<f32>, f32, f32),
+    // Opposite corners of the rectangle.
+    Rect(Point2<f32>, Point2<f32>),
 }
 
 impl EmissionShape {
     /// Gets a random point that complies
     /// with the given shape.
     /// TODO: This is an ideal case for unit tests.
-    fn get_random(&self) -> Point2<f32> {
+    fn get_random<R: Rng>(&self, rng: &mut R) -> Point2<f32> {
         match *self {
             EmissionShape::Point(v) => v,
             EmissionShape::Line(p1, p2) => {
@@ -406,23 +757,22 @@ impl EmissionShape {
                 let max_x = f32::max(p1.x, p2.x);
                 let min_y = f32::min(p1.y, p2.y);
                 let max_y = f32::max(p1.y, p2.y);
-                let mut rng = rand::thread_rng();
                 let x: f32;
                 let y: f32;
                 if min_x == max_x {
                     // Line is vertical
                     x = min_x;
-                    y = rng.gen_range(min_y, max_y);
+                    y = range(rng, min_y, max_y);
                 } else if min_y == max_y {
                     // Line is horizontal
                     y = max_y;
-                    x = rng.gen_range(min_x, max_x)
+                    x = range(rng, min_x, max_x);
                 } else {
                     // Line is sloped.
                     let dy = max_y - min_y;
                     let dx = max_x - min_x;
                     let slope = dy / dx;
-                    x = rng.gen_range(min_x, max_x);
+                    x = range(rng, min_x, max_x);
                     y = (slope * (x - min_x)) + min_y;
                 }
 
@@ -442,9 +792,8 @@ impl EmissionShape {
                 Point2 { x, y }
             }
             EmissionShape::Circle(center, radius) => {
-                let mut rng = rand::thread_rng();
                 let theta = rng.gen_range(0.0, f32::consts::PI * 2.0);
-                let r = rng.gen_range(0.0, radius);
+                let r = range(rng, 0.0, radius);
                 let x = theta.cos() * r;
                 let y = theta.sin() * r;
                 Point2 {
@@ -452,15 +801,227 @@ impl EmissionShape {
                     y: y + center.y,
                 }
             }
+            EmissionShape::Ring(center, inner, outer) => {
+                let theta = rng.gen_range(0.0, f32::consts::PI * 2.0);
+                // Picking the radius squared keeps the points from
+                // bunching up on the inside.
+                let r = range(rng, inner * inner, outer * outer).sqrt();
+                Point2 {
+                    x: theta.cos() * r + center.x,
+                    y: theta.sin() * r + center.y,
+                }
+            }
+            EmissionShape::Rect(p1, p2) => Point2 {
+                x: range(rng, f32::min(p1.x, p2.x), f32::max(p1.x, p2.x)),
+                y: range(rng, f32::min(p1.y, p2.y), f32::max(p1.y, p2.y)),
+            },
+        }
+    }
+}
+
+/// How particles pick frames from a flipbook image; see
+/// `ParticleSystemBuilder::flipbook()`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub enum FlipbookMode {
+    /// Goes through all the frames once over each particle's life,
+    /// like an explosion.
+    ByAge,
+    /// Each particle sticks to a frame picked at random, for
+    /// variety.
+    Random,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Flipbook {
+    columns: u32,
+    rows: u32,
+    mode: FlipbookMode,
+}
+
+impl Flipbook {
+    fn frames(&self) -> u32 {
+        self.columns * self.rows
+    }
+
+    fn frame_at(&self, life_fraction: f32) -> u32 {
+        let frame = (life_fraction * self.frames() as f32) as u32;
+        frame.min(self.frames() - 1)
+    }
+
+    // The frame's source rect, in the [0, 1] coordinates
+    // `DrawParam::src` uses.
+    fn src(&self, frame: u32) -> graphics::Rect {
+        let w = 1.0 / self.columns as f32;
+        let h = 1.0 / self.rows as f32;
+        let column = frame % self.columns;
+        let row = frame / self.columns;
+        graphics::Rect::new(column as f32 * w, row as f32 * h, w, h)
+    }
+}
+
+/// How fast and which way particles start off, for a
+/// `ParticleEffectDesc`.  See `ParticleSystemBuilder`'s
+/// `start_velocity_range()` and `start_velocity_cone()`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub enum VelocityDesc {
+    Range(Vector2<f32>, Vector2<f32>),
+    Cone {
+        direction: f32,
+        spread: f32,
+        min_speed: f32,
+        max_speed: f32,
+    },
+}
+
+/// A particle effect as plain data, so it can live in a file and
+/// be tweaked without recompiling.  With the `serialize` feature
+/// enabled it can be deserialized from JSON, RON and so on, and
+/// anything a file leaves out gets the default.
+///
+/// ```rust,ignore
+/// let desc: ParticleEffectDesc = serde_json::from_reader(file)?;
+/// let sparks = desc.build(ctx)?;
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize), serde(default))]
+pub struct ParticleEffectDesc {
+    /// How many particles can be alive at once.
+    pub max_particles: usize,
+    /// Particles emitted per second.
+    pub emission_rate: f32,
+    /// How long the emitter emits for, in seconds, or `None` for
+    /// forever.
+    pub emitter_lifetime: Option<f32>,
+    pub shape: EmissionShape,
+    pub velocity: VelocityDesc,
+    pub acceleration: Vector2<f32>,
+    /// The range particles' lifetimes are picked from, in seconds.
+    pub lifetime: (f32, f32),
+    /// The range particles' angular velocities are picked from, in
+    /// radians per second.
+    pub spin: (f32, f32),
+    /// Size over each particle's life, as `(time, size)` stops.
+    pub size: Vec<(f32, f32)>,
+    /// The range particles' colors are picked from, as `[r, g, b, a]`,
+    /// if there's no `color`.
+    pub start_color: ([f32; 4], [f32; 4]),
+    /// Color over each particle's life, as `(time, [r, g, b, a])`
+    /// stops.
+    pub color: Vec<(f32, [f32; 4])>,
+    /// If there are any `(time, alpha)` stops here, they override
+    /// the alpha from `color`.
+    pub alpha: Vec<(f32, f32)>,
+    /// See `ParticleSystemBuilder::linear_damping()`.
+    pub damping: f32,
+    pub simulation_space: SimulationSpace,
+    /// See `ParticleSystemBuilder::inherit_velocity()`.
+    pub inherit_velocity: f32,
+    /// Path to the particle image, as given to `graphics::Image::new()`,
+    /// or `None` for small white squares.
+    pub image: Option<String>,
+}
+
+impl Default for ParticleEffectDesc {
+    fn default() -> Self {
+        ParticleEffectDesc {
+            max_particles: 1000,
+            emission_rate: 100.0,
+            emitter_lifetime: None,
+            shape: EmissionShape::Point(Point2 { x: 0.0, y: 0.0 }),
+            velocity: VelocityDesc::Cone {
+                direction: -f32::consts::FRAC_PI_2,
+                spread: f32::consts::FRAC_PI_4,
+                min_speed: 50.0,
+                max_speed: 100.0,
+            },
+            acceleration: Vector2 { x: 0.0, y: 0.0 },
+            lifetime: (1.0, 1.0),
+            spin: (0.0, 0.0),
+            size: vec![(0.0, 1.0)],
+            start_color: ([1.0, 1.0, 1.0, 1.0], [1.0, 1.0, 1.0, 1.0]),
+            color: Vec::new(),
+            alpha: Vec::new(),
+            damping: 0.0,
+            simulation_space: SimulationSpace::Local,
+            inherit_velocity: 0.0,
+            image: None,
+        }
+    }
+}
+
+impl ParticleEffectDesc {
+    /// Sets up a builder for the effect, loading its image if it
+    /// has one.  Use this rather than `build()` to change anything
+    /// else before building.
+    pub fn builder(&self, ctx: &mut Context) -> GameResult<ParticleSystemBuilder> {
+        let mut builder = ParticleSystemBuilder::new(ctx)
+            .count(self.max_particles)
+            .emission_rate(self.emission_rate)
+            .emission_shape(self.shape)
+            .acceleration(self.acceleration)
+            .linear_damping(Transition::fixed(self.damping))
+            .simulation_space(self.simulation_space)
+            .inherit_velocity(self.inherit_velocity);
+        builder = match self.velocity {
+            VelocityDesc::Range(low, high) if low == high => builder.start_velocity(low),
+            VelocityDesc::Range(low, high) => builder.start_velocity_range(low, high),
+            VelocityDesc::Cone {
+                direction,
+                spread,
+                min_speed,
+                max_speed,
+            } => builder.start_velocity_cone(direction, spread, min_speed, max_speed),
+        };
+        builder = match self.lifetime {
+            (low, high) if low >= high => builder.start_max_age(low),
+            (low, high) => builder.start_max_age_range(low, high),
+        };
+        builder = builder.start_color_range(self.start_color.0.into(), self.start_color.1.into());
+        builder = match self.spin {
+            (low, high) if low >= high => builder.start_ang_vel(low),
+            (low, high) => builder.start_ang_vel_range(low, high),
+        };
+        if let Some(lifetime) = self.emitter_lifetime {
+            builder = builder.emitter_lifetime(lifetime);
+        }
+        if !self.size.is_empty() {
+            builder = builder.delta_size(Transition::stops(&self.size));
         }
+        if !self.color.is_empty() {
+            let stops: Vec<_> = self
+                .color
+                .iter()
+                .map(|&(t, c)| (t, graphics::Color::from(c)))
+                .collect();
+            builder = builder.delta_color(Transition::stops(&stops));
+        }
+        if !self.alpha.is_empty() {
+            builder = builder.delta_alpha(Transition::stops(&self.alpha));
+        }
+        if let Some(path) = &self.image {
+            builder = builder.image(graphics::Image::new(ctx, path)?);
+        }
+        Ok(builder)
+    }
+
+    /// Makes a particle system for the effect.
+    pub fn build(&self, ctx: &mut Context) -> GameResult<ParticleSystem> {
+        Ok(self.builder(ctx)?.build())
     }
 }
 
 use std::cell::{Cell, RefCell};
 
+struct SubEmitter {
+    system: ParticleSystem,
+    burst: usize,
+}
+
 pub struct ParticleSystem {
     // Bookkeeping stuff
-    particles: Vec<Particle>,
+    particles: Particles,
     residual_particle: f32,
     max_particles: usize,
 
@@ -470,7 +1031,7 @@ pub struct ParticleSystem {
     start_color: ValueGenerator<graphics::Color>,
     start_position: ValueGenerator<Point2<f32>>,
     start_shape: EmissionShape,
-    start_velocity: ValueGenerator<Vector2<f32>>,
+    start_velocity: VelocityGenerator,
     start_angle: ValueGenerator<f32>,
     start_ang_vel: ValueGenerator<f32>,
     start_size: ValueGenerator<f32>,
@@ -479,10 +1040,35 @@ pub struct ParticleSystem {
     acceleration: Vector2<f32>,
 
     delta_size: Transition<f32>,
-    delta_color: Transition<graphics::Color>,
+    delta_color: Option<Transition<graphics::Color>>,
+    delta_alpha: Option<Transition<f32>>,
+    delta_ang_vel: Option<Transition<f32>>,
+    linear_damping: Transition<f32>,
+
+    position: Point2<f32>,
+    rotation: f32,
+    // Where the emitter was as of the last update.
+    last_position: Option<Point2<f32>>,
+    inherit_velocity: f32,
+    simulation_space: SimulationSpace,
+    rng: Random,
+    sub_emitters: Vec<SubEmitter>,
+    flipbook: Option<Flipbook>,
+    // How many points long and how wide particles' trails are.
+    trails: Option<(usize, f32)>,
+    draw_order: DrawOrder,
+    emitter_lifetime: Option<f32>,
+    emitter_age: f32,
+    paused: bool,
+    time_scale: f32,
 
     sprite_batch: RefCell<SpriteBatch>,
     sprite_batch_dirty: Cell<bool>,
+    // Reused for building the trails' mesh each draw.
+    trail_vertices: RefCell<Vec<graphics::Vertex>>,
+    trail_indices: RefCell<Vec<u32>>,
+    // Reused for working out which order to draw particles in.
+    draw_indices: RefCell<Vec<usize>>,
 }
 
 impl ParticleSystem {
@@ -490,13 +1076,16 @@ impl ParticleSystem {
         let image = ParticleSystem::make_image(ctx, 5);
         let sprite_batch = SpriteBatch::new(image);
         ParticleSystem {
-            particles: Vec::new(),
+            particles: Particles::default(),
             max_particles: 0,
             acceleration: Vector2 { x: 0.0, y: 0.0 },
             start_color: ValueGenerator::Fixed((255, 255, 255).into()),
             start_position: ValueGenerator::Fixed(Point2 { x: 0.0, y: 0.0 }),
             start_shape: EmissionShape::Point(Point2 { x: 0.0, y: 0.0 }),
-            start_velocity: ValueGenerator::Fixed(Vector2 { x: 1.0, y: 1.0 }),
+            start_velocity: VelocityGenerator::Components(ValueGenerator::Fixed(Vector2 {
+                x: 1.0,
+                y: 1.0,
+            })),
             start_angle: ValueGenerator::Fixed(0.0),
             start_ang_vel: ValueGenerator::Fixed(0.0),
             start_size: ValueGenerator::Fixed(1.0),
@@ -505,10 +1094,68 @@ impl ParticleSystem {
             residual_particle: 0.0,
 
             delta_size: Transition::fixed(1.0),
-            delta_color: Transition::fixed((255, 255, 255).into()),
+            delta_color: None,
+            delta_alpha: None,
+            delta_ang_vel: None,
+            linear_damping: Transition::fixed(0.0),
+
+            position: Point2 { x: 0.0, y: 0.0 },
+            rotation: 0.0,
+            last_position: None,
+            inherit_velocity: 0.0,
+            simulation_space: SimulationSpace::Local,
+            rng: Random::new(),
+            sub_emitters: Vec::new(),
+            flipbook: None,
+            trails: None,
+            draw_order: DrawOrder::NewestOnTop,
+            emitter_lifetime: None,
+            emitter_age: 0.0,
+            paused: false,
+            time_scale: 1.0,
 
             sprite_batch: RefCell::new(sprite_batch),
             sprite_batch_dirty: Cell::new(true),
+            trail_vertices: RefCell::new(Vec::new()),
+            trail_indices: RefCell::new(Vec::new()),
+            draw_indices: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// Moves the emitter.  Emission shapes are relative to it, and
+    /// the system's `SimulationSpace` says whether particles that
+    /// have already been emitted move with it.
+    pub fn set_position(&mut self, position: Point2<f32>) {
+        self.position = position;
+        self.sprite_batch_dirty.set(true);
+    }
+
+    pub fn position(&self) -> Point2<f32> {
+        self.position
+    }
+
+    /// Moves and turns the emitter, for instance to follow a ship's
+    /// exhaust around.  New particles' emission shape, velocity and
+    /// angle are rotated by `rotation` radians; ones already emitted
+    /// aren't.
+    pub fn set_emitter_transform(&mut self, position: Point2<f32>, rotation: f32) {
+        self.set_position(position);
+        self.rotation = rotation;
+    }
+
+    pub fn rotation(&self) -> f32 {
+        self.rotation
+    }
+
+    // Where a particle gets drawn, relative to the emitter in local
+    // space.
+    fn draw_offset(&self) -> Vector2<f32> {
+        match self.simulation_space {
+            SimulationSpace::Local => Vector2 {
+                x: self.position.x,
+                y: self.position.y,
+            },
+            SimulationSpace::World => Vector2 { x: 0.0, y: 0.0 },
         }
     }
 
@@ -520,48 +1167,266 @@ impl ParticleSystem {
 
     /// Number of living particles.
     pub fn count(&self) -> usize {
-        return self.particles.len();
+        self.particles.living
+    }
+
+    /// Whether the emitter's lifetime, if it has one, hasn't run
+    /// out yet.
+    pub fn is_emitting(&self) -> bool {
+        self.emitter_lifetime
+            .map_or(true, |lifetime| self.emitter_age < lifetime)
+    }
+
+    /// Whether the system has stopped emitting and all its particles,
+    /// including its sub-emitters', are gone, so it can be thrown
+    /// away.
+    pub fn is_finished(&self) -> bool {
+        !self.is_emitting()
+            && self.particles.living == 0
+            && self.sub_emitters.iter().all(|sub| sub.system.is_finished())
+    }
+
+    /// Starts the emitter's lifetime over, so a one-off effect can
+    /// be played again.
+    pub fn restart(&mut self) {
+        self.emitter_age = 0.0;
+        self.residual_particle = 0.0;
+    }
+
+    /// Freezes the system; `update()` does nothing until `resume()`.
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Makes the system run faster or slower; `update()` multiplies
+    /// its `dt` by this.  1 by default.
+    pub fn set_time_scale(&mut self, scale: f32) {
+        self.time_scale = scale.max(0.0);
+    }
+
+    pub fn time_scale(&self) -> f32 {
+        self.time_scale
+    }
+
+    /// Reseeds the system's random number generator; see
+    /// `ParticleSystemBuilder::seed()`.
+    pub fn reseed(&mut self, seed: u64) {
+        self.rng = Random::with_seed(seed);
     }
 
     pub fn emit_one(&mut self) {
-        let pos = self.start_shape.get_random();
-        let vec = self.start_velocity.get_value();
-        let col = self.start_color.get_value();
-        let size = self.start_size.get_value();
-        let max_age = self.start_max_age.get_value();
-        let angle = self.start_angle.get_value();
-        let ang_vel = self.start_ang_vel.get_value();
+        let mut rng = self.rng.clone();
+        self.emit_one_with(&mut rng);
+        self.rng = rng;
+    }
+
+    /// Same as `emit_one()` but takes its random numbers from the
+    /// given `Rng` rather than the system's own.
+    pub fn emit_one_with<R: Rng>(&mut self, rng: &mut R) {
+        let origin = self.emitter_origin();
+        self.emit_at(origin, Vector2 { x: 0.0, y: 0.0 }, rng);
+    }
+
+    // Where the emitter is, in the space particles are simulated in.
+    fn emitter_origin(&self) -> Point2<f32> {
+        match self.simulation_space {
+            SimulationSpace::Local => Point2 { x: 0.0, y: 0.0 },
+            SimulationSpace::World => self.position,
+        }
+    }
+
+    /// Emits `count` particles at once, with the emission shape
+    /// centered on `origin` rather than the emitter.  `origin` is
+    /// where particles are drawn from, ignoring the emitter's
+    /// position even in local space.
+    pub fn burst_at(&mut self, origin: Point2<f32>, count: usize) {
+        let mut rng = self.rng.clone();
+        self.burst_at_with(origin, count, &mut rng);
+        self.rng = rng;
+    }
+
+    /// Same as `burst_at()` but takes its random numbers from the
+    /// given `Rng`.
+    pub fn burst_at_with<R: Rng>(&mut self, origin: Point2<f32>, count: usize, rng: &mut R) {
+        let offset = self.draw_offset();
+        let origin = Point2 {
+            x: origin.x - offset.x,
+            y: origin.y - offset.y,
+        };
+        for _ in 0..count {
+            self.emit_at(origin, Vector2 { x: 0.0, y: 0.0 }, rng);
+        }
+    }
+
+    fn emit_at<R: Rng>(&mut self, origin: Point2<f32>, inherited: Vector2<f32>, rng: &mut R) {
+        if self.particles.living >= self.max_particles {
+            return;
+        }
+        let shape_pos = self.start_shape.get_random(rng);
+        let offset = rotate(
+            Vector2 {
+                x: shape_pos.x,
+                y: shape_pos.y,
+            },
+            self.rotation,
+        );
+        let pos = Point2 {
+            x: origin.x + offset.x,
+            y: origin.y + offset.y,
+        };
+        let mut vec = rotate(self.start_velocity.get_value(rng), self.rotation);
+        vec.x += inherited.x;
+        vec.y += inherited.y;
+        let col = self.start_color.get_value(rng);
+        let size = self.start_size.get_value(rng);
+        let max_age = self.start_max_age.get_value(rng);
+        let angle = self.start_angle.get_value(rng) + self.rotation;
+        let ang_vel = self.start_ang_vel.get_value(rng);
         let mut newparticle = Particle::new(pos, vec, col, size, angle, max_age);
         newparticle.ang_vel = ang_vel;
-        if self.particles.len() <= self.max_particles {
-            self.particles.push(newparticle);
+        if let Some(flipbook) = self.flipbook {
+            if flipbook.mode == FlipbookMode::Random {
+                newparticle.frame = rng.gen_range(0, flipbook.frames());
+            }
         }
+        self.particles.push(newparticle);
     }
 
     pub fn update(&mut self, dt: f32) {
+        let mut rng = self.rng.clone();
+        self.update_with(dt, &mut rng);
+        self.rng = rng;
+    }
+
+    /// Same as `update()` but takes its random numbers from the
+    /// given `Rng`, so several systems can share one deterministic
+    /// source.
+    pub fn update_with<R: Rng>(&mut self, dt: f32, rng: &mut R) {
+        let last_position = self.last_position.replace(self.position);
+        if self.paused {
+            return;
+        }
+        let dt = dt * self.time_scale;
+        // Only emit for however much of this frame the emitter was
+        // still alive.
+        let emit_dt = match self.emitter_lifetime {
+            Some(lifetime) => (lifetime - self.emitter_age).clamp(0.0, dt),
+            None => dt,
+        };
+        self.emitter_age += dt;
         // This is tricky 'cause we have to keep the emission rate
         // correct and constant.  So we "accumulate" particles over
         // time until we have >1 of them and then emit it.
-        let num_to_emit = self.emission_rate * dt + self.residual_particle;
+        let num_to_emit = self.emission_rate * emit_dt + self.residual_particle;
         let actual_num_to_emit = num_to_emit.trunc() as usize;
         self.residual_particle = num_to_emit.fract();
-        for _ in 0..actual_num_to_emit {
-            self.emit_one()
+        let movement = match (self.simulation_space, last_position) {
+            (SimulationSpace::World, Some(last)) => Vector2 {
+                x: self.position.x - last.x,
+                y: self.position.y - last.y,
+            },
+            _ => Vector2 { x: 0.0, y: 0.0 },
+        };
+        let inherited = if dt > 0.0 {
+            Vector2 {
+                x: movement.x / dt * self.inherit_velocity,
+                y: movement.y / dt * self.inherit_velocity,
+            }
+        } else {
+            Vector2 { x: 0.0, y: 0.0 }
+        };
+        let origin = self.emitter_origin();
+        for i in 0..actual_num_to_emit {
+            let behind = spawn_lag(i, actual_num_to_emit);
+            let spawn_origin = Point2 {
+                x: origin.x - movement.x * behind,
+                y: origin.y - movement.y * behind,
+            };
+            self.emit_at(spawn_origin, inherited, rng);
+        }
+        let n = self.particles.living;
+        let ps = &mut self.particles;
+        for i in 0..n {
+            let life_fraction = ps.age[i] / ps.max_age[i];
+            let damping = 1.0 / (1.0 + self.linear_damping.get(life_fraction) * dt);
+            let vel = &mut ps.vel[i];
+            vel.x = (vel.x + self.acceleration.x * dt) * damping;
+            vel.y = (vel.y + self.acceleration.y * dt) * damping;
+        }
+        for (pos, vel) in ps.pos[..n].iter_mut().zip(&ps.vel[..n]) {
+            pos.x += vel.x * dt;
+            pos.y += vel.y * dt;
+        }
+        match &self.delta_ang_vel {
+            None => {
+                for (angle, ang_vel) in ps.angle[..n].iter_mut().zip(&ps.ang_vel[..n]) {
+                    *angle += ang_vel * dt;
+                }
+            }
+            Some(scale) => {
+                for i in 0..n {
+                    ps.angle[i] += ps.ang_vel[i] * scale.get(ps.age[i] / ps.max_age[i]) * dt;
+                }
+            }
+        }
+        for i in 0..n {
+            ps.size[i] = self.delta_size.get(ps.age[i] / ps.max_age[i]);
+        }
+        if let Some(color) = &self.delta_color {
+            for i in 0..n {
+                ps.color[i] = color.get(ps.age[i] / ps.max_age[i]);
+            }
+        }
+        if let Some(alpha) = &self.delta_alpha {
+            for i in 0..n {
+                ps.color[i].a = alpha.get(ps.age[i] / ps.max_age[i]);
+            }
+        }
+        if let Some(flipbook) = self.flipbook {
+            if flipbook.mode == FlipbookMode::ByAge {
+                for i in 0..n {
+                    ps.frame[i] = flipbook.frame_at(ps.age[i] / ps.max_age[i]);
+                }
+            }
+        }
+        if let Some((max_points, _)) = self.trails {
+            for (trail, pos) in ps.trail[..n].iter_mut().zip(&ps.pos[..n]) {
+                if trail.len() >= max_points {
+                    trail.pop_front();
+                }
+                trail.push_back(*pos);
+            }
+        }
+        for age in ps.age[..n].iter_mut() {
+            *age += dt;
         }
-        for mut p in self.particles.iter_mut() {
-            let life_fraction = p.age / p.max_age;
-            p.vel.x += self.acceleration.x * dt;
-            p.vel.y += self.acceleration.y * dt;
-            p.pos.x += p.vel.x * dt;
-            p.pos.y += p.vel.y * dt;
-            p.age += dt;
-            p.angle += p.ang_vel;
 
-            p.size = self.delta_size.get(life_fraction);
-            p.color = self.delta_color.get(life_fraction);
+        if !self.sub_emitters.is_empty() {
+            let offset = self.draw_offset();
+            let ps = &self.particles;
+            for i in (0..n).filter(|&i| ps.is_dead(i)) {
+                let pos = Point2 {
+                    x: ps.pos[i].x + offset.x,
+                    y: ps.pos[i].y + offset.y,
+                };
+                for sub in self.sub_emitters.iter_mut() {
+                    sub.system.burst_at_with(pos, sub.burst, rng);
+                }
+            }
+            for sub in self.sub_emitters.iter_mut() {
+                sub.system.update_with(dt, rng);
+            }
         }
 
-        self.particles.retain(|p| p.age < p.max_age);
+        self.particles.recycle_dead();
         self.sprite_batch_dirty.set(true);
     }
 }
@@ -573,25 +1438,64 @@ impl graphics::Drawable for ParticleSystem {
             use std::ops::DerefMut;
             let mut sb_ref = self.sprite_batch.borrow_mut();
             let sb = sb_ref.deref_mut();
+            // Clearing keeps the batch's capacity, so this doesn't
+            // allocate once it's seen the most particles it will.
             sb.clear();
-            for particle in &self.particles {
-                let drawparam = graphics::DrawParam {
-                    dest: particle.pos,
-                    rotation: particle.angle,
+            let offset = self.draw_offset();
+            let ps = &self.particles;
+            // Particles are stored in the order they were emitted,
+            // which is also oldest first.
+            let mut order = self.draw_indices.borrow_mut();
+            order.clear();
+            order.extend(0..ps.living);
+            match self.draw_order {
+                DrawOrder::NewestOnTop => (),
+                DrawOrder::OldestOnTop => order.reverse(),
+                DrawOrder::ByY => {
+                    order.sort_unstable_by(|&a, &b| ps.pos[a].y.total_cmp(&ps.pos[b].y))
+                }
+            }
+            for &i in order.iter() {
+                sb.add(graphics::DrawParam {
+                    dest: Point2 {
+                        x: ps.pos[i].x + offset.x,
+                        y: ps.pos[i].y + offset.y,
+                    },
+                    rotation: ps.angle[i],
                     scale: Vector2 {
-                        x: particle.size,
-                        y: particle.size,
+                        x: ps.size[i],
+                        y: ps.size[i],
                     },
                     offset: Point2 { x: 0.5, y: 0.5 },
-                    color: particle.color,
-                    ..Default::default()
-                };
-                sb.add(drawparam);
+                    color: ps.color[i],
+                    src: self
+                        .flipbook
+                        .map_or(graphics::Rect::one(), |f| f.src(ps.frame[i])),
+                });
             }
             self.sprite_batch_dirty.set(false);
         }
 
+        if let Some((_, width)) = self.trails {
+            let offset = self.draw_offset();
+            let mut vertices = self.trail_vertices.borrow_mut();
+            let mut indices = self.trail_indices.borrow_mut();
+            vertices.clear();
+            indices.clear();
+            let ps = &self.particles;
+            for (trail, &color) in ps.trail[..ps.living].iter().zip(&ps.color) {
+                trail::add_ribbon(trail, offset, width, color, &mut vertices, &mut indices);
+            }
+            if !indices.is_empty() {
+                let mut mesh = graphics::Mesh::from_raw(context, &vertices, &indices, None)?;
+                mesh.set_blend_mode(self.blend_mode());
+                graphics::draw(context, &mesh, param)?;
+            }
+        }
         self.sprite_batch.borrow().draw(context, param)?;
+        for sub in &self.sub_emitters {
+            sub.system.draw(context, param)?;
+        }
         Ok(())
     }
 
@@ -604,26 +1508,217 @@ impl graphics::Drawable for ParticleSystem {
     }
 
     fn dimensions(&self, _ctx: &mut Context) -> Option<graphics::Rect> {
-        if self.particles.is_empty() {
+        if self.particles.living == 0 {
             None
         } else {
+            let offset = self.draw_offset();
             let mut x = f32::MAX;
             let mut y = f32::MAX;
             let mut size = f32::MIN;
 
-            for particle in &self.particles {
-                if particle.pos.x < x {
-                    x = particle.pos.x;
+            let ps = &self.particles;
+            for (pos, &particle_size) in ps.pos[..ps.living].iter().zip(&ps.size) {
+                if pos.x < x {
+                    x = pos.x;
                 }
-                if particle.pos.y < y {
-                    y = particle.pos.y;
+                if pos.y < y {
+                    y = pos.y;
                 }
-                if particle.size > size {
-                    size = particle.size;
+                if particle_size > size {
+                    size = particle_size;
                 }
             }
 
-            Some(graphics::Rect::new(x, y, size, size))
+            Some(graphics::Rect::new(x + offset.x, y + offset.y, size, size))
+        }
+    }
+}
+
+impl crate::perf::PerfSource for ParticleSystem {
+    fn report(&self, perf: &mut crate::perf::PerfHud) {
+        perf.add_counter("particles", self.count() as i64);
+        perf.add_counter("particle systems", 1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn length(x: f32, y: f32) -> f32 {
+        (x * x + y * y).sqrt()
+    }
+
+    #[test]
+    fn test_eased_transition() {
+        let linear = Transition::range(0.0, 10.0);
+        let eased = Transition::eased(0.0, 10.0, |t| t * t);
+        assert_eq!(linear.get(0.5), 5.0);
+        assert_eq!(eased.get(0.5), 2.5);
+        assert_eq!(eased.get(1.0), 10.0);
+    }
+
+    #[test]
+    fn test_transition_stops() {
+        let fire = Transition::stops(&[
+            (1.0, graphics::Color::new(0.0, 0.0, 0.0, 0.0)),
+            (0.25, graphics::Color::new(1.0, 0.5, 0.0, 1.0)),
+            (0.0, graphics::Color::new(1.0, 1.0, 0.0, 1.0)),
+        ]);
+        assert_eq!(fire.get(0.0), graphics::Color::new(1.0, 1.0, 0.0, 1.0));
+        assert_eq!(fire.get(0.125), graphics::Color::new(1.0, 0.75, 0.0, 1.0));
+        assert_eq!(fire.get(0.625), graphics::Color::new(0.5, 0.25, 0.0, 0.5));
+        assert_eq!(fire.get(1.5), graphics::Color::new(0.0, 0.0, 0.0, 0.0));
+        let one = Transition::stops(&[(0.5, 3.0)]);
+        assert_eq!(one.get(0.0), 3.0);
+        assert_eq!(one.get(1.0), 3.0);
+        // A NaN time from a data file sorts last instead of panicking.
+        match Transition::stops(&[(f32::NAN, 1.0), (0.0, 2.0)]) {
+            Transition::Stops(stops) => assert_eq!(stops[0], (0.0, 2.0)),
+            _ => unreachable!(),
+        }
+    }
+
+    #[cfg(feature = "serialize")]
+    #[test]
+    fn test_effect_desc_serialization() {
+        let desc: ParticleEffectDesc = serde_json::from_str(
+            r#"{
+                "emission_rate": 250.0,
+                "shape": { "Ring": [[0.0, 0.0], 4.0, 8.0] },
+                "color": [[0.0, [1.0, 1.0, 0.0, 1.0]], [1.0, [0.5, 0.5, 0.5, 0.0]]],
+                "image": "/spark.png"
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(desc.emission_rate, 250.0);
+        assert_eq!(
+            desc.shape,
+            EmissionShape::Ring(Point2 { x: 0.0, y: 0.0 }, 4.0, 8.0)
+        );
+        assert_eq!(desc.color.len(), 2);
+        assert_eq!(desc.image.as_deref(), Some("/spark.png"));
+        assert_eq!(
+            desc.max_particles,
+            ParticleEffectDesc::default().max_particles
+        );
+
+        let json = serde_json::to_string(&desc).unwrap();
+        let loaded: ParticleEffectDesc = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded, desc);
+    }
+
+    #[test]
+    fn test_flipbook() {
+        let flipbook = Flipbook {
+            columns: 4,
+            rows: 2,
+            mode: FlipbookMode::ByAge,
+        };
+        assert_eq!(flipbook.frame_at(0.0), 0);
+        assert_eq!(flipbook.frame_at(0.5), 4);
+        assert_eq!(flipbook.frame_at(0.99), 7);
+        assert_eq!(flipbook.frame_at(1.0), 7);
+        assert_eq!(flipbook.src(0), graphics::Rect::new(0.0, 0.0, 0.25, 0.5));
+        assert_eq!(flipbook.src(6), graphics::Rect::new(0.5, 0.5, 0.25, 0.5));
+    }
+
+    #[test]
+    fn test_rotate() {
+        let v = Vector2 { x: 2.0, y: 0.0 };
+        assert_eq!(rotate(v, 0.0), v);
+        let turned = rotate(v, f32::consts::FRAC_PI_2);
+        assert!(turned.x.abs() < 1e-6);
+        assert!((turned.y - 2.0).abs() < 1e-6);
+        let back = rotate(turned, -f32::consts::FRAC_PI_2);
+        assert!((back.x - 2.0).abs() < 1e-6 && back.y.abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_spawn_lag() {
+        assert_eq!(spawn_lag(0, 1), 0.0);
+        let lags: Vec<f32> = (0..4).map(|i| spawn_lag(i, 4)).collect();
+        assert_eq!(lags, vec![0.75, 0.5, 0.25, 0.0]);
+    }
+
+    #[test]
+    fn test_recycle_dead() {
+        let mut particles = Particles::default();
+        for &max_age in &[0.5, 2.0, 1.0, 0.2, 3.0] {
+            particles.push(Particle::new(
+                Point2 { x: max_age, y: 0.0 },
+                Vector2 { x: 0.0, y: 0.0 },
+                graphics::WHITE,
+                1.0,
+                0.0,
+                max_age,
+            ));
+            particles.trail[particles.living - 1].push_back(Point2 { x: 0.0, y: 0.0 });
+        }
+        for age in particles.age.iter_mut() {
+            *age = 1.0;
+        }
+        particles.recycle_dead();
+        assert_eq!(particles.living, 2);
+        // The living keep their order, and nothing's lost.
+        assert_eq!(&particles.max_age[..2], &[2.0, 3.0]);
+        assert_eq!(particles.pos[1].x, 3.0);
+        assert_eq!(particles.max_age.len(), 5);
+
+        // New particles go in the dead ones' slots, fresh.
+        particles.push(Particle::new(
+            Point2 { x: 4.0, y: 0.0 },
+            Vector2 { x: 0.0, y: 0.0 },
+            graphics::WHITE,
+            1.0,
+            0.0,
+            4.0,
+        ));
+        assert_eq!(particles.living, 3);
+        assert_eq!(particles.pos.len(), 5);
+        assert_eq!(particles.pos[2].x, 4.0);
+        assert_eq!(particles.age[2], 0.0);
+        assert!(particles.trail[2].is_empty());
+    }
+
+    #[test]
+    fn test_emission_shapes() {
+        let mut rng = Random::with_seed(1);
+        let center = Point2 { x: 10.0, y: -5.0 };
+        let ring = EmissionShape::Ring(center, 3.0, 4.0);
+        let rect = EmissionShape::Rect(Point2 { x: 2.0, y: 1.0 }, Point2 { x: -2.0, y: 1.0 });
+        for _ in 0..100 {
+            let p = ring.get_random(&mut rng);
+            let r = length(p.x - center.x, p.y - center.y);
+            assert!((3.0 - 1e-4..=4.0 + 1e-4).contains(&r));
+            let p = rect.get_random(&mut rng);
+            assert!((-2.0..=2.0).contains(&p.x));
+            assert_eq!(p.y, 1.0);
+        }
+
+        // Degenerate shapes from data files emit from a point rather
+        // than panicking.
+        let dot = EmissionShape::Circle(center, 0.0);
+        assert_eq!(dot.get_random(&mut rng), center);
+        let stub = EmissionShape::Line(center, center);
+        assert_eq!(stub.get_random(&mut rng), center);
+    }
+
+    #[test]
+    fn test_velocity_cone() {
+        let mut rng = Random::with_seed(2);
+        let cone = VelocityGenerator::Cone {
+            direction: f32::consts::FRAC_PI_2,
+            spread: 0.5,
+            min_speed: 10.0,
+            max_speed: 20.0,
+        };
+        for _ in 0..100 {
+            let v = cone.get_value(&mut rng);
+            let speed = length(v.x, v.y);
+            assert!((10.0 - 1e-3..=20.0 + 1e-3).contains(&speed));
+            let angle = v.y.atan2(v.x);
+            assert!((angle - f32::consts::FRAC_PI_2).abs() <= 0.25 + 1e-4);
         }
     }
 }
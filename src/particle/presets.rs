@@ -0,0 +1,200 @@
+//! Ready-made particle effects, as `ParticleEffectDesc`'s.
+//!
+//! Each takes the path of the image to draw particles with, or `None`
+//! for plain squares, and a scale that multiplies distances, speeds
+//! and sizes.  Sizes are tuned for the default 5 pixel squares, so
+//! with an image you'll usually want to tweak them:
+//!
+//! ```rust,ignore
+//! let mut desc = presets::fire(Some("/flame.png"), 1.0);
+//! desc.size = vec![(0.0, 0.5), (1.0, 0.1)];
+//! let fire = desc.build(ctx)?;
+//! ```
+//!
+//! Rain and snow fall from a strip 400 units wide above the emitter,
+//! and are simulated in world space so they keep falling straight
+//! if the emitter follows the camera around.
+
+use std::f32::consts::PI;
+
+use ggez::mint::{Point2, Vector2};
+
+use super::{EmissionShape, ParticleEffectDesc, SimulationSpace, VelocityDesc};
+
+fn point(x: f32, y: f32, scale: f32) -> Point2<f32> {
+    Point2 {
+        x: x * scale,
+        y: y * scale,
+    }
+}
+
+fn vector(x: f32, y: f32, scale: f32) -> Vector2<f32> {
+    Vector2 {
+        x: x * scale,
+        y: y * scale,
+    }
+}
+
+fn sizes(stops: &[(f32, f32)], scale: f32) -> Vec<(f32, f32)> {
+    stops.iter().map(|&(t, size)| (t, size * scale)).collect()
+}
+
+fn upwards(spread: f32, min_speed: f32, max_speed: f32, scale: f32) -> VelocityDesc {
+    VelocityDesc::Cone {
+        direction: -PI / 2.0,
+        spread,
+        min_speed: min_speed * scale,
+        max_speed: max_speed * scale,
+    }
+}
+
+/// Flames licking upwards, yellow to red to smoke.
+pub fn fire(image: Option<&str>, scale: f32) -> ParticleEffectDesc {
+    ParticleEffectDesc {
+        max_particles: 300,
+        emission_rate: 150.0,
+        shape: EmissionShape::Circle(point(0.0, 0.0, scale), 8.0 * scale),
+        velocity: upwards(0.6, 40.0, 80.0, scale),
+        acceleration: vector(0.0, -20.0, scale),
+        lifetime: (0.6, 1.0),
+        size: sizes(&[(0.0, 2.0), (1.0, 0.5)], scale),
+        color: vec![
+            (0.0, [1.0, 0.9, 0.3, 1.0]),
+            (0.3, [1.0, 0.5, 0.1, 0.9]),
+            (0.7, [0.8, 0.1, 0.0, 0.6]),
+            (1.0, [0.1, 0.1, 0.1, 0.0]),
+        ],
+        image: image.map(str::to_owned),
+        ..ParticleEffectDesc::default()
+    }
+}
+
+/// Slow grey puffs that grow and fade.
+pub fn smoke(image: Option<&str>, scale: f32) -> ParticleEffectDesc {
+    ParticleEffectDesc {
+        max_particles: 120,
+        emission_rate: 30.0,
+        shape: EmissionShape::Circle(point(0.0, 0.0, scale), 6.0 * scale),
+        velocity: upwards(0.5, 15.0, 30.0, scale),
+        lifetime: (2.0, 3.0),
+        spin: (-1.2, 1.2),
+        size: sizes(&[(0.0, 1.0), (1.0, 4.0)], scale),
+        color: vec![(0.0, [0.8, 0.8, 0.8, 1.0]), (1.0, [0.4, 0.4, 0.4, 1.0])],
+        alpha: vec![(0.0, 0.0), (0.1, 0.5), (1.0, 0.0)],
+        damping: 0.3,
+        image: image.map(str::to_owned),
+        ..ParticleEffectDesc::default()
+    }
+}
+
+/// Hot sparks flying every which way and falling.
+pub fn sparks(image: Option<&str>, scale: f32) -> ParticleEffectDesc {
+    ParticleEffectDesc {
+        max_particles: 100,
+        emission_rate: 60.0,
+        velocity: VelocityDesc::Cone {
+            direction: 0.0,
+            spread: PI * 2.0,
+            min_speed: 100.0 * scale,
+            max_speed: 200.0 * scale,
+        },
+        acceleration: vector(0.0, 300.0, scale),
+        lifetime: (0.3, 0.6),
+        size: sizes(&[(0.0, 0.6)], scale),
+        color: vec![
+            (0.0, [1.0, 1.0, 1.0, 1.0]),
+            (0.3, [1.0, 0.9, 0.4, 1.0]),
+            (1.0, [1.0, 0.4, 0.0, 0.0]),
+        ],
+        damping: 1.0,
+        image: image.map(str::to_owned),
+        ..ParticleEffectDesc::default()
+    }
+}
+
+/// Drops falling fast and slightly slanted.
+pub fn rain(image: Option<&str>, scale: f32) -> ParticleEffectDesc {
+    ParticleEffectDesc {
+        max_particles: 300,
+        emission_rate: 200.0,
+        shape: EmissionShape::Rect(point(-200.0, -10.0, scale), point(200.0, 0.0, scale)),
+        velocity: VelocityDesc::Range(vector(-20.0, 400.0, scale), vector(-10.0, 500.0, scale)),
+        lifetime: (1.0, 1.2),
+        size: sizes(&[(0.0, 0.4)], scale),
+        color: vec![(0.0, [0.6, 0.7, 1.0, 0.6])],
+        simulation_space: SimulationSpace::World,
+        image: image.map(str::to_owned),
+        ..ParticleEffectDesc::default()
+    }
+}
+
+/// Flakes drifting down.
+pub fn snow(image: Option<&str>, scale: f32) -> ParticleEffectDesc {
+    ParticleEffectDesc {
+        max_particles: 300,
+        emission_rate: 30.0,
+        shape: EmissionShape::Rect(point(-200.0, -10.0, scale), point(200.0, 0.0, scale)),
+        velocity: VelocityDesc::Range(vector(-20.0, 30.0, scale), vector(20.0, 60.0, scale)),
+        lifetime: (6.0, 8.0),
+        spin: (-3.0, 3.0),
+        size: sizes(&[(0.0, 0.6)], scale),
+        color: vec![(0.0, [1.0, 1.0, 1.0, 0.9])],
+        alpha: vec![(0.0, 0.9), (0.9, 0.9), (1.0, 0.0)],
+        simulation_space: SimulationSpace::World,
+        image: image.map(str::to_owned),
+        ..ParticleEffectDesc::default()
+    }
+}
+
+/// Colorful bits thrown up that tumble down.
+pub fn confetti(image: Option<&str>, scale: f32) -> ParticleEffectDesc {
+    ParticleEffectDesc {
+        max_particles: 200,
+        emission_rate: 80.0,
+        velocity: upwards(1.2, 150.0, 300.0, scale),
+        acceleration: vector(0.0, 200.0, scale),
+        lifetime: (2.0, 3.0),
+        spin: (-18.0, 18.0),
+        size: sizes(&[(0.0, 1.0)], scale),
+        start_color: ([0.2, 0.2, 0.2, 1.0], [1.0, 1.0, 1.0, 1.0]),
+        damping: 1.5,
+        image: image.map(str::to_owned),
+        ..ParticleEffectDesc::default()
+    }
+}
+
+/// A twinkle of little stars, for picking things up.
+pub fn sparkle(image: Option<&str>, scale: f32) -> ParticleEffectDesc {
+    ParticleEffectDesc {
+        max_particles: 50,
+        emission_rate: 40.0,
+        shape: EmissionShape::Ring(point(0.0, 0.0, scale), 4.0 * scale, 12.0 * scale),
+        velocity: upwards(0.8, 10.0, 30.0, scale),
+        lifetime: (0.4, 0.8),
+        spin: (-12.0, 12.0),
+        size: sizes(&[(0.0, 0.2), (0.3, 0.8), (1.0, 0.0)], scale),
+        color: vec![(0.0, [1.0, 1.0, 0.8, 1.0]), (1.0, [1.0, 0.9, 0.3, 0.0])],
+        image: image.map(str::to_owned),
+        ..ParticleEffectDesc::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_presets_scale() {
+        let presets: &[fn(Option<&str>, f32) -> ParticleEffectDesc] =
+            &[fire, smoke, sparks, rain, snow, confetti, sparkle];
+        for preset in presets {
+            let small = preset(None, 1.0);
+            let big = preset(Some("/spark.png"), 2.0);
+            assert!(small.max_particles > 0);
+            assert_eq!(small.image, None);
+            assert_eq!(big.image.as_deref(), Some("/spark.png"));
+            assert_eq!(big.acceleration.y, small.acceleration.y * 2.0);
+            assert_eq!(big.size[0].1, small.size[0].1 * 2.0);
+        }
+    }
+}
@@ -0,0 +1,235 @@
+//! Ribbons that follow moving points, narrowing and fading towards
+//! their tails, for projectiles, comets and the like.
+//!
+//! A `Trail` follows any point you like; push its position every
+//! update and draw it:
+//!
+//! ```rust,ignore
+//! self.trail.push(self.bullet.pos);
+//! // ...and in draw():
+//! graphics::draw(ctx, &self.trail, DrawParam::default())?;
+//! ```
+//!
+//! Particles can leave trails of their own, with
+//! `ParticleSystemBuilder::trails()`.
+
+use std::collections::VecDeque;
+
+use ggez::graphics::{self, BlendMode, Color, DrawParam, Image, Rect, Vertex};
+use ggez::mint::{Point2, Vector2};
+use ggez::{Context, GameResult};
+
+/// A ribbon through a point's most recent positions.  See the
+/// module docs.
+#[derive(Debug, Clone)]
+pub struct Trail {
+    points: VecDeque<Point2<f32>>,
+    max_points: usize,
+    width: f32,
+    color: Color,
+    image: Option<Image>,
+    blend_mode: Option<BlendMode>,
+}
+
+impl Trail {
+    /// Makes a trail through the last `max_points` positions pushed,
+    /// `width` wide at the head.
+    pub fn new(max_points: usize, width: f32) -> Self {
+        Trail {
+            points: VecDeque::with_capacity(max_points),
+            max_points,
+            width,
+            color: graphics::WHITE,
+            image: None,
+            blend_mode: None,
+        }
+    }
+
+    /// Sets the color at the head of the trail.  It fades out towards
+    /// the tail.
+    pub fn set_color(&mut self, color: Color) {
+        self.color = color;
+    }
+
+    /// Sets an image to stretch along the trail, tail on the left
+    /// and head on the right, rather than drawing it plain.
+    pub fn set_image(&mut self, image: Option<Image>) {
+        self.image = image;
+    }
+
+    /// Adds a new head position, dropping the oldest once there are
+    /// `max_points`.
+    pub fn push(&mut self, point: Point2<f32>) {
+        if self.points.len() >= self.max_points {
+            self.points.pop_front();
+        }
+        self.points.push_back(point);
+    }
+
+    /// Forgets all the positions, say when the thing it's following
+    /// teleports.
+    pub fn clear(&mut self) {
+        self.points.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+}
+
+impl graphics::Drawable for Trail {
+    fn draw(&self, ctx: &mut Context, param: DrawParam) -> GameResult {
+        let mut vertices = Vec::new();
+        let mut indices = Vec::new();
+        add_ribbon(
+            &self.points,
+            Vector2 { x: 0.0, y: 0.0 },
+            self.width,
+            self.color,
+            &mut vertices,
+            &mut indices,
+        );
+        if indices.is_empty() {
+            return Ok(());
+        }
+        let mut mesh = graphics::Mesh::from_raw(ctx, &vertices, &indices, self.image.clone())?;
+        mesh.set_blend_mode(self.blend_mode);
+        graphics::draw(ctx, &mesh, param)
+    }
+
+    fn dimensions(&self, _ctx: &mut Context) -> Option<Rect> {
+        let first = self.points.front()?;
+        let mut rect = Rect::new(first.x, first.y, 0.0, 0.0);
+        for p in &self.points {
+            let right = rect.right().max(p.x);
+            let bottom = rect.bottom().max(p.y);
+            rect.x = rect.x.min(p.x);
+            rect.y = rect.y.min(p.y);
+            rect.w = right - rect.x;
+            rect.h = bottom - rect.y;
+        }
+        Some(rect)
+    }
+
+    fn set_blend_mode(&mut self, mode: Option<BlendMode>) {
+        self.blend_mode = mode;
+    }
+
+    fn blend_mode(&self) -> Option<BlendMode> {
+        self.blend_mode
+    }
+}
+
+// Vertex colors skip the sRGB conversion ggez does for everything
+// else, so we do it ourselves.
+fn linear_color(color: Color) -> [f32; 4] {
+    fn f(c: f32) -> f32 {
+        if c <= 0.04045 {
+            c / 12.92
+        } else {
+            ((c + 0.055) / 1.055).powf(2.4)
+        }
+    }
+    [f(color.r), f(color.g), f(color.b), color.a]
+}
+
+/// Adds a ribbon through `points`, oldest first and moved by
+/// `offset`, to a mesh's vertices and indices.  It tapers from
+/// `width` and `color` at the newest point to nothing at the oldest.
+pub(super) fn add_ribbon(
+    points: &VecDeque<Point2<f32>>,
+    offset: Vector2<f32>,
+    width: f32,
+    color: Color,
+    vertices: &mut Vec<Vertex>,
+    indices: &mut Vec<u32>,
+) {
+    let n = points.len();
+    if n < 2 {
+        return;
+    }
+    let first = vertices.len() as u32;
+    let mut normal = [0.0, -1.0];
+    for i in 0..n {
+        let before = points[i.saturating_sub(1)];
+        let after = points[(i + 1).min(n - 1)];
+        let (dx, dy) = (after.x - before.x, after.y - before.y);
+        let len = (dx * dx + dy * dy).sqrt();
+        // Points on top of each other keep the last direction.
+        if len > 0.0 {
+            normal = [-dy / len, dx / len];
+        }
+        let t = i as f32 / (n - 1) as f32;
+        let half = width * t / 2.0;
+        let mut c = color;
+        c.a *= t;
+        let c = linear_color(c);
+        let p = Point2 {
+            x: points[i].x + offset.x,
+            y: points[i].y + offset.y,
+        };
+        vertices.push(Vertex {
+            pos: [p.x + normal[0] * half, p.y + normal[1] * half],
+            uv: [t, 0.0],
+            color: c,
+        });
+        vertices.push(Vertex {
+            pos: [p.x - normal[0] * half, p.y - normal[1] * half],
+            uv: [t, 1.0],
+            color: c,
+        });
+    }
+    for i in 0..(n as u32 - 1) {
+        let v = first + i * 2;
+        indices.extend_from_slice(&[v, v + 1, v + 2, v + 1, v + 3, v + 2]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ribbon() {
+        let mut trail = Trail::new(3, 2.0);
+        for x in 0..5 {
+            trail.push(Point2 {
+                x: x as f32,
+                y: 0.0,
+            });
+        }
+        assert_eq!(trail.len(), 3);
+
+        let mut vertices = Vec::new();
+        // Whatever's already in the mesh stays put.
+        vertices.push(Vertex {
+            pos: [0.0, 0.0],
+            uv: [0.0, 0.0],
+            color: [1.0; 4],
+        });
+        let mut indices = Vec::new();
+        add_ribbon(
+            &trail.points,
+            Vector2 { x: 0.0, y: 0.0 },
+            2.0,
+            graphics::WHITE,
+            &mut vertices,
+            &mut indices,
+        );
+        assert_eq!(vertices.len(), 7);
+        assert_eq!(indices.len(), 12);
+        assert_eq!(indices[0], 1);
+        // The tail comes to a point and is transparent...
+        assert_eq!(vertices[1].pos, vertices[2].pos);
+        assert_eq!(vertices[1].color[3], 0.0);
+        // ...and the head is the full width, across the direction
+        // it's going.
+        assert_eq!(vertices[5].pos, [4.0, 1.0]);
+        assert_eq!(vertices[6].pos, [4.0, -1.0]);
+        assert_eq!(vertices[6].color, [1.0; 4]);
+    }
+}
@@ -0,0 +1,369 @@
+//! A performance overlay: FPS, a graph of recent frame times split
+//! into update and draw, and whatever counters you care to feed it.
+//!
+//! Wrap your update and draw code in the scoped timers, call
+//! `end_frame()` once per frame, and draw the `PerfHud` like any
+//! other `Drawable`:
+//!
+//! ```rust,ignore
+//! fn update(&mut self, ctx: &mut Context) -> GameResult {
+//!     let _t = self.perf.time_update();
+//!     // ...
+//! }
+//!
+//! fn draw(&mut self, ctx: &mut Context) -> GameResult {
+//!     {
+//!         let _t = self.perf.time_draw();
+//!         // ...
+//!     }
+//!     self.particles.report(&mut self.perf);
+//!     graphics::draw(ctx, &self.perf, (Point2 { x: 10.0, y: 10.0 },))?;
+//!     self.perf.end_frame();
+//!     graphics::present(ctx)
+//! }
+//! ```
+//!
+//! Counters are just named numbers, reset every frame.  Other
+//! modules in this crate implement `PerfSource` to report their own
+//! counters, such as the number of live particles.
+
+use std::cell::Cell;
+use std::collections::BTreeMap;
+use std::time::Instant;
+
+use ggez::graphics::{self, BlendMode, Color, DrawMode, DrawParam, Drawable, Rect, Text};
+use ggez::mint::Point2;
+use ggez::timer;
+use ggez::{Context, GameResult};
+
+/// A fixed-size buffer that overwrites its oldest item when full.
+#[derive(Debug, Clone)]
+pub struct RingBuffer<T> {
+    items: Vec<T>,
+    capacity: usize,
+    // Index of the oldest item once the buffer has filled up.
+    start: usize,
+}
+
+impl<T> RingBuffer<T> {
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "RingBuffer must have a nonzero capacity");
+        RingBuffer {
+            items: Vec::with_capacity(capacity),
+            capacity,
+            start: 0,
+        }
+    }
+
+    /// Adds an item, dropping the oldest one if the buffer is full.
+    pub fn push(&mut self, item: T) {
+        if self.items.len() < self.capacity {
+            self.items.push(item);
+        } else {
+            self.items[self.start] = item;
+            self.start = (self.start + 1) % self.capacity;
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// The most recently pushed item.
+    pub fn last(&self) -> Option<&T> {
+        if self.items.is_empty() {
+            None
+        } else {
+            let i = (self.start + self.items.len() - 1) % self.items.len();
+            Some(&self.items[i])
+        }
+    }
+
+    /// Iterates from the oldest item to the newest.
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        let (newer, older) = self.items.split_at(self.start);
+        older.iter().chain(newer.iter())
+    }
+
+    pub fn clear(&mut self) {
+        self.items.clear();
+        self.start = 0;
+    }
+}
+
+/// Timings for one frame, in seconds.
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct FrameSample {
+    pub update: f32,
+    pub draw: f32,
+}
+
+impl FrameSample {
+    pub fn total(&self) -> f32 {
+        self.update + self.draw
+    }
+}
+
+/// Adds the time from its creation until it is dropped to
+/// one of a `PerfHud`'s timings.
+pub struct ScopedTimer<'a> {
+    target: &'a Cell<f32>,
+    start: Instant,
+}
+
+impl<'a> Drop for ScopedTimer<'a> {
+    fn drop(&mut self) {
+        let elapsed = timer::duration_to_f64(self.start.elapsed()) as f32;
+        self.target.set(self.target.get() + elapsed);
+    }
+}
+
+/// Something that can report performance counters to a `PerfHud`.
+pub trait PerfSource {
+    fn report(&self, perf: &mut PerfHud);
+}
+
+/// The performance overlay.  See the module docs.
+#[derive(Debug)]
+pub struct PerfHud {
+    enabled: bool,
+    history: RingBuffer<FrameSample>,
+    update_time: Cell<f32>,
+    draw_time: Cell<f32>,
+    counters: BTreeMap<String, i64>,
+    last_counters: BTreeMap<String, i64>,
+    /// Size of the frame time graph in pixels.
+    pub graph_size: (f32, f32),
+    /// Frame time that fills the graph's height, in seconds.
+    pub graph_scale: f32,
+    pub update_color: Color,
+    pub draw_color: Color,
+    pub background: Color,
+}
+
+impl PerfHud {
+    /// Creates a new `PerfHud` remembering the given number of
+    /// frames of history.
+    pub fn new(history_frames: usize) -> Self {
+        PerfHud {
+            enabled: true,
+            history: RingBuffer::new(history_frames),
+            update_time: Cell::new(0.0),
+            draw_time: Cell::new(0.0),
+            counters: BTreeMap::new(),
+            last_counters: BTreeMap::new(),
+            graph_size: (history_frames as f32 * 2.0, 60.0),
+            graph_scale: 1.0 / 30.0,
+            update_color: Color::new(0.2, 0.8, 0.2, 1.0),
+            draw_color: Color::new(0.2, 0.4, 1.0, 1.0),
+            background: Color::new(0.0, 0.0, 0.0, 0.6),
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Turns drawing on or off.  Timing and counters are still
+    /// recorded either way, it's cheap.
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    pub fn toggle(&mut self) {
+        self.enabled = !self.enabled;
+    }
+
+    /// Starts timing update code; the time is recorded when the
+    /// returned object is dropped.
+    pub fn time_update(&self) -> ScopedTimer<'_> {
+        ScopedTimer {
+            target: &self.update_time,
+            start: Instant::now(),
+        }
+    }
+
+    /// Starts timing draw code; the time is recorded when the
+    /// returned object is dropped.
+    pub fn time_draw(&self) -> ScopedTimer<'_> {
+        ScopedTimer {
+            target: &self.draw_time,
+            start: Instant::now(),
+        }
+    }
+
+    /// Sets a counter for this frame.
+    pub fn set_counter(&mut self, name: &str, value: i64) {
+        self.counters.insert(name.to_owned(), value);
+    }
+
+    /// Adds to a counter for this frame.
+    pub fn add_counter(&mut self, name: &str, amount: i64) {
+        *self.counters.entry(name.to_owned()).or_insert(0) += amount;
+    }
+
+    /// The value of a counter as of the last completed frame.
+    pub fn counter(&self, name: &str) -> Option<i64> {
+        self.last_counters.get(name).cloned()
+    }
+
+    /// Finishes the current frame, saving its timings to the history
+    /// and resetting the counters.
+    pub fn end_frame(&mut self) {
+        self.history.push(FrameSample {
+            update: self.update_time.replace(0.0),
+            draw: self.draw_time.replace(0.0),
+        });
+        self.last_counters = std::mem::take(&mut self.counters);
+    }
+
+    pub fn history(&self) -> &RingBuffer<FrameSample> {
+        &self.history
+    }
+
+    /// Average timings over the whole history.
+    pub fn average(&self) -> FrameSample {
+        if self.history.is_empty() {
+            return FrameSample::default();
+        }
+        let n = self.history.len() as f32;
+        let sum = self
+            .history
+            .iter()
+            .fold(FrameSample::default(), |acc, s| FrameSample {
+                update: acc.update + s.update,
+                draw: acc.draw + s.draw,
+            });
+        FrameSample {
+            update: sum.update / n,
+            draw: sum.draw / n,
+        }
+    }
+
+    fn text(&self, ctx: &Context) -> String {
+        let avg = self.average();
+        let mut s = format!(
+            "FPS: {:.1}\nupdate: {:.2} ms\ndraw: {:.2} ms",
+            timer::fps(ctx),
+            avg.update * 1000.0,
+            avg.draw * 1000.0
+        );
+        for (name, value) in &self.last_counters {
+            s.push_str(&format!("\n{}: {}", name, value));
+        }
+        s
+    }
+}
+
+impl Default for PerfHud {
+    fn default() -> Self {
+        Self::new(120)
+    }
+}
+
+impl Drawable for PerfHud {
+    fn draw(&self, ctx: &mut Context, param: DrawParam) -> GameResult<()> {
+        if !self.enabled {
+            return Ok(());
+        }
+        let text = Text::new(self.text(ctx));
+        let (text_w, text_h) = text.dimensions(ctx);
+        let (graph_w, graph_h) = self.graph_size;
+        let margin = 4.0;
+        let width = graph_w.max(text_w as f32) + margin * 2.0;
+        let height = text_h as f32 + graph_h + margin * 3.0;
+
+        let mut mb = graphics::MeshBuilder::new();
+        mb.rectangle(
+            DrawMode::fill(),
+            Rect::new(0.0, 0.0, width, height),
+            self.background,
+        );
+        let bar_w = graph_w / self.history.capacity() as f32;
+        let bottom = height - margin;
+        for (i, sample) in self.history.iter().enumerate() {
+            let x = margin + i as f32 * bar_w;
+            let update_h = (sample.update / self.graph_scale).min(1.0) * graph_h;
+            let draw_h = (sample.draw / self.graph_scale).min(1.0 - update_h / graph_h) * graph_h;
+            if update_h > 0.0 {
+                let r = Rect::new(x, bottom - update_h, bar_w, update_h);
+                mb.rectangle(DrawMode::fill(), r, self.update_color);
+            }
+            if draw_h > 0.0 {
+                let r = Rect::new(x, bottom - update_h - draw_h, bar_w, draw_h);
+                mb.rectangle(DrawMode::fill(), r, self.draw_color);
+            }
+        }
+        let mesh = mb.build(ctx)?;
+        graphics::draw(ctx, &mesh, param)?;
+        let text_param = param.dest(Point2 {
+            x: param.dest.x + margin,
+            y: param.dest.y + margin,
+        });
+        graphics::draw(ctx, &text, text_param)
+    }
+
+    fn dimensions(&self, _ctx: &mut Context) -> Option<Rect> {
+        None
+    }
+
+    fn set_blend_mode(&mut self, _mode: Option<BlendMode>) {}
+
+    fn blend_mode(&self) -> Option<BlendMode> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ring_buffer() {
+        let mut rb = RingBuffer::new(3);
+        assert!(rb.is_empty());
+        assert_eq!(rb.last(), None);
+        rb.push(1);
+        rb.push(2);
+        assert_eq!(rb.iter().cloned().collect::<Vec<_>>(), vec![1, 2]);
+        rb.push(3);
+        rb.push(4);
+        rb.push(5);
+        assert_eq!(rb.len(), 3);
+        assert_eq!(rb.iter().cloned().collect::<Vec<_>>(), vec![3, 4, 5]);
+        assert_eq!(rb.last(), Some(&5));
+    }
+
+    #[test]
+    fn test_frames_and_counters() {
+        let mut perf = PerfHud::new(10);
+        {
+            let _t = perf.time_update();
+        }
+        {
+            let _t = perf.time_draw();
+        }
+        perf.add_counter("draw calls", 2);
+        perf.add_counter("draw calls", 3);
+        perf.set_counter("entities", 7);
+        // Counters only show up once the frame ends.
+        assert_eq!(perf.counter("draw calls"), None);
+        perf.end_frame();
+        assert_eq!(perf.counter("draw calls"), Some(5));
+        assert_eq!(perf.counter("entities"), Some(7));
+        assert_eq!(perf.history().len(), 1);
+        assert!(perf.history().last().unwrap().total() >= 0.0);
+
+        perf.end_frame();
+        assert_eq!(perf.counter("draw calls"), None);
+        assert_eq!(perf.history().last(), Some(&FrameSample::default()));
+    }
+}
@@ -0,0 +1,458 @@
+//! Full-screen post-processing effects.
+//!
+//! Call `PostFx::begin()` before drawing your scene and
+//! `PostFx::end_and_draw()` after; in between everything gets drawn
+//! to an offscreen canvas, which is then run through each enabled
+//! effect in order and drawn to the screen.  You don't need to know
+//! anything about shaders or canvases to use the built-in effects:
+//!
+//! * `Vignette`: darkens the edges of the screen
+//! * `ChromaticAberration`: splits the color channels apart
+//! * `Flash`: fills the screen with a color that fades out, see
+//!   `PostFx::flash()`
+//! * `PaletteSwap`: maps brightness onto a four-color palette
+//! * `Crt`: scanlines and a curved screen
+//!
+//! You can also write your own with `Effect::Custom`.  Your pixel
+//! shader gets the same inputs as ggez's default shader plus this
+//! uniform block, of which the built-in effects use whichever parts
+//! they need:
+//!
+//! ```glsl
+//! layout (std140) uniform PostFxConsts {
+//!     mat4 u_Palette;     // four colors, one per column
+//!     vec4 u_Color;
+//!     vec4 u_Params;      // effect-specific
+//!     vec2 u_Resolution;  // in pixels
+//!     float u_Time;       // seconds since creation
+//! };
+//! ```
+//!
+//! Note that each pass is a full extra draw of the whole screen, so
+//! don't go too crazy.
+
+use gfx::{self, *};
+use ggez::graphics::{self, BlendMode, Canvas, Color, DrawParam, Drawable, Shader};
+use ggez::mint::{Point2, Vector2};
+use ggez::{Context, GameResult};
+
+gfx_defines! {
+    constant PostFxConsts {
+        palette: [[f32; 4]; 4] = "u_Palette",
+        color: [f32; 4] = "u_Color",
+        params: [f32; 4] = "u_Params",
+        resolution: [f32; 2] = "u_Resolution",
+        time: f32 = "u_Time",
+    }
+}
+
+// Same as ggez's default vertex shader.
+const VERTEX_SHADER: &[u8] = b"#version 150 core
+
+in vec2 a_Pos;
+in vec2 a_Uv;
+in vec4 a_VertColor;
+
+in vec4 a_Src;
+in vec4 a_TCol1;
+in vec4 a_TCol2;
+in vec4 a_TCol3;
+in vec4 a_TCol4;
+in vec4 a_Color;
+
+layout (std140) uniform Globals {
+    mat4 u_MVP;
+};
+
+out vec2 v_Uv;
+out vec4 v_Color;
+
+void main() {
+    v_Uv = a_Uv * a_Src.zw + a_Src.xy;
+    v_Color = a_Color * a_VertColor;
+    mat4 instance_transform = mat4(a_TCol1, a_TCol2, a_TCol3, a_TCol4);
+    vec4 position = instance_transform * vec4(a_Pos, 0.0, 1.0);
+
+    gl_Position = u_MVP * position;
+}
+";
+
+const PIXEL_HEADER: &str = "#version 150 core
+
+uniform sampler2D t_Texture;
+in vec2 v_Uv;
+in vec4 v_Color;
+out vec4 Target0;
+
+layout (std140) uniform Globals {
+    mat4 u_MVP;
+};
+
+layout (std140) uniform PostFxConsts {
+    mat4 u_Palette;
+    vec4 u_Color;
+    vec4 u_Params;
+    vec2 u_Resolution;
+    float u_Time;
+};
+";
+
+const VIGNETTE: &str = "
+void main() {
+    vec4 color = texture(t_Texture, v_Uv);
+    float dist = distance(v_Uv, vec2(0.5, 0.5)) * 1.4142;
+    float shade = smoothstep(u_Params.y, 1.0, dist) * u_Params.x;
+    Target0 = vec4(color.rgb * (1.0 - shade), color.a) * v_Color;
+}
+";
+
+const CHROMATIC_ABERRATION: &str = "
+void main() {
+    vec2 dir = v_Uv - vec2(0.5, 0.5);
+    vec2 offset = dir * u_Params.x / u_Resolution * 2.0;
+    float r = texture(t_Texture, v_Uv + offset).r;
+    vec4 center = texture(t_Texture, v_Uv);
+    float b = texture(t_Texture, v_Uv - offset).b;
+    Target0 = vec4(r, center.g, b, center.a) * v_Color;
+}
+";
+
+const FLASH: &str = "
+void main() {
+    vec4 color = texture(t_Texture, v_Uv);
+    Target0 = vec4(mix(color.rgb, u_Color.rgb, u_Color.a), color.a) * v_Color;
+}
+";
+
+const PALETTE_SWAP: &str = "
+void main() {
+    vec4 color = texture(t_Texture, v_Uv);
+    float lum = dot(color.rgb, vec3(0.299, 0.587, 0.114));
+    int index = int(clamp(lum * 4.0, 0.0, 3.0));
+    Target0 = vec4(u_Palette[index].rgb, color.a) * v_Color;
+}
+";
+
+const CRT: &str = "
+void main() {
+    vec2 uv = v_Uv * 2.0 - 1.0;
+    uv *= 1.0 + u_Params.y * dot(uv, uv) * 0.25;
+    uv = (uv + 1.0) * 0.5;
+    if (uv.x < 0.0 || uv.x > 1.0 || uv.y < 0.0 || uv.y > 1.0) {
+        Target0 = vec4(0.0, 0.0, 0.0, 1.0);
+        return;
+    }
+    vec4 color = texture(t_Texture, uv);
+    float scanline = sin(uv.y * u_Resolution.y * 3.14159) * 0.5 + 0.5;
+    color.rgb *= 1.0 - u_Params.x * scanline;
+    Target0 = color * v_Color;
+}
+";
+
+/// A single post-processing effect and its settings.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Effect {
+    /// Darkens the screen towards the corners.  `strength` is how
+    /// dark the corners get, 0 to 1; `radius` is how far out from
+    /// the center the darkening starts, 0 to 1.
+    Vignette { strength: f32, radius: f32 },
+    /// Offsets the red and blue channels outwards by up to `offset`
+    /// pixels at the edges of the screen.
+    ChromaticAberration { offset: f32 },
+    /// Blends the screen towards a color; controlled by
+    /// `PostFx::flash()`.
+    Flash,
+    /// Replaces each pixel with one of four colors, darkest to
+    /// lightest, based on its brightness.  Classic handheld look.
+    PaletteSwap { palette: [Color; 4] },
+    /// Scanlines of the given strength, 0 to 1, and barrel
+    /// distortion of the given amount.
+    Crt { scanlines: f32, curvature: f32 },
+    /// Your own pixel shader.  `source` is everything after the
+    /// uniform declarations, ie at least a `main()` function.
+    /// `params` and `color` are passed through to it.
+    Custom {
+        source: String,
+        params: [f32; 4],
+        color: Color,
+    },
+}
+
+impl Effect {
+    fn source(&self) -> &str {
+        match self {
+            Effect::Vignette { .. } => VIGNETTE,
+            Effect::ChromaticAberration { .. } => CHROMATIC_ABERRATION,
+            Effect::Flash => FLASH,
+            Effect::PaletteSwap { .. } => PALETTE_SWAP,
+            Effect::Crt { .. } => CRT,
+            Effect::Custom { source, .. } => source,
+        }
+    }
+
+    /// The shader constants for this effect, except for the
+    /// ones that are the same for every pass.
+    fn consts(&self, flash: Color) -> PostFxConsts {
+        let mut consts = PostFxConsts {
+            palette: [[0.0; 4]; 4],
+            color: [0.0; 4],
+            params: [0.0; 4],
+            resolution: [0.0; 2],
+            time: 0.0,
+        };
+        match self {
+            Effect::Vignette { strength, radius } => {
+                consts.params = [*strength, *radius, 0.0, 0.0];
+            }
+            Effect::ChromaticAberration { offset } => {
+                consts.params = [*offset, 0.0, 0.0, 0.0];
+            }
+            Effect::Flash => {
+                consts.color = flash.into();
+            }
+            Effect::PaletteSwap { palette } => {
+                for (column, color) in consts.palette.iter_mut().zip(palette.iter()) {
+                    *column = (*color).into();
+                }
+            }
+            Effect::Crt {
+                scanlines,
+                curvature,
+            } => {
+                consts.params = [*scanlines, *curvature, 0.0, 0.0];
+            }
+            Effect::Custom { params, color, .. } => {
+                consts.params = *params;
+                consts.color = (*color).into();
+            }
+        }
+        consts
+    }
+}
+
+/// Identifies a pass added to a `PostFx`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct PassId(usize);
+
+struct Pass {
+    effect: Effect,
+    enabled: bool,
+    shader: Shader<PostFxConsts>,
+}
+
+/// A screen flash that fades out linearly.
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct FlashState {
+    color: Color,
+    duration: f32,
+    remaining: f32,
+}
+
+impl FlashState {
+    fn update(&mut self, dt: f32) {
+        self.remaining = (self.remaining - dt).max(0.0);
+    }
+
+    /// The flash color, with alpha scaled by how far it has faded.
+    fn current(&self) -> Color {
+        let t = if self.duration > 0.0 {
+            self.remaining / self.duration
+        } else {
+            0.0
+        };
+        Color::new(self.color.r, self.color.g, self.color.b, self.color.a * t)
+    }
+}
+
+/// An offscreen render target and a chain of effects to apply
+/// to it.  See the module docs.
+pub struct PostFx {
+    targets: [Canvas; 2],
+    passes: Vec<Pass>,
+    flash: FlashState,
+    time: f32,
+}
+
+impl PostFx {
+    /// Creates a new `PostFx` with no effects, rendering at the
+    /// size of the window.
+    pub fn new(ctx: &mut Context) -> GameResult<Self> {
+        Ok(PostFx {
+            targets: Self::make_targets(ctx)?,
+            passes: Vec::new(),
+            flash: FlashState {
+                color: graphics::WHITE,
+                duration: 0.0,
+                remaining: 0.0,
+            },
+            time: 0.0,
+        })
+    }
+
+    fn make_targets(ctx: &mut Context) -> GameResult<[Canvas; 2]> {
+        let mut a = Canvas::with_window_size(ctx)?;
+        let mut b = Canvas::with_window_size(ctx)?;
+        // Each pass overwrites the whole target, no blending wanted.
+        a.set_blend_mode(Some(BlendMode::Replace));
+        b.set_blend_mode(Some(BlendMode::Replace));
+        Ok([a, b])
+    }
+
+    /// Recreates the offscreen targets; call this when the
+    /// window is resized.
+    pub fn resize(&mut self, ctx: &mut Context) -> GameResult<()> {
+        self.targets = Self::make_targets(ctx)?;
+        Ok(())
+    }
+
+    /// Adds an effect to the end of the chain, compiling its shader.
+    pub fn add(&mut self, ctx: &mut Context, effect: Effect) -> GameResult<PassId> {
+        let source = format!("{}{}", PIXEL_HEADER, effect.source());
+        let shader = Shader::from_u8(
+            ctx,
+            VERTEX_SHADER,
+            source.as_bytes(),
+            effect.consts(self.flash.current()),
+            "PostFxConsts",
+            Some(&[BlendMode::Replace]),
+        )?;
+        self.passes.push(Pass {
+            effect,
+            enabled: true,
+            shader,
+        });
+        Ok(PassId(self.passes.len() - 1))
+    }
+
+    /// Gets the settings of an effect so you can change them.
+    ///
+    /// Changing it to a different kind of effect won't work,
+    /// since the shader is already compiled.
+    pub fn effect_mut(&mut self, id: PassId) -> &mut Effect {
+        &mut self.passes[id.0].effect
+    }
+
+    /// Turns an effect on or off without removing it.
+    pub fn set_enabled(&mut self, id: PassId, enabled: bool) {
+        self.passes[id.0].enabled = enabled;
+    }
+
+    pub fn is_enabled(&self, id: PassId) -> bool {
+        self.passes[id.0].enabled
+    }
+
+    /// Starts a screen flash of the given color, fading out over
+    /// `duration` seconds.  Only does anything if you've added an
+    /// `Effect::Flash`.
+    pub fn flash(&mut self, color: Color, duration: f32) {
+        self.flash = FlashState {
+            color,
+            duration,
+            remaining: duration,
+        };
+    }
+
+    /// Advances time-based effects.
+    pub fn update(&mut self, dt: f32) {
+        self.time += dt;
+        self.flash.update(dt);
+    }
+
+    /// Redirects drawing to the offscreen target.  You still need
+    /// to clear it yourself.
+    pub fn begin(&self, ctx: &mut Context) {
+        graphics::set_canvas(ctx, Some(&self.targets[0]));
+    }
+
+    /// The offscreen canvas `begin()` draws to.
+    pub fn canvas(&self) -> &Canvas {
+        &self.targets[0]
+    }
+
+    /// Runs the effect chain over everything drawn since `begin()`
+    /// and draws the result to the screen.  Doesn't call
+    /// `graphics::present()`, so you can still draw a HUD on top.
+    pub fn end_and_draw(&mut self, ctx: &mut Context) -> GameResult<()> {
+        let screen = graphics::screen_coordinates(ctx);
+        let (w, h) = graphics::drawable_size(ctx);
+        let target_size = self.targets[0].image().dimensions();
+        let param = DrawParam::new()
+            .dest(Point2 {
+                x: screen.x,
+                y: screen.y,
+            })
+            .scale(Vector2 {
+                x: screen.w / target_size.w,
+                y: screen.h / target_size.h,
+            });
+
+        let enabled: Vec<usize> = (0..self.passes.len())
+            .filter(|&i| self.passes[i].enabled)
+            .collect();
+        let mut source = 0;
+        if enabled.is_empty() {
+            graphics::set_canvas(ctx, None);
+            return graphics::draw(ctx, &self.targets[source], param);
+        }
+        let flash = self.flash.current();
+        for (n, &i) in enabled.iter().enumerate() {
+            let pass = &self.passes[i];
+            let last = n == enabled.len() - 1;
+            if last {
+                graphics::set_canvas(ctx, None);
+            } else {
+                graphics::set_canvas(ctx, Some(&self.targets[1 - source]));
+            }
+            let mut consts = pass.effect.consts(flash);
+            consts.resolution = [w, h];
+            consts.time = self.time;
+            let _lock = graphics::use_shader(ctx, &pass.shader);
+            pass.shader.send(ctx, consts)?;
+            graphics::draw(ctx, &self.targets[source], param)?;
+            source = 1 - source;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flash_fades() {
+        let mut flash = FlashState {
+            color: Color::new(1.0, 0.0, 0.0, 1.0),
+            duration: 1.0,
+            remaining: 1.0,
+        };
+        assert_eq!(flash.current().a, 1.0);
+        flash.update(0.25);
+        assert!((flash.current().a - 0.75).abs() < 0.0001);
+        flash.update(5.0);
+        assert_eq!(flash.current().a, 0.0);
+        assert_eq!(flash.current().r, 1.0);
+    }
+
+    #[test]
+    fn test_effect_consts() {
+        let red = Color::new(1.0, 0.0, 0.0, 0.5);
+        let c = Effect::Vignette {
+            strength: 0.8,
+            radius: 0.4,
+        }
+        .consts(red);
+        assert_eq!(c.params, [0.8, 0.4, 0.0, 0.0]);
+        assert_eq!(c.color, [0.0; 4]);
+        let c = Effect::Flash.consts(red);
+        assert_eq!(c.color, [1.0, 0.0, 0.0, 0.5]);
+        let black = Color::new(0.0, 0.0, 0.0, 1.0);
+        let white = graphics::WHITE;
+        let c = Effect::PaletteSwap {
+            palette: [black, red, red, white],
+        }
+        .consts(red);
+        assert_eq!(c.palette[0], [0.0, 0.0, 0.0, 1.0]);
+        assert_eq!(c.palette[3], [1.0, 1.0, 1.0, 1.0]);
+    }
+}
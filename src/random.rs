@@ -0,0 +1,395 @@
+//! Random number helpers for the sort of things games actually
+//! want to do with random numbers: pick a value in a range, pick
+//! a point in a circle, pick an item out of a weighted loot table,
+//! and so on.
+//!
+//! Everything goes through a `Random` object wrapping a small,
+//! seedable PRNG, so if you seed it the same way you get the same
+//! results every time.  That's handy for replays, tests, and
+//! "daily challenge" style levels.  `Random` implements `rand::Rng`
+//! so you can also hand it to anything else that wants one, such
+//! as the particle system.
+//!
+//! There's also a `ShuffleBag` for "random but fair" choices
+//! (think Tetris pieces) and a `ValueNoise` generator for smooth
+//! 1D and 2D noise, which is good for screen shake, wobbly
+//! terrain and so on.
+
+use std::f32;
+
+use ggez::graphics;
+use ggez::mint::{Point2, Vector2};
+use rand::{self, Rng, SeedableRng, XorShiftRng};
+
+/// A seedable random number generator with some game-oriented
+/// helper methods.
+#[derive(Clone, Debug)]
+pub struct Random {
+    rng: XorShiftRng,
+}
+
+impl Random {
+    /// Creates a new `Random` seeded from the OS's random
+    /// number source, so it will be different every run.
+    pub fn new() -> Self {
+        let seed = rand::thread_rng().gen::<u64>();
+        Self::with_seed(seed)
+    }
+
+    /// Creates a new `Random` with the given seed.  Two `Random`'s
+    /// made with the same seed will produce the same sequence of
+    /// values.
+    pub fn with_seed(seed: u64) -> Self {
+        // XorShift must not be seeded with all zeroes, so we mix
+        // in some constants.
+        let lo = seed as u32;
+        let hi = (seed >> 32) as u32;
+        let rng =
+            XorShiftRng::from_seed([lo ^ 0x9E37_79B9, hi ^ 0x243F_6A88, 0x85A3_08D3, 0x1319_8A2E]);
+        Random { rng }
+    }
+
+    /// Returns a random `f32` in the range `[low, high)`.
+    ///
+    /// Unlike `Rng::gen_range()` this is fine with `low == high`,
+    /// and just returns `low` then.
+    pub fn range(&mut self, low: f32, high: f32) -> f32 {
+        if low >= high {
+            low
+        } else {
+            self.rng.gen_range(low, high)
+        }
+    }
+
+    /// Returns a random `i32` in the range `[low, high)`, or
+    /// `low` if the range is empty.
+    pub fn range_i32(&mut self, low: i32, high: i32) -> i32 {
+        if low >= high {
+            low
+        } else {
+            self.rng.gen_range(low, high)
+        }
+    }
+
+    /// Returns true with the given probability, which should be
+    /// between 0.0 and 1.0.
+    pub fn chance(&mut self, probability: f32) -> bool {
+        self.rng.gen::<f32>() < probability
+    }
+
+    /// Returns a random point in the rectangle with the given corners.
+    pub fn point2_range(&mut self, low: Point2<f32>, high: Point2<f32>) -> Point2<f32> {
+        let x = self.range(low.x, high.x);
+        let y = self.range(low.y, high.y);
+        Point2 { x, y }
+    }
+
+    /// Returns a random vector with each component between the
+    /// components of `low` and `high`.
+    pub fn vector2_range(&mut self, low: Vector2<f32>, high: Vector2<f32>) -> Vector2<f32> {
+        let x = self.range(low.x, high.x);
+        let y = self.range(low.y, high.y);
+        Vector2 { x, y }
+    }
+
+    /// Returns a random color with each channel between the
+    /// channels of `low` and `high`, including alpha.
+    pub fn color_range(&mut self, low: graphics::Color, high: graphics::Color) -> graphics::Color {
+        let r = self.range(low.r, high.r);
+        let g = self.range(low.g, high.g);
+        let b = self.range(low.b, high.b);
+        let a = self.range(low.a, high.a);
+        graphics::Color::new(r, g, b, a)
+    }
+
+    /// Returns a random angle in radians, in the range `[0, 2π)`.
+    pub fn angle(&mut self) -> f32 {
+        self.range(0.0, f32::consts::PI * 2.0)
+    }
+
+    /// Returns a random unit-length vector, ie a point on the
+    /// edge of the unit circle.
+    pub fn unit_vector(&mut self) -> Vector2<f32> {
+        let theta = self.angle();
+        Vector2 {
+            x: theta.cos(),
+            y: theta.sin(),
+        }
+    }
+
+    /// Returns a random point inside a circle of the given radius
+    /// centered on the origin.  Points are uniformly distributed
+    /// over the area of the circle, rather than bunched up in the
+    /// middle.
+    pub fn in_circle(&mut self, radius: f32) -> Vector2<f32> {
+        self.in_annulus(0.0, radius)
+    }
+
+    /// Returns a random point inside the ring between `inner_radius`
+    /// and `outer_radius`, centered on the origin, uniformly
+    /// distributed over its area.
+    pub fn in_annulus(&mut self, inner_radius: f32, outer_radius: f32) -> Vector2<f32> {
+        // Picking the radius uniformly would put too many points
+        // near the center, so we pick the radius squared instead.
+        let r2 = self.range(inner_radius * inner_radius, outer_radius * outer_radius);
+        let r = r2.sqrt();
+        let dir = self.unit_vector();
+        Vector2 {
+            x: dir.x * r,
+            y: dir.y * r,
+        }
+    }
+
+    /// Picks an item out of a list of `(item, weight)` pairs, with
+    /// each item's chance of being picked proportional to its
+    /// weight.  Returns `None` if the list is empty or all the
+    /// weights are zero.  Negative weights are treated as zero.
+    pub fn weighted_choice<'a, T>(&mut self, items: &'a [(T, f32)]) -> Option<&'a T> {
+        let total: f32 = items.iter().map(|&(_, w)| w.max(0.0)).sum();
+        if total <= 0.0 {
+            return None;
+        }
+        let mut pick = self.range(0.0, total);
+        for (item, weight) in items {
+            let weight = weight.max(0.0);
+            if pick < weight {
+                return Some(item);
+            }
+            pick -= weight;
+        }
+        // Floating point rounding can leave us just past the end,
+        // so we fall back to the last item that could be picked.
+        items
+            .iter()
+            .rev()
+            .find(|&&(_, w)| w > 0.0)
+            .map(|(item, _)| item)
+    }
+
+    /// Picks a random item out of a slice, or `None` if it's empty.
+    pub fn choose<'a, T>(&mut self, items: &'a [T]) -> Option<&'a T> {
+        self.rng.choose(items)
+    }
+
+    /// Shuffles the slice in place.
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        self.rng.shuffle(items)
+    }
+}
+
+impl Default for Random {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Rng for Random {
+    fn next_u32(&mut self) -> u32 {
+        self.rng.next_u32()
+    }
+}
+
+/// A "shuffle bag" of items, which hands them out in random order
+/// but never repeats one until all of them have been handed out.
+///
+/// This tends to feel fairer to players than truly random picks,
+/// which can give you the same thing five times in a row.
+#[derive(Clone, Debug)]
+pub struct ShuffleBag<T> {
+    items: Vec<T>,
+    order: Vec<usize>,
+    cursor: usize,
+}
+
+impl<T> ShuffleBag<T> {
+    /// Creates a new bag containing the given items.  To make an
+    /// item more common, just put it in more than once.
+    pub fn new(items: Vec<T>) -> Self {
+        let order = (0..items.len()).collect();
+        ShuffleBag {
+            items,
+            order,
+            // Start "empty" so the first call shuffles.
+            cursor: usize::MAX,
+        }
+    }
+
+    /// Number of items in the bag, whether or not they have
+    /// been handed out this round.
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Whether the bag has no items at all.
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Takes the next item out of the bag, refilling and reshuffling
+    /// it if it's run out.  Returns `None` only if the bag has no
+    /// items at all.
+    pub fn next<R: Rng>(&mut self, rng: &mut R) -> Option<&T> {
+        if self.items.is_empty() {
+            return None;
+        }
+        if self.cursor >= self.order.len() {
+            rng.shuffle(&mut self.order);
+            self.cursor = 0;
+        }
+        let idx = self.order[self.cursor];
+        self.cursor += 1;
+        Some(&self.items[idx])
+    }
+}
+
+const NOISE_TABLE_SIZE: usize = 256;
+
+/// Smooth value noise in one or two dimensions.
+///
+/// Random values are assigned to every integer coordinate and
+/// smoothly interpolated between, so nearby inputs give nearby
+/// outputs.  Output is always in the range `[0, 1]`.  Scale your
+/// inputs to control how "bumpy" it is; the noise repeats every
+/// 256 units.
+#[derive(Clone)]
+pub struct ValueNoise {
+    permutation: [u8; NOISE_TABLE_SIZE],
+    values: [f32; NOISE_TABLE_SIZE],
+}
+
+impl ValueNoise {
+    /// Creates a new noise generator using the given `Random`
+    /// to pick its lattice values.
+    pub fn new(rng: &mut Random) -> Self {
+        let mut permutation = [0u8; NOISE_TABLE_SIZE];
+        for (i, p) in permutation.iter_mut().enumerate() {
+            *p = i as u8;
+        }
+        rng.shuffle(&mut permutation);
+        let mut values = [0.0; NOISE_TABLE_SIZE];
+        for v in values.iter_mut() {
+            *v = rng.gen::<f32>();
+        }
+        ValueNoise {
+            permutation,
+            values,
+        }
+    }
+
+    /// Creates a new noise generator from a seed.
+    pub fn with_seed(seed: u64) -> Self {
+        Self::new(&mut Random::with_seed(seed))
+    }
+
+    fn lattice1(&self, x: i32) -> f32 {
+        let i = self.permutation[(x & 0xFF) as usize];
+        self.values[i as usize]
+    }
+
+    fn lattice2(&self, x: i32, y: i32) -> f32 {
+        let i = self.permutation[(x & 0xFF) as usize] as i32;
+        let j = self.permutation[((i + y) & 0xFF) as usize];
+        self.values[j as usize]
+    }
+
+    /// Samples 1D noise at the given point.
+    pub fn noise1(&self, x: f32) -> f32 {
+        let x0 = x.floor();
+        let t = smoothstep(x - x0);
+        let x0 = x0 as i32;
+        lerp(self.lattice1(x0), self.lattice1(x0 + 1), t)
+    }
+
+    /// Samples 2D noise at the given point.
+    pub fn noise2(&self, x: f32, y: f32) -> f32 {
+        let x0 = x.floor();
+        let y0 = y.floor();
+        let tx = smoothstep(x - x0);
+        let ty = smoothstep(y - y0);
+        let x0 = x0 as i32;
+        let y0 = y0 as i32;
+        let top = lerp(self.lattice2(x0, y0), self.lattice2(x0 + 1, y0), tx);
+        let bottom = lerp(self.lattice2(x0, y0 + 1), self.lattice2(x0 + 1, y0 + 1), tx);
+        lerp(top, bottom, ty)
+    }
+}
+
+fn smoothstep(t: f32) -> f32 {
+    t * t * (3.0 - 2.0 * t)
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seeded_determinism() {
+        let mut r1 = Random::with_seed(1234);
+        let mut r2 = Random::with_seed(1234);
+        for _ in 0..100 {
+            assert_eq!(r1.range(-10.0, 10.0), r2.range(-10.0, 10.0));
+        }
+        // Zero must be a usable seed too.
+        let mut r3 = Random::with_seed(0);
+        let _ = r3.range(0.0, 1.0);
+    }
+
+    #[test]
+    fn test_ranges() {
+        let mut r = Random::with_seed(42);
+        assert_eq!(r.range(3.0, 3.0), 3.0);
+        assert_eq!(r.range_i32(5, 5), 5);
+        for _ in 0..100 {
+            let v = r.in_annulus(2.0, 3.0);
+            let len = (v.x * v.x + v.y * v.y).sqrt();
+            assert!((1.999..=3.001).contains(&len));
+            let p = r.point2_range(Point2 { x: 0.0, y: 5.0 }, Point2 { x: 1.0, y: 6.0 });
+            assert!(p.x >= 0.0 && p.x < 1.0);
+            assert!(p.y >= 5.0 && p.y < 6.0);
+        }
+    }
+
+    #[test]
+    fn test_weighted_choice() {
+        let mut r = Random::with_seed(7);
+        let empty: [(u32, f32); 0] = [];
+        assert_eq!(r.weighted_choice(&empty), None);
+        assert_eq!(r.weighted_choice(&[(1, 0.0), (2, -1.0)]), None);
+        for _ in 0..100 {
+            assert_eq!(r.weighted_choice(&[(1, 0.0), (2, 5.0), (3, 0.0)]), Some(&2));
+        }
+    }
+
+    #[test]
+    fn test_shuffle_bag() {
+        let mut r = Random::with_seed(99);
+        let mut bag = ShuffleBag::new(vec![1, 2, 3, 4, 5]);
+        for _ in 0..3 {
+            let mut round: Vec<i32> = (0..5).map(|_| *bag.next(&mut r).unwrap()).collect();
+            round.sort();
+            assert_eq!(round, vec![1, 2, 3, 4, 5]);
+        }
+        let mut empty: ShuffleBag<i32> = ShuffleBag::new(vec![]);
+        assert_eq!(empty.next(&mut r), None);
+    }
+
+    #[test]
+    fn test_value_noise() {
+        let noise = ValueNoise::with_seed(5);
+        for i in 0..1000 {
+            let x = i as f32 * 0.37 - 100.0;
+            let n1 = noise.noise1(x);
+            let n2 = noise.noise2(x, x * 0.5);
+            assert!((0.0..=1.0).contains(&n1));
+            assert!((0.0..=1.0).contains(&n2));
+        }
+        // Continuous across lattice points
+        let a = noise.noise1(2.0 - 0.0001);
+        let b = noise.noise1(2.0 + 0.0001);
+        assert!((a - b).abs() < 0.001);
+    }
+}
@@ -13,16 +13,179 @@
 //! This is basically identical in concept to the Amethyst engine's scene
 //! system, the only difference is the details of how the pieces are put
 //! together.
+//!
+//! To run a stack as your game, put it in a `SceneRunner`, which
+//! passes it everything ggez tells it as `SceneEvents`:
+//!
+//! ```rust,ignore
+//! let mut stack = SceneStack::new(ctx, world);
+//! stack.push(Box::new(MainMenu::new(ctx)));
+//! event::run(ctx, event_loop, &mut SceneRunner::new(stack))
+//! ```
+//!
+//! Scenes can talk to each other through the stack's `Mailbox`: hand
+//! each scene a copy of `SceneStack::mailbox()` when you create it,
+//! and messages it sends are passed to the scenes they're addressed
+//! to via `Scene::receive()`.
+//!
+//! Scenes that implement `SaveableScene` can be saved along with
+//! where they are on the stack, and put back with a
+//! `SceneRegistry`, for saving the game anywhere.
+//!
+//! Scenes can also be registered with the stack under a name, and
+//! switched to with `SceneSwitch::push_named("boss_fight")`, so data
+//! files and scripts can refer to them.
+//!
+//! Cutscenes and tutorials can be written as a linear script of
+//! waits and actions with `ScriptedScene`, rather than as a state
+//! machine:
+//!
+//! ```rust,ignore
+//! SceneSwitch::push(
+//!     ScriptedScene::new("intro")
+//!         .then(|world| world.show_caption("Long ago..."))
+//!         .wait(2.0)
+//!         .wait_for_input(|e| matches!(e, SceneEvents::Key { .. }))
+//!         .switch(|_| SceneSwitch::replace(Level::new())),
+//! )
+//! ```
+//!
+//! For things that should happen later, give a scene its own
+//! `Scheduler`.  Since it belongs to the scene, it stops while the
+//! scene isn't being updated and goes away when the scene does:
+//!
+//! ```rust,ignore
+//! fn update(&mut self, world: &mut World, ctx: &mut Context) -> SceneSwitch<World, Ev> {
+//!     for event in self.timers.update(timer::delta(ctx).as_secs_f32()) {
+//!         match event {
+//!             Timed::SpawnWave => self.spawn_wave(world),
+//!             Timed::Blink => self.cursor_visible = !self.cursor_visible,
+//!         }
+//!     }
+//!     // ...
+//! }
+//! ```
+//!
+//! Scenes that take a while to set up can be loaded in the background
+//! with a `LoadingScene`, which shows some other scene meanwhile:
+//!
+//! ```rust,ignore
+//! SceneSwitch::push(LoadingScene::spawn(
+//!     LoadingScreen::new(),
+//!     move || LevelData::parse(&path),
+//!     |data, world, ctx| SceneSwitch::replace(Level::new(data, world, ctx)),
+//! ))
+//! ```
+//!
+//! When scenes are updating or drawing when they shouldn't be, turn on
+//! `SceneStack::set_debug_overlay()` to see the whole stack on screen.
+
+use std::any::Any;
+use std::cell::RefCell;
+use std::collections::{HashMap, VecDeque};
+use std::rc::Rc;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+use std::time::{Duration, Instant};
 
 use ggez;
+use ggez::event::{self, Axis, Button, EventHandler, GamepadId, KeyCode, KeyMods, MouseButton};
+use ggez::graphics;
+use ggez::timer;
+use ggez::{GameError, GameResult};
+
+#[cfg(feature = "serialize")]
+use serde::{Deserialize, Serialize};
 
 /// A command to change to a new scene, either by pushign a new one,
 /// popping one or replacing the current scene (pop and then push).
 pub enum SceneSwitch<C, Ev> {
     None,
-    Push(Box<Scene<C, Ev>>),
-    Replace(Box<Scene<C, Ev>>),
+    Push(Box<dyn Scene<C, Ev>>),
+    Replace(Box<dyn Scene<C, Ev>>),
+    /// Pushes a new scene made by whatever the stack has registered
+    /// under this name with `SceneStack::register_named()`.  Does
+    /// nothing if nothing is.
+    PushNamed(String),
+    /// Like `PushNamed`, but replaces the current scene.
+    ReplaceNamed(String),
+    /// Clears the whole stack, then pushes the new scene; for things
+    /// like going back to the main menu from deep in the game.
+    ReplaceAll(Box<dyn Scene<C, Ev>>),
     Pop,
+    /// Pops the current scene and hands the scene underneath a value,
+    /// such as what was picked in a menu, through its
+    /// `on_child_result()`.
+    PopWith(Box<dyn Any>),
+    /// Pops this many scenes at once.
+    PopN(usize),
+    /// Pops every scene, leaving the stack empty.
+    PopAll,
+    /// Pops scenes until the top one has this name.  Does nothing
+    /// if there isn't one.
+    PopUntil(String),
+    /// Starts a `SceneStack::preload()`, leaving the stack as it is
+    /// for now.
+    Preload(Duration, PreloadFn<C, Ev>),
+}
+
+/// What a scene says about being removed by a scene switch; see
+/// `Scene::on_before_switch()`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SwitchDecision {
+    /// Go ahead.
+    Allow,
+    /// Don't; the switch is dropped.
+    Veto,
+    /// Not yet; ask again next update.
+    Defer,
+}
+
+/// The input events ggez gives you, with everything it says about
+/// them, ready to be used as a scene stack's event type.
+///
+/// For keys and buttons, the `started` passed along with the event
+/// to `Scene::input()` says whether it was pressed or released.  For
+/// everything else it's always true, except `Focus`, where it says
+/// whether focus was gained or lost.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum SceneEvents {
+    Key {
+        keycode: KeyCode,
+        keymods: KeyMods,
+        /// Whether it's the keyboard repeating a held key.
+        repeat: bool,
+    },
+    Text(char),
+    MouseButton {
+        button: MouseButton,
+        x: f32,
+        y: f32,
+    },
+    MouseMotion {
+        x: f32,
+        y: f32,
+        dx: f32,
+        dy: f32,
+    },
+    MouseWheel {
+        x: f32,
+        y: f32,
+    },
+    GamepadButton {
+        button: Button,
+        id: GamepadId,
+    },
+    GamepadAxis {
+        axis: Axis,
+        value: f32,
+        id: GamepadId,
+    },
+    Resize {
+        width: f32,
+        height: f32,
+    },
+    Focus,
 }
 
 /// A trait for you to implement on a scene.
@@ -40,6 +203,167 @@ pub trait Scene<C, Ev> {
     fn draw_previous(&self) -> bool {
         false
     }
+    /// Like `draw_previous()`, but for updating: whether the scene
+    /// below this one keeps running too, say under a notification,
+    /// or is paused, say under a pause menu.
+    ///
+    /// Only the current scene's `SceneSwitch` is acted on; whatever
+    /// the scenes below return is ignored.
+    fn update_previous(&self) -> bool {
+        false
+    }
+    /// Whether input events stop at this scene, or carry on to the
+    /// scene below it too (and so on, until one that blocks).  Things
+    /// like a pause menu should block; things like a HUD or subtitles
+    /// that sit over the game but don't take input shouldn't.
+    fn blocks_input(&self) -> bool {
+        true
+    }
+    /// Called before a switch that would pop or replace this scene,
+    /// to let it object, say if there are unsaved changes.  If it
+    /// defers, the stack asks again at the start of each update until
+    /// it gets an answer, or some other switch comes along instead,
+    /// such as pushing an "are you sure?" scene.
+    fn on_before_switch(&mut self, _switch: &SceneSwitch<C, Ev>) -> SwitchDecision {
+        SwitchDecision::Allow
+    }
+    /// Called when the scene above this one pops itself with
+    /// `SceneSwitch::PopWith`, with the value it returned.  Use
+    /// `downcast()` to get it back as the type it was sent as.
+    fn on_child_result(&mut self, _gameworld: &mut C, _result: Box<dyn Any>) {}
+    /// Called with each message sent to this scene's name, or to
+    /// everyone, through the stack's `Mailbox`.  Use
+    /// `downcast_ref()` to see what it is.
+    fn receive(&mut self, _gameworld: &mut C, _message: &dyn Any) {}
+    /// Returns `Some(self)` if the scene implements `SaveableScene`.
+    /// Scenes that don't are left out when the stack is saved.
+    fn as_saveable(&self) -> Option<&dyn SaveableScene<C, Ev>> {
+        None
+    }
+}
+
+/// A scene that can be saved with `SceneStack::save()` and recreated
+/// by a `SceneRegistry`.  Remember to override `Scene::as_saveable()`
+/// too.
+pub trait SaveableScene<C, Ev>: Scene<C, Ev> {
+    /// Which of the registry's factories recreates this scene.
+    fn kind(&self) -> &str;
+    /// The scene's state, in whatever format its factory reads.
+    fn save_state(&self) -> String;
+}
+
+/// One saved scene.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct SavedScene {
+    pub kind: String,
+    pub state: String,
+}
+
+/// A saved scene stack, from the bottom up.  With the `serialize`
+/// feature enabled this can be serialized.  The stack's world isn't
+/// included; save that however you like.
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct SavedStack {
+    pub scenes: Vec<SavedScene>,
+}
+
+type SceneFactory<C, Ev> =
+    Box<dyn Fn(&str, &mut C, &mut ggez::Context) -> GameResult<Box<dyn Scene<C, Ev>>>>;
+
+/// Knows how to recreate each kind of `SaveableScene` from its saved
+/// state.
+pub struct SceneRegistry<C, Ev> {
+    factories: HashMap<String, SceneFactory<C, Ev>>,
+}
+
+impl<C, Ev> SceneRegistry<C, Ev> {
+    pub fn new() -> Self {
+        SceneRegistry {
+            factories: HashMap::new(),
+        }
+    }
+
+    /// Registers the function that recreates scenes of the given
+    /// kind from their saved state.
+    pub fn register<F>(&mut self, kind: &str, factory: F)
+    where
+        F: Fn(&str, &mut C, &mut ggez::Context) -> GameResult<Box<dyn Scene<C, Ev>>> + 'static,
+    {
+        self.factories.insert(kind.to_owned(), Box::new(factory));
+    }
+
+    /// Recreates a saved stack, replacing whatever scenes `stack`
+    /// had.  If any scene can't be recreated, `stack` is left alone.
+    pub fn restore(
+        &self,
+        saved: &SavedStack,
+        stack: &mut SceneStack<C, Ev>,
+        ctx: &mut ggez::Context,
+    ) -> GameResult {
+        let mut scenes = Vec::with_capacity(saved.scenes.len());
+        for scene in &saved.scenes {
+            let factory = self.factories.get(&scene.kind).ok_or_else(|| {
+                GameError::ResourceLoadError(format!("No scene registered as {:?}", scene.kind))
+            })?;
+            scenes.push(factory(&scene.state, &mut stack.world, ctx)?);
+        }
+        stack.scenes = scenes;
+        Ok(())
+    }
+}
+
+impl<C, Ev> Default for SceneRegistry<C, Ev> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Who each message is for, `None` meaning everyone.
+type MessageQueue = Vec<(Option<String>, Box<dyn Any>)>;
+
+/// Somewhere for scenes to leave messages for each other.  Copies
+/// all share the same queue; the `SceneStack` delivers everything in
+/// it at the start of its next `update()`, before any scene updates,
+/// whether or not the scenes it's for are running.  Messages for
+/// scenes that aren't on the stack are dropped.
+#[derive(Clone, Default)]
+pub struct Mailbox {
+    queue: Rc<RefCell<MessageQueue>>,
+}
+
+impl Mailbox {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sends a message to every scene with the given name.
+    pub fn send<T>(&self, to: &str, message: T)
+    where
+        T: Any,
+    {
+        self.queue
+            .borrow_mut()
+            .push((Some(to.to_owned()), Box::new(message)));
+    }
+
+    /// Sends a message to every scene on the stack.
+    pub fn broadcast<T>(&self, message: T)
+    where
+        T: Any,
+    {
+        self.queue.borrow_mut().push((None, Box::new(message)));
+    }
+
+    /// Returns whether there are messages waiting to be delivered.
+    pub fn is_empty(&self) -> bool {
+        self.queue.borrow().is_empty()
+    }
+
+    fn take(&self) -> MessageQueue {
+        std::mem::take(&mut *self.queue.borrow_mut())
+    }
 }
 
 impl<C, Ev> SceneSwitch<C, Ev> {
@@ -61,37 +385,576 @@ impl<C, Ev> SceneSwitch<C, Ev> {
     {
         SceneSwitch::Push(Box::new(scene))
     }
+
+    /// Same as `replace()` but returns SceneSwitch::PopWith
+    pub fn pop_with<T>(result: T) -> Self
+    where
+        T: Any,
+    {
+        SceneSwitch::PopWith(Box::new(result))
+    }
+
+    /// Shortcut for boxing the step function of a
+    /// `SceneSwitch::Preload`.
+    pub fn preload<F>(budget: Duration, step: F) -> Self
+    where
+        F: FnMut(&mut C, &mut ggez::Context) -> LoadStep<C, Ev> + 'static,
+    {
+        SceneSwitch::Preload(budget, Box::new(step))
+    }
+
+    /// Same as `replace()` but returns SceneSwitch::ReplaceAll
+    pub fn replace_all<S>(scene: S) -> Self
+    where
+        S: Scene<C, Ev> + 'static,
+    {
+        SceneSwitch::ReplaceAll(Box::new(scene))
+    }
+
+    /// Shortcut for making a `SceneSwitch::PushNamed`.
+    pub fn push_named(name: &str) -> Self {
+        SceneSwitch::PushNamed(name.to_owned())
+    }
+
+    /// Shortcut for making a `SceneSwitch::ReplaceNamed`.
+    pub fn replace_named(name: &str) -> Self {
+        SceneSwitch::ReplaceNamed(name.to_owned())
+    }
+}
+
+type FinishFn<C, Ev, T> = Box<dyn FnOnce(T, &mut C, &mut ggez::Context) -> SceneSwitch<C, Ev>>;
+
+/// A scene that does the slow part of making another scene on a
+/// background thread, showing a scene of your choice until it's done.
+/// See the module docs.
+pub struct LoadingScene<C, Ev, T> {
+    scene: Box<dyn Scene<C, Ev>>,
+    receiver: Receiver<T>,
+    loaded: Option<T>,
+    finish: Option<FinishFn<C, Ev, T>>,
+}
+
+impl<C, Ev, T> LoadingScene<C, Ev, T>
+where
+    T: Send + 'static,
+{
+    /// Runs `load` on a new thread, showing `scene` meanwhile.  Once
+    /// it's done, `finish` gets its result on the main thread, where
+    /// it can create graphics resources, and says what to switch to;
+    /// usually a `SceneSwitch::Replace` with the loaded scene.
+    pub fn spawn<S, L, F>(scene: S, load: L, finish: F) -> Self
+    where
+        S: Scene<C, Ev> + 'static,
+        L: FnOnce() -> T + Send + 'static,
+        F: FnOnce(T, &mut C, &mut ggez::Context) -> SceneSwitch<C, Ev> + 'static,
+    {
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            // If nobody's waiting any more, nevermind.
+            let _ = sender.send(load());
+        });
+        LoadingScene {
+            scene: Box::new(scene),
+            receiver,
+            loaded: None,
+            finish: Some(Box::new(finish)),
+        }
+    }
+}
+
+impl<C, Ev, T> LoadingScene<C, Ev, T> {
+    /// Returns whether the background work is done.  Panics if the
+    /// loading thread did.
+    pub fn is_loaded(&mut self) -> bool {
+        if self.loaded.is_none() {
+            match self.receiver.try_recv() {
+                Ok(t) => self.loaded = Some(t),
+                Err(TryRecvError::Empty) => (),
+                Err(TryRecvError::Disconnected) => {
+                    panic!("ERROR: Scene loading thread panicked.")
+                }
+            }
+        }
+        self.loaded.is_some()
+    }
+}
+
+impl<C, Ev, T> Scene<C, Ev> for LoadingScene<C, Ev, T> {
+    fn update(&mut self, gameworld: &mut C, ctx: &mut ggez::Context) -> SceneSwitch<C, Ev> {
+        if self.is_loaded() {
+            if let (Some(t), Some(finish)) = (self.loaded.take(), self.finish.take()) {
+                return finish(t, gameworld, ctx);
+            }
+        }
+        self.scene.update(gameworld, ctx)
+    }
+
+    fn draw(&mut self, gameworld: &mut C, ctx: &mut ggez::Context) -> ggez::GameResult<()> {
+        self.scene.draw(gameworld, ctx)
+    }
+
+    fn input(&mut self, gameworld: &mut C, event: Ev, started: bool) {
+        self.scene.input(gameworld, event, started)
+    }
+
+    fn name(&self) -> &str {
+        self.scene.name()
+    }
+
+    fn draw_previous(&self) -> bool {
+        self.scene.draw_previous()
+    }
+
+    fn update_previous(&self) -> bool {
+        self.scene.update_previous()
+    }
+
+    fn blocks_input(&self) -> bool {
+        self.scene.blocks_input()
+    }
+    fn on_before_switch(&mut self, switch: &SceneSwitch<C, Ev>) -> SwitchDecision {
+        self.scene.on_before_switch(switch)
+    }
+}
+
+type ScriptAction<C> = Box<dyn FnMut(&mut C)>;
+type ScriptSwitch<C, Ev> = Box<dyn FnOnce(&mut C) -> SceneSwitch<C, Ev>>;
+type InputFilter<Ev> = Box<dyn Fn(&Ev) -> bool>;
+type ScriptDraw<C> = Box<dyn FnMut(&mut C, &mut ggez::Context) -> GameResult>;
+
+/// One step of a `ScriptedScene`.
+pub enum ScriptCommand<C, Ev> {
+    /// Waits this many seconds.
+    Wait(f32),
+    /// Waits for an input event, the start of one that the function
+    /// returns true for.
+    WaitForInput(InputFilter<Ev>),
+    /// Does something to the world.
+    Run(ScriptAction<C>),
+    /// Changes scene.  The script carries on after this if the
+    /// scene comes back to the top of the stack.
+    Switch(ScriptSwitch<C, Ev>),
+}
+
+/// A scene that runs through a list of `ScriptCommand`s, one after
+/// another, and pops itself when it runs out.  See the module docs.
+pub struct ScriptedScene<C, Ev> {
+    name: String,
+    commands: VecDeque<ScriptCommand<C, Ev>>,
+    input_seen: bool,
+    draw: Option<ScriptDraw<C>>,
+    draw_previous: bool,
+    update_previous: bool,
+}
+
+impl<C, Ev> ScriptedScene<C, Ev> {
+    pub fn new(name: &str) -> Self {
+        ScriptedScene {
+            name: name.to_owned(),
+            commands: VecDeque::new(),
+            input_seen: false,
+            draw: None,
+            draw_previous: false,
+            update_previous: false,
+        }
+    }
+
+    /// Adds a command to the end of the script.
+    pub fn command(mut self, command: ScriptCommand<C, Ev>) -> Self {
+        self.commands.push_back(command);
+        self
+    }
+
+    pub fn wait(self, seconds: f32) -> Self {
+        self.command(ScriptCommand::Wait(seconds))
+    }
+
+    pub fn wait_for_input<F>(self, filter: F) -> Self
+    where
+        F: Fn(&Ev) -> bool + 'static,
+    {
+        self.command(ScriptCommand::WaitForInput(Box::new(filter)))
+    }
+
+    pub fn then<F>(self, action: F) -> Self
+    where
+        F: FnMut(&mut C) + 'static,
+    {
+        self.command(ScriptCommand::Run(Box::new(action)))
+    }
+
+    pub fn switch<F>(self, switch: F) -> Self
+    where
+        F: FnOnce(&mut C) -> SceneSwitch<C, Ev> + 'static,
+    {
+        self.command(ScriptCommand::Switch(Box::new(switch)))
+    }
+
+    /// Sets how the scene draws itself; by default it doesn't.
+    pub fn with_draw<F>(mut self, draw: F) -> Self
+    where
+        F: FnMut(&mut C, &mut ggez::Context) -> GameResult + 'static,
+    {
+        self.draw = Some(Box::new(draw));
+        self
+    }
+
+    /// Sets whether the scenes underneath keep being drawn and
+    /// updated while the script runs, for scripts that play over the
+    /// game like tutorials.  Both are off by default.
+    pub fn over_previous(mut self, draw: bool, update: bool) -> Self {
+        self.draw_previous = draw;
+        self.update_previous = update;
+        self
+    }
+
+    /// Returns whether the script has run out of commands.
+    pub fn is_finished(&self) -> bool {
+        self.commands.is_empty()
+    }
+
+    /// Runs commands until one has to wait, or there's a switch to
+    /// make.
+    fn advance(&mut self, gameworld: &mut C, dt: f32) -> SceneSwitch<C, Ev> {
+        let mut dt = dt;
+        while let Some(command) = self.commands.front_mut() {
+            match command {
+                ScriptCommand::Wait(left) => {
+                    if *left > dt {
+                        *left -= dt;
+                        return SceneSwitch::None;
+                    }
+                    dt -= *left;
+                }
+                ScriptCommand::WaitForInput(_) => {
+                    if !self.input_seen {
+                        return SceneSwitch::None;
+                    }
+                    self.input_seen = false;
+                }
+                ScriptCommand::Run(action) => action(gameworld),
+                ScriptCommand::Switch(_) => {
+                    if let Some(ScriptCommand::Switch(switch)) = self.commands.pop_front() {
+                        return switch(gameworld);
+                    }
+                }
+            }
+            self.commands.pop_front();
+        }
+        SceneSwitch::Pop
+    }
+}
+
+impl<C, Ev> Scene<C, Ev> for ScriptedScene<C, Ev> {
+    fn update(&mut self, gameworld: &mut C, ctx: &mut ggez::Context) -> SceneSwitch<C, Ev> {
+        let dt = timer::duration_to_f64(timer::delta(ctx)) as f32;
+        self.advance(gameworld, dt)
+    }
+
+    fn draw(&mut self, gameworld: &mut C, ctx: &mut ggez::Context) -> GameResult {
+        match self.draw.as_mut() {
+            Some(draw) => draw(gameworld, ctx),
+            None => Ok(()),
+        }
+    }
+
+    fn input(&mut self, _gameworld: &mut C, event: Ev, started: bool) {
+        if let Some(ScriptCommand::WaitForInput(filter)) = self.commands.front() {
+            if started && filter(&event) {
+                self.input_seen = true;
+            }
+        }
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn draw_previous(&self) -> bool {
+        self.draw_previous
+    }
+
+    fn update_previous(&self) -> bool {
+        self.update_previous
+    }
+}
+
+/// Identifies something scheduled on a `Scheduler`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct TimerId(u64);
+
+struct Timer<T> {
+    id: TimerId,
+    due: f64,
+    interval: Option<f64>,
+    event: T,
+}
+
+/// Delayed and repeating events, for a scene to keep as one of its
+/// fields.  See the module docs.
+pub struct Scheduler<T> {
+    timers: Vec<Timer<T>>,
+    now: f64,
+    next_id: u64,
+}
+
+impl<T> Scheduler<T> {
+    pub fn new() -> Self {
+        Scheduler {
+            timers: Vec::new(),
+            now: 0.0,
+            next_id: 0,
+        }
+    }
+
+    fn add(&mut self, delay: f32, interval: Option<f32>, event: T) -> TimerId {
+        let id = TimerId(self.next_id);
+        self.next_id += 1;
+        self.timers.push(Timer {
+            id,
+            due: self.now + f64::from(delay),
+            interval: interval.map(f64::from),
+            event,
+        });
+        id
+    }
+
+    /// Sends `event` once, `delay` seconds from now.
+    pub fn after(&mut self, delay: f32, event: T) -> TimerId {
+        self.add(delay, None, event)
+    }
+
+    /// Cancels something scheduled.  Returns whether it was still
+    /// waiting to happen.
+    pub fn cancel(&mut self, id: TimerId) -> bool {
+        let before = self.timers.len();
+        self.timers.retain(|t| t.id != id);
+        self.timers.len() != before
+    }
+
+    pub fn is_scheduled(&self, id: TimerId) -> bool {
+        self.timers.iter().any(|t| t.id == id)
+    }
+
+    /// Cancels everything.
+    pub fn clear(&mut self) {
+        self.timers.clear();
+    }
+
+    /// Moves time on, returning the events that are due, in the
+    /// order they fell due.  A repeating event can come up several
+    /// times if `dt` is long.
+    pub fn update(&mut self, dt: f32) -> Vec<T>
+    where
+        T: Clone,
+    {
+        self.now += f64::from(dt);
+        let mut fired = Vec::new();
+        loop {
+            let now = self.now;
+            let next = self
+                .timers
+                .iter()
+                .enumerate()
+                .filter(|(_, t)| t.due <= now)
+                .min_by(|(_, a), (_, b)| a.due.partial_cmp(&b.due).unwrap())
+                .map(|(i, _)| i);
+            let i = match next {
+                Some(i) => i,
+                None => break,
+            };
+            match self.timers[i].interval {
+                Some(interval) => {
+                    let timer = &mut self.timers[i];
+                    timer.due += interval;
+                    fired.push(timer.event.clone());
+                }
+                None => fired.push(self.timers.remove(i).event),
+            }
+        }
+        fired
+    }
+}
+
+impl<T> Scheduler<T>
+where
+    T: Clone,
+{
+    /// Sends `event` every `interval` seconds, starting `interval`
+    /// seconds from now.  An `interval` of 0 or less is treated as
+    /// a very short one.
+    pub fn every(&mut self, interval: f32, event: T) -> TimerId {
+        let interval = interval.max(f32::EPSILON);
+        self.add(interval, Some(interval), event)
+    }
+}
+
+impl<T> Default for Scheduler<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// How far along a `SceneStack::preload()` is.
+pub enum LoadStep<C, Ev> {
+    /// There's more to do.
+    Working,
+    /// It's done; make this switch.
+    Done(SceneSwitch<C, Ev>),
+}
+
+type PreloadFn<C, Ev> = Box<dyn FnMut(&mut C, &mut ggez::Context) -> LoadStep<C, Ev>>;
+
+type SceneConstructor<C, Ev> = Box<dyn Fn(&mut C) -> Box<dyn Scene<C, Ev>>>;
+
+struct Preload<C, Ev> {
+    budget: Duration,
+    step: PreloadFn<C, Ev>,
+}
+
+// How long a scene took last frame, if it was updated or drawn at all.
+#[derive(Debug, Copy, Clone, Default)]
+struct SceneTiming {
+    update: Option<Duration>,
+    draw: Option<Duration>,
 }
 
 /// A stack of `Scene`'s, together with a context object.
 pub struct SceneStack<C, Ev> {
     pub world: C,
-    scenes: Vec<Box<Scene<C, Ev>>>,
+    scenes: Vec<Box<dyn Scene<C, Ev>>>,
+    mailbox: Mailbox,
+    preload: Option<Preload<C, Ev>>,
+    // A switch a scene has deferred.
+    pending_switch: Option<SceneSwitch<C, Ev>>,
+    debug_overlay: bool,
+    // One per scene, bottom first.
+    timings: Vec<SceneTiming>,
+    constructors: HashMap<String, SceneConstructor<C, Ev>>,
 }
 
 impl<C, Ev> SceneStack<C, Ev> {
     pub fn new(_ctx: &mut ggez::Context, global_state: C) -> Self {
+        Self::with_world(global_state)
+    }
+
+    fn with_world(world: C) -> Self {
         Self {
-            world: global_state,
+            world,
             scenes: Vec::new(),
+            mailbox: Mailbox::new(),
+            preload: None,
+            pending_switch: None,
+            debug_overlay: false,
+            timings: Vec::new(),
+            constructors: HashMap::new(),
+        }
+    }
+
+    /// Registers a function that makes a scene, so it can be switched
+    /// to by name with `SceneSwitch::PushNamed` or `ReplaceNamed`.
+    /// That way scenes, level files and scripts can refer to scenes
+    /// without knowing their types.  Anything the scene needs to load
+    /// should come from the world, or be loaded once it's running.
+    ///
+    /// Replaces anything already registered under that name.
+    pub fn register_named<F>(&mut self, name: &str, constructor: F)
+    where
+        F: Fn(&mut C) -> Box<dyn Scene<C, Ev>> + 'static,
+    {
+        self.constructors
+            .insert(name.to_owned(), Box::new(constructor));
+    }
+
+    /// Returns whether anything's registered under the given name,
+    /// for checking names that come from data files before switching
+    /// to them.
+    pub fn is_registered(&self, name: &str) -> bool {
+        self.constructors.contains_key(name)
+    }
+
+    fn construct(&mut self, name: &str) -> Option<Box<dyn Scene<C, Ev>>> {
+        let constructor = self.constructors.get(name)?;
+        Some(constructor(&mut self.world))
+    }
+
+    /// Builds the next scene a bit at a time while the current one
+    /// keeps running.  Each `update()`, after the scenes have been
+    /// updated, `step` gets called over and over until it's used up
+    /// `budget` (but at least once).  When it returns
+    /// `LoadStep::Done`, its switch is made straight away.
+    ///
+    /// Replaces any preload already going.  Scenes can start one by
+    /// returning `SceneSwitch::Preload`.
+    pub fn preload<F>(&mut self, budget: Duration, step: F)
+    where
+        F: FnMut(&mut C, &mut ggez::Context) -> LoadStep<C, Ev> + 'static,
+    {
+        self.preload = Some(Preload {
+            budget,
+            step: Box::new(step),
+        });
+    }
+
+    pub fn is_preloading(&self) -> bool {
+        self.preload.is_some()
+    }
+
+    pub fn cancel_preload(&mut self) {
+        self.preload = None;
+    }
+
+    fn update_preload(&mut self, ctx: &mut ggez::Context) {
+        let mut preload = match self.preload.take() {
+            Some(preload) => preload,
+            None => return,
+        };
+        let start = Instant::now();
+        loop {
+            if let LoadStep::Done(switch) = (preload.step)(&mut self.world, ctx) {
+                self.switch(switch);
+                return;
+            }
+            if start.elapsed() >= preload.budget {
+                break;
+            }
+        }
+        self.preload = Some(preload);
+    }
+
+    /// Returns a handle to the stack's mailbox, for scenes to send
+    /// each other messages with.
+    pub fn mailbox(&self) -> Mailbox {
+        self.mailbox.clone()
+    }
+
+    /// Hands out everything waiting in the mailbox.  Messages sent
+    /// while doing so wait for next time.
+    fn deliver_messages(&mut self) {
+        for (to, message) in self.mailbox.take() {
+            for scene in self.scenes.iter_mut() {
+                if to.as_ref().map_or(true, |to| to == scene.name()) {
+                    scene.receive(&mut self.world, &*message);
+                }
+            }
         }
     }
 
     /// Add a new scene to the top of the stack.
-    pub fn push(&mut self, scene: Box<Scene<C, Ev>>) {
+    pub fn push(&mut self, scene: Box<dyn Scene<C, Ev>>) {
         self.scenes.push(scene)
     }
 
     /// Remove the top scene from the stack and returns it;
     /// panics if there is none.
-    pub fn pop(&mut self) -> Box<Scene<C, Ev>> {
+    pub fn pop(&mut self) -> Box<dyn Scene<C, Ev>> {
         self.scenes
             .pop()
             .expect("ERROR: Popped an empty scene stack.")
     }
 
     /// Returns the current scene; panics if there is none.
-    pub fn current(&self) -> &Scene<C, Ev> {
+    pub fn current(&self) -> &dyn Scene<C, Ev> {
         &**self
             .scenes
             .last()
@@ -99,14 +962,91 @@ impl<C, Ev> SceneStack<C, Ev> {
     }
 
     /// Executes the given SceneSwitch command; if it is a pop or replace
-    /// it returns `Some(old_scene)`, otherwise `None`
-    pub fn switch(&mut self, next_scene: SceneSwitch<C, Ev>) -> Option<Box<Scene<C, Ev>>> {
+    /// it returns `Some(old_scene)`, otherwise `None`.  When several
+    /// scenes are removed at once, `old_scene` is the one that was
+    /// current and the rest are dropped.
+    ///
+    /// Panics if asked to pop more scenes than there are, except
+    /// for `PopAll` and `ReplaceAll`, or to switch to a name that
+    /// isn't registered.
+    ///
+    /// Scenes that would be removed get asked first, through
+    /// `Scene::on_before_switch()`.  If any veto it, nothing happens;
+    /// if any defer it, it's held on to and tried again at the start
+    /// of the next `update()`.  Any other switch replaces one that's
+    /// being held on to.
+    pub fn switch(&mut self, next_scene: SceneSwitch<C, Ev>) -> Option<Box<dyn Scene<C, Ev>>> {
+        if let SceneSwitch::None = next_scene {
+            return None;
+        }
+        self.pending_switch = None;
+        match self.ask_before_switch(&next_scene) {
+            SwitchDecision::Allow => self.make_switch(next_scene),
+            SwitchDecision::Veto => None,
+            SwitchDecision::Defer => {
+                self.pending_switch = Some(next_scene);
+                None
+            }
+        }
+    }
+
+    /// Returns whether a scene has deferred a switch.
+    pub fn is_switch_pending(&self) -> bool {
+        self.pending_switch.is_some()
+    }
+
+    fn ask_before_switch(&mut self, switch: &SceneSwitch<C, Ev>) -> SwitchDecision {
+        let len = self.scenes.len();
+        let removed = match switch {
+            SceneSwitch::None
+            | SceneSwitch::Push(_)
+            | SceneSwitch::PushNamed(_)
+            | SceneSwitch::Preload(..) => 0,
+            SceneSwitch::Pop | SceneSwitch::PopWith(_) | SceneSwitch::Replace(_) => 1,
+            SceneSwitch::ReplaceNamed(name) => usize::from(self.is_registered(name)),
+            SceneSwitch::PopN(n) => *n,
+            SceneSwitch::PopAll | SceneSwitch::ReplaceAll(_) => len,
+            SceneSwitch::PopUntil(name) => self
+                .scenes
+                .iter()
+                .rposition(|s| s.name() == name)
+                .map_or(0, |i| len - 1 - i),
+        };
+        let mut decision = SwitchDecision::Allow;
+        for scene in self.scenes.iter_mut().rev().take(removed) {
+            match scene.on_before_switch(switch) {
+                SwitchDecision::Veto => return SwitchDecision::Veto,
+                SwitchDecision::Defer => decision = SwitchDecision::Defer,
+                SwitchDecision::Allow => (),
+            }
+        }
+        decision
+    }
+
+    fn make_switch(&mut self, next_scene: SceneSwitch<C, Ev>) -> Option<Box<dyn Scene<C, Ev>>> {
         match next_scene {
             SceneSwitch::None => None,
             SceneSwitch::Pop => {
                 let s = self.pop();
                 Some(s)
             }
+            SceneSwitch::PopWith(result) => {
+                let s = self.pop();
+                if let Some(parent) = self.scenes.last_mut() {
+                    parent.on_child_result(&mut self.world, result);
+                }
+                Some(s)
+            }
+            SceneSwitch::PopN(n) => self.pop_n(n),
+            SceneSwitch::PopAll => {
+                let n = self.scenes.len();
+                self.pop_n(n)
+            }
+            SceneSwitch::PopUntil(name) => self.pop_until(&name),
+            SceneSwitch::Preload(budget, step) => {
+                self.preload = Some(Preload { budget, step });
+                None
+            }
             SceneSwitch::Push(s) => {
                 self.push(s);
                 None
@@ -116,51 +1056,397 @@ impl<C, Ev> SceneStack<C, Ev> {
                 self.push(s);
                 Some(old_scene)
             }
+            SceneSwitch::ReplaceAll(s) => {
+                let n = self.scenes.len();
+                let old_scene = self.pop_n(n);
+                self.push(s);
+                old_scene
+            }
+            SceneSwitch::PushNamed(name) => {
+                let s = self.construct(&name)?;
+                self.push(s);
+                None
+            }
+            SceneSwitch::ReplaceNamed(name) => {
+                let s = self.construct(&name)?;
+                let old_scene = self.pop();
+                self.push(s);
+                Some(old_scene)
+            }
         }
     }
 
+    /// Pops scenes until the top one is called `name`, returning the
+    /// one that was on top, or `None` if nothing was popped.  Does
+    /// nothing if there's no scene called `name`.
+    pub fn pop_until(&mut self, name: &str) -> Option<Box<dyn Scene<C, Ev>>> {
+        let found = self.scenes.iter().rposition(|s| s.name() == name)?;
+        let n = self.scenes.len() - 1 - found;
+        self.pop_n(n)
+    }
+
+    /// Saves every `SaveableScene` on the stack, in order.
+    pub fn save(&self) -> SavedStack {
+        let scenes = self
+            .scenes
+            .iter()
+            .filter_map(|s| s.as_saveable())
+            .map(|s| SavedScene {
+                kind: s.kind().to_owned(),
+                state: s.save_state(),
+            })
+            .collect();
+        SavedStack { scenes }
+    }
+
+    /// The names of the scenes on the stack, from the bottom up.
+    pub fn iter_names(&self) -> impl Iterator<Item = &str> {
+        self.scenes.iter().map(|s| s.name())
+    }
+
+    /// Returns whether there's a scene with the given name anywhere
+    /// on the stack.
+    pub fn contains(&self, name: &str) -> bool {
+        self.iter_names().any(|n| n == name)
+    }
+
+    /// How many scenes are on the stack.
+    pub fn depth(&self) -> usize {
+        self.scenes.len()
+    }
+
+    /// Pops `n` scenes, returning the one that was on top.
+    fn pop_n(&mut self, n: usize) -> Option<Box<dyn Scene<C, Ev>>> {
+        assert!(
+            n <= self.scenes.len(),
+            "ERROR: Popped {} scenes from a scene stack of {}.",
+            n,
+            self.scenes.len()
+        );
+        if n == 0 {
+            return None;
+        }
+        let top = self.scenes.pop();
+        let len = self.scenes.len();
+        self.scenes.truncate(len + 1 - n);
+        top
+    }
+
     // These functions must be on the SceneStack because otherwise
     // if you try to get the current scene and the world to call
     // update() on the current scene it causes a double-borrow.  :/
     pub fn update(&mut self, ctx: &mut ggez::Context) {
-        let next_scene = {
-            let current_scene = &mut **self
-                .scenes
-                .last_mut()
-                .expect("Tried to update empty scene stack");
-            current_scene.update(&mut self.world, ctx)
-        };
+        assert!(!self.scenes.is_empty(), "Tried to update empty scene stack");
+        self.deliver_messages();
+        if let Some(pending) = self.pending_switch.take() {
+            self.switch(pending);
+        }
+        self.timings
+            .resize_with(self.scenes.len(), Default::default);
+        for timing in self.timings.iter_mut() {
+            timing.update = None;
+        }
+        let next_scene =
+            SceneStack::update_scenes(&mut self.scenes, &mut self.timings, &mut self.world, ctx);
         self.switch(next_scene);
+        self.update_preload(ctx);
+    }
+
+    /// Like `draw_scenes()`, we walk down the stack as far as
+    /// `update_previous()` says and update from the bottom up,
+    /// returning the top scene's switch.
+    fn update_scenes(
+        scenes: &mut [Box<dyn Scene<C, Ev>>],
+        timings: &mut [SceneTiming],
+        world: &mut C,
+        ctx: &mut ggez::Context,
+    ) -> SceneSwitch<C, Ev> {
+        match (scenes.split_last_mut(), timings.split_last_mut()) {
+            (Some((current, rest)), Some((timing, rest_timings))) => {
+                if current.update_previous() {
+                    SceneStack::update_scenes(rest, rest_timings, world, ctx);
+                }
+                let start = Instant::now();
+                let next_scene = current.update(world, ctx);
+                timing.update = Some(start.elapsed());
+                next_scene
+            }
+            _ => SceneSwitch::None,
+        }
     }
 
     /// We walk down the scene stack until we find a scene where we aren't
     /// supposed to draw the previous one, then draw them from the bottom up.
     ///
     /// This allows for layering GUI's and such.
-    fn draw_scenes(scenes: &mut [Box<Scene<C, Ev>>], world: &mut C, ctx: &mut ggez::Context) {
-        assert!(scenes.len() > 0);
-        if let Some((current, rest)) = scenes.split_last_mut() {
+    fn draw_scenes(
+        scenes: &mut [Box<dyn Scene<C, Ev>>],
+        timings: &mut [SceneTiming],
+        world: &mut C,
+        ctx: &mut ggez::Context,
+    ) -> GameResult {
+        assert!(!scenes.is_empty());
+        if let (Some((current, rest)), Some((timing, rest_timings))) =
+            (scenes.split_last_mut(), timings.split_last_mut())
+        {
             if current.draw_previous() {
-                SceneStack::draw_scenes(rest, world, ctx);
+                SceneStack::draw_scenes(rest, rest_timings, world, ctx)?;
             }
-            current
-                .draw(world, ctx)
-                .expect("I would hope drawing a scene never fails!");
+            let start = Instant::now();
+            current.draw(world, ctx)?;
+            timing.draw = Some(start.elapsed());
         }
+        Ok(())
     }
 
-    /// Draw the current scene.
-    pub fn draw(&mut self, ctx: &mut ggez::Context) {
-        SceneStack::draw_scenes(&mut self.scenes, &mut self.world, ctx)
+    /// Draw the current scene.  Stops at the first scene that fails
+    /// to draw and returns its error.
+    pub fn draw(&mut self, ctx: &mut ggez::Context) -> GameResult {
+        self.timings
+            .resize_with(self.scenes.len(), Default::default);
+        for timing in self.timings.iter_mut() {
+            timing.draw = None;
+        }
+        SceneStack::draw_scenes(&mut self.scenes, &mut self.timings, &mut self.world, ctx)?;
+        if self.debug_overlay {
+            self.draw_debug_overlay(ctx)?;
+        }
+        Ok(())
     }
 
-    /// Feeds the given input event to the current scene.
-    pub fn input(&mut self, event: Ev, started: bool) {
-        let current_scene = &mut **self
-            .scenes
-            .last_mut()
-            .expect("Tried to do input for empty scene stack");
-        current_scene.input(&mut self.world, event, started);
+    /// Turns the debug overlay on or off.  It's drawn over the
+    /// scenes in the top left corner of the window, and lists every
+    /// scene on the stack, top first, with which of `draw_previous()`,
+    /// `update_previous()` and `blocks_input()` it says, and how long
+    /// it took to update and draw last frame.  Scenes that weren't
+    /// updated or drawn get a `-` instead.
+    pub fn set_debug_overlay(&mut self, enabled: bool) {
+        self.debug_overlay = enabled;
+    }
+
+    /// Returns whether the debug overlay is on.
+    pub fn debug_overlay(&self) -> bool {
+        self.debug_overlay
+    }
+
+    fn debug_text(&self) -> String {
+        let ms = |d: Option<Duration>| match d {
+            Some(d) => format!("{:.2} ms", d.as_secs_f64() * 1000.0),
+            None => "-".to_owned(),
+        };
+        let mut lines = Vec::new();
+        for (i, scene) in self.scenes.iter().enumerate().rev() {
+            let timing = self.timings.get(i).copied().unwrap_or_default();
+            let mut flags = Vec::new();
+            if scene.draw_previous() {
+                flags.push("draw_previous");
+            }
+            if scene.update_previous() {
+                flags.push("update_previous");
+            }
+            if !scene.blocks_input() {
+                flags.push("passes input");
+            }
+            let flags = if flags.is_empty() {
+                String::new()
+            } else {
+                format!(" [{}]", flags.join(", "))
+            };
+            lines.push(format!(
+                "{}: {}{}  update {}  draw {}",
+                i,
+                scene.name(),
+                flags,
+                ms(timing.update),
+                ms(timing.draw)
+            ));
+        }
+        if self.pending_switch.is_some() {
+            lines.push("switch deferred".to_owned());
+        }
+        if self.preload.is_some() {
+            lines.push("preloading".to_owned());
+        }
+        lines.join("\n")
+    }
+
+    fn draw_debug_overlay(&self, ctx: &mut ggez::Context) -> GameResult {
+        // Scenes may have moved the screen around; draw in pixels.
+        let screen = graphics::screen_coordinates(ctx);
+        let (w, h) = graphics::drawable_size(ctx);
+        graphics::set_screen_coordinates(ctx, graphics::Rect::new(0.0, 0.0, w, h))?;
+
+        let text = graphics::Text::new(self.debug_text());
+        let (text_w, text_h) = text.dimensions(ctx);
+        let margin = 4.0;
+        let background = graphics::Mesh::new_rectangle(
+            ctx,
+            graphics::DrawMode::fill(),
+            graphics::Rect::new(
+                0.0,
+                0.0,
+                text_w as f32 + margin * 2.0,
+                text_h as f32 + margin * 2.0,
+            ),
+            graphics::Color::new(0.0, 0.0, 0.0, 0.7),
+        )?;
+        graphics::draw(ctx, &background, graphics::DrawParam::default())?;
+        graphics::draw(
+            ctx,
+            &text,
+            (ggez::mint::Point2 {
+                x: margin,
+                y: margin,
+            },),
+        )?;
+        graphics::set_screen_coordinates(ctx, screen)
+    }
+
+    /// Feeds the given input event to the current scene, and the ones
+    /// below it down to the first that `blocks_input()`.
+    pub fn input(&mut self, event: Ev, started: bool)
+    where
+        Ev: Clone,
+    {
+        assert!(
+            !self.scenes.is_empty(),
+            "Tried to do input for empty scene stack"
+        );
+        for scene in self.scenes.iter_mut().rev() {
+            scene.input(&mut self.world, event.clone(), started);
+            if scene.blocks_input() {
+                break;
+            }
+        }
+    }
+}
+
+/// Runs a `SceneStack` as a ggez `EventHandler`: updates and draws
+/// it, and hands its scenes every event as `SceneEvents`.  The game
+/// quits once the stack is empty.
+///
+/// It presents each frame after drawing the scenes, but leaves
+/// clearing the screen to them.  Unlike ggez's default handler, it
+/// doesn't quit when Escape is pressed.
+pub struct SceneRunner<C> {
+    pub stack: SceneStack<C, SceneEvents>,
+}
+
+impl<C> SceneRunner<C> {
+    pub fn new(stack: SceneStack<C, SceneEvents>) -> Self {
+        SceneRunner { stack }
+    }
+
+    fn input(&mut self, event: SceneEvents, started: bool) {
+        if !self.stack.scenes.is_empty() {
+            self.stack.input(event, started);
+        }
+    }
+}
+
+impl<C> EventHandler for SceneRunner<C> {
+    fn update(&mut self, ctx: &mut ggez::Context) -> ggez::GameResult {
+        if !self.stack.scenes.is_empty() {
+            self.stack.update(ctx);
+        }
+        if self.stack.scenes.is_empty() {
+            event::quit(ctx);
+        }
+        Ok(())
+    }
+
+    fn draw(&mut self, ctx: &mut ggez::Context) -> ggez::GameResult {
+        if !self.stack.scenes.is_empty() {
+            self.stack.draw(ctx)?;
+        }
+        graphics::present(ctx)
+    }
+
+    fn mouse_button_down_event(
+        &mut self,
+        _ctx: &mut ggez::Context,
+        button: MouseButton,
+        x: f32,
+        y: f32,
+    ) {
+        self.input(SceneEvents::MouseButton { button, x, y }, true);
+    }
+
+    fn mouse_button_up_event(
+        &mut self,
+        _ctx: &mut ggez::Context,
+        button: MouseButton,
+        x: f32,
+        y: f32,
+    ) {
+        self.input(SceneEvents::MouseButton { button, x, y }, false);
+    }
+
+    fn mouse_motion_event(&mut self, _ctx: &mut ggez::Context, x: f32, y: f32, dx: f32, dy: f32) {
+        self.input(SceneEvents::MouseMotion { x, y, dx, dy }, true);
+    }
+
+    fn mouse_wheel_event(&mut self, _ctx: &mut ggez::Context, x: f32, y: f32) {
+        self.input(SceneEvents::MouseWheel { x, y }, true);
+    }
+
+    fn key_down_event(
+        &mut self,
+        _ctx: &mut ggez::Context,
+        keycode: KeyCode,
+        keymods: KeyMods,
+        repeat: bool,
+    ) {
+        let event = SceneEvents::Key {
+            keycode,
+            keymods,
+            repeat,
+        };
+        self.input(event, true);
+    }
+
+    fn key_up_event(&mut self, _ctx: &mut ggez::Context, keycode: KeyCode, keymods: KeyMods) {
+        let event = SceneEvents::Key {
+            keycode,
+            keymods,
+            repeat: false,
+        };
+        self.input(event, false);
+    }
+
+    fn text_input_event(&mut self, _ctx: &mut ggez::Context, character: char) {
+        self.input(SceneEvents::Text(character), true);
+    }
+
+    fn gamepad_button_down_event(
+        &mut self,
+        _ctx: &mut ggez::Context,
+        button: Button,
+        id: GamepadId,
+    ) {
+        self.input(SceneEvents::GamepadButton { button, id }, true);
+    }
+
+    fn gamepad_button_up_event(&mut self, _ctx: &mut ggez::Context, button: Button, id: GamepadId) {
+        self.input(SceneEvents::GamepadButton { button, id }, false);
+    }
+
+    fn gamepad_axis_event(
+        &mut self,
+        _ctx: &mut ggez::Context,
+        axis: Axis,
+        value: f32,
+        id: GamepadId,
+    ) {
+        self.input(SceneEvents::GamepadAxis { axis, value, id }, true);
+    }
+
+    fn focus_event(&mut self, _ctx: &mut ggez::Context, gained: bool) {
+        self.input(SceneEvents::Focus, gained);
+    }
+
+    fn resize_event(&mut self, _ctx: &mut ggez::Context, width: f32, height: f32) {
+        self.input(SceneEvents::Resize { width, height }, true);
     }
 }
 
@@ -175,5 +1461,401 @@ mod tests {
     #[test]
     fn test1() {
         let x = Thing { scenes: vec![] };
+        assert!(x.scenes.is_empty());
+    }
+
+    struct Named(&'static str);
+
+    impl Scene<u32, u32> for Named {
+        fn update(&mut self, _world: &mut u32, _ctx: &mut ggez::Context) -> SceneSwitch<u32, u32> {
+            SceneSwitch::None
+        }
+        fn draw(&mut self, _world: &mut u32, _ctx: &mut ggez::Context) -> ggez::GameResult<()> {
+            Ok(())
+        }
+        fn input(&mut self, _world: &mut u32, _event: u32, _started: bool) {}
+        fn name(&self) -> &str {
+            self.0
+        }
+    }
+
+    struct Parent;
+
+    impl Scene<u32, u32> for Parent {
+        fn update(&mut self, _world: &mut u32, _ctx: &mut ggez::Context) -> SceneSwitch<u32, u32> {
+            SceneSwitch::None
+        }
+        fn draw(&mut self, _world: &mut u32, _ctx: &mut ggez::Context) -> ggez::GameResult<()> {
+            Ok(())
+        }
+        fn input(&mut self, _world: &mut u32, _event: u32, _started: bool) {}
+        fn name(&self) -> &str {
+            "parent"
+        }
+        fn on_child_result(&mut self, world: &mut u32, result: Box<dyn Any>) {
+            if let Ok(choice) = result.downcast::<u32>() {
+                *world = *choice;
+            }
+        }
+    }
+
+    // SceneStack::new() wants a Context, which tests can't make.
+    fn stack(names: &[&'static str]) -> SceneStack<u32, u32> {
+        let mut stack = SceneStack::with_world(0);
+        for name in names {
+            stack.push(Box::new(Named(name)));
+        }
+        stack
+    }
+
+    fn names(stack: &SceneStack<u32, u32>) -> Vec<&str> {
+        stack.iter_names().collect()
+    }
+
+    #[test]
+    fn test_loading_scene() {
+        let (go, wait) = mpsc::channel::<()>();
+        let mut loading: LoadingScene<u32, u32, u32> = LoadingScene::spawn(
+            Named("loading"),
+            move || {
+                wait.recv().unwrap();
+                42
+            },
+            |_, _, _| SceneSwitch::Pop,
+        );
+        assert_eq!(loading.name(), "loading");
+        assert!(!loading.is_loaded());
+        go.send(()).unwrap();
+        while !loading.is_loaded() {
+            thread::yield_now();
+        }
+        assert_eq!(loading.loaded, Some(42));
+    }
+
+    #[test]
+    fn test_child_result() {
+        let mut s = stack(&[]);
+        s.push(Box::new(Parent));
+        s.push(Box::new(Named("menu")));
+        let old = s.switch(SceneSwitch::pop_with(3u32));
+        assert_eq!(old.unwrap().name(), "menu");
+        assert_eq!(s.world, 3);
+        // Results of the wrong type are up to the parent to ignore.
+        s.push(Box::new(Named("menu")));
+        s.switch(SceneSwitch::pop_with("three"));
+        assert_eq!(s.world, 3);
+    }
+
+    struct Counter(&'static str);
+
+    impl Scene<u32, u32> for Counter {
+        fn update(&mut self, _world: &mut u32, _ctx: &mut ggez::Context) -> SceneSwitch<u32, u32> {
+            SceneSwitch::None
+        }
+        fn draw(&mut self, _world: &mut u32, _ctx: &mut ggez::Context) -> ggez::GameResult<()> {
+            Ok(())
+        }
+        fn input(&mut self, _world: &mut u32, _event: u32, _started: bool) {}
+        fn name(&self) -> &str {
+            self.0
+        }
+        fn receive(&mut self, world: &mut u32, message: &dyn Any) {
+            if let Some(n) = message.downcast_ref::<u32>() {
+                *world += n;
+            }
+        }
+    }
+
+    #[test]
+    fn test_mailbox() {
+        let mut s = stack(&[]);
+        s.push(Box::new(Counter("hud")));
+        s.push(Box::new(Counter("game")));
+        s.push(Box::new(Named("pause")));
+        let mailbox = s.mailbox();
+        mailbox.send("hud", 1u32);
+        mailbox.send("nobody", 10u32);
+        mailbox.broadcast(100u32);
+        mailbox.send("game", "not a number");
+        assert!(!s.mailbox().is_empty());
+        s.deliver_messages();
+        assert!(mailbox.is_empty());
+        assert_eq!(s.world, 201);
+    }
+
+    #[test]
+    fn test_named_lookup() {
+        let mut s = stack(&["menu", "overworld", "town", "shop", "dialog"]);
+        assert_eq!(s.depth(), 5);
+        assert!(s.contains("town"));
+        assert!(!s.contains("dungeon"));
+
+        assert!(s.pop_until("dungeon").is_none());
+        assert!(s.pop_until("dialog").is_none());
+        assert_eq!(s.depth(), 5);
+        let old = s.pop_until("town");
+        assert_eq!(old.unwrap().name(), "dialog");
+        assert_eq!(names(&s), vec!["menu", "overworld", "town"]);
+        let old = s.switch(SceneSwitch::PopUntil("overworld".to_owned()));
+        assert_eq!(old.unwrap().name(), "town");
+        assert_eq!(names(&s), vec!["menu", "overworld"]);
+    }
+
+    struct Level(u32);
+
+    impl Scene<u32, u32> for Level {
+        fn update(&mut self, _world: &mut u32, _ctx: &mut ggez::Context) -> SceneSwitch<u32, u32> {
+            SceneSwitch::None
+        }
+        fn draw(&mut self, _world: &mut u32, _ctx: &mut ggez::Context) -> ggez::GameResult<()> {
+            Ok(())
+        }
+        fn input(&mut self, _world: &mut u32, _event: u32, _started: bool) {}
+        fn name(&self) -> &str {
+            "level"
+        }
+        fn as_saveable(&self) -> Option<&dyn SaveableScene<u32, u32>> {
+            Some(self)
+        }
+    }
+
+    impl SaveableScene<u32, u32> for Level {
+        fn kind(&self) -> &str {
+            "level"
+        }
+        fn save_state(&self) -> String {
+            self.0.to_string()
+        }
+    }
+
+    #[test]
+    fn test_save() {
+        let mut s = stack(&["menu"]);
+        s.push(Box::new(Level(1)));
+        s.push(Box::new(Level(2)));
+        s.push(Box::new(Named("pause")));
+        let saved = s.save();
+        let level = |n: &str| SavedScene {
+            kind: "level".to_owned(),
+            state: n.to_owned(),
+        };
+        assert_eq!(saved.scenes, vec![level("1"), level("2")]);
+    }
+
+    #[test]
+    fn test_scripted_scene() {
+        let mut world = 0;
+        let mut script: ScriptedScene<u32, u32> = ScriptedScene::new("cutscene")
+            .then(|w| *w += 1)
+            .wait(1.0)
+            .then(|w| *w += 10)
+            .wait_for_input(|e| *e == 5)
+            .switch(|w| {
+                *w += 100;
+                SceneSwitch::push(Named("shop"))
+            })
+            .then(|w| *w += 1000);
+
+        assert!(matches!(script.advance(&mut world, 0.5), SceneSwitch::None));
+        assert_eq!(world, 1);
+        assert!(matches!(script.advance(&mut world, 0.6), SceneSwitch::None));
+        assert_eq!(world, 11);
+        // Only the right input, starting, gets it going again.
+        script.input(&mut world, 4, true);
+        script.input(&mut world, 5, false);
+        assert!(matches!(script.advance(&mut world, 0.1), SceneSwitch::None));
+        script.input(&mut world, 5, true);
+        assert!(matches!(
+            script.advance(&mut world, 0.1),
+            SceneSwitch::Push(_)
+        ));
+        assert_eq!(world, 111);
+        assert!(matches!(script.advance(&mut world, 0.1), SceneSwitch::Pop));
+        assert_eq!(world, 1111);
+        assert!(script.is_finished());
+    }
+
+    #[test]
+    fn test_scheduler() {
+        let mut timers = Scheduler::new();
+        let once = timers.after(1.5, "once");
+        let blink = timers.every(1.0, "blink");
+        assert!(timers.update(0.5).is_empty());
+        assert_eq!(timers.update(0.5), vec!["blink"]);
+        assert!(timers.is_scheduled(once));
+        assert_eq!(timers.update(1.5), vec!["once", "blink"]);
+        assert!(!timers.is_scheduled(once));
+        assert!(!timers.cancel(once));
+        assert_eq!(timers.update(2.0), vec!["blink", "blink"]);
+        assert!(timers.cancel(blink));
+        assert!(timers.update(10.0).is_empty());
+    }
+
+    // Adds input events to the world, times a power of ten so we can
+    // tell who got what.
+    struct Sink(u32, bool);
+
+    impl Scene<u32, u32> for Sink {
+        fn update(&mut self, _world: &mut u32, _ctx: &mut ggez::Context) -> SceneSwitch<u32, u32> {
+            SceneSwitch::None
+        }
+        fn draw(&mut self, _world: &mut u32, _ctx: &mut ggez::Context) -> ggez::GameResult<()> {
+            Ok(())
+        }
+        fn input(&mut self, world: &mut u32, event: u32, _started: bool) {
+            *world += event * self.0;
+        }
+        fn name(&self) -> &str {
+            "sink"
+        }
+        fn blocks_input(&self) -> bool {
+            self.1
+        }
+    }
+
+    #[test]
+    fn test_input_pass_through() {
+        let mut s = stack(&[]);
+        s.push(Box::new(Sink(1000, true)));
+        s.push(Box::new(Sink(100, true)));
+        s.push(Box::new(Sink(10, false)));
+        s.push(Box::new(Sink(1, false)));
+        s.input(2, true);
+        assert_eq!(s.world, 222);
+        s.push(Box::new(Sink(10000, true)));
+        s.input(1, true);
+        assert_eq!(s.world, 10222);
+    }
+
+    #[test]
+    fn test_preload_switch() {
+        let mut s = stack(&["level 1"]);
+        let old = s.switch(SceneSwitch::preload(Duration::from_millis(2), |_, _| {
+            LoadStep::Working
+        }));
+        assert!(old.is_none());
+        assert!(s.is_preloading());
+        assert_eq!(names(&s), vec!["level 1"]);
+        s.cancel_preload();
+        assert!(!s.is_preloading());
+    }
+
+    // Decides whatever the test tells it to.
+    struct Editor(Rc<std::cell::Cell<SwitchDecision>>);
+
+    impl Scene<u32, u32> for Editor {
+        fn update(&mut self, _world: &mut u32, _ctx: &mut ggez::Context) -> SceneSwitch<u32, u32> {
+            SceneSwitch::None
+        }
+        fn draw(&mut self, _world: &mut u32, _ctx: &mut ggez::Context) -> ggez::GameResult<()> {
+            Ok(())
+        }
+        fn input(&mut self, _world: &mut u32, _event: u32, _started: bool) {}
+        fn name(&self) -> &str {
+            "editor"
+        }
+        fn on_before_switch(&mut self, _switch: &SceneSwitch<u32, u32>) -> SwitchDecision {
+            self.0.get()
+        }
+    }
+
+    #[test]
+    fn test_switch_veto() {
+        let decision = Rc::new(std::cell::Cell::new(SwitchDecision::Veto));
+        let decide = |d| decision.set(d);
+        let mut s = stack(&["menu"]);
+        s.push(Box::new(Editor(decision.clone())));
+        s.push(Box::new(Named("toolbox")));
+
+        // Pushing on top is none of the editor's business.
+        s.switch(SceneSwitch::push(Named("help")));
+        assert!(s.switch(SceneSwitch::PopN(3)).is_none());
+        assert_eq!(names(&s), vec!["menu", "editor", "toolbox", "help"]);
+        assert!(s.switch(SceneSwitch::PopN(2)).is_some());
+
+        decide(SwitchDecision::Defer);
+        assert!(s.switch(SceneSwitch::replace_all(Named("title"))).is_none());
+        assert!(s.is_switch_pending());
+        // Asked again, still deferred.
+        let pending = s.pending_switch.take().unwrap();
+        s.switch(pending);
+        assert!(s.is_switch_pending());
+        decide(SwitchDecision::Allow);
+        let pending = s.pending_switch.take().unwrap();
+        assert_eq!(s.switch(pending).unwrap().name(), "editor");
+        assert_eq!(names(&s), vec!["title"]);
+        assert!(!s.is_switch_pending());
+    }
+
+    #[test]
+    fn test_debug_text() {
+        let mut s = stack(&["menu"]);
+        s.push(Box::new(Sink(0, false)));
+        s.timings = vec![
+            SceneTiming {
+                update: None,
+                draw: Some(Duration::from_micros(1500)),
+            },
+            SceneTiming {
+                update: Some(Duration::from_micros(250)),
+                draw: Some(Duration::from_micros(100)),
+            },
+        ];
+        assert_eq!(
+            s.debug_text(),
+            "1: sink [passes input]  update 0.25 ms  draw 0.10 ms\n\
+             0: menu  update -  draw 1.50 ms"
+        );
+        // Scenes pushed since the last frame haven't got timings yet.
+        s.push(Box::new(Named("pause")));
+        assert!(s.debug_text().starts_with("2: pause  update -  draw -\n"));
+    }
+
+    #[test]
+    fn test_named_scenes() {
+        let mut s = stack(&["level"]);
+        s.register_named("boss_fight", |world| {
+            *world += 1;
+            Box::new(Named("boss_fight"))
+        });
+        s.register_named("credits", |_| Box::new(Named("credits")));
+        assert!(s.is_registered("credits"));
+        assert!(!s.is_registered("level"));
+
+        s.switch(SceneSwitch::push_named("boss_fight"));
+        assert_eq!(names(&s), vec!["level", "boss_fight"]);
+        assert_eq!(s.world, 1);
+        let old = s.switch(SceneSwitch::replace_named("credits"));
+        assert_eq!(old.unwrap().name(), "boss_fight");
+        assert_eq!(names(&s), vec!["level", "credits"]);
+    }
+
+    #[test]
+    fn test_named_scene_missing() {
+        let mut s = stack(&["level"]);
+        assert!(s.switch(SceneSwitch::replace_named("nowhere")).is_none());
+        assert!(s.switch(SceneSwitch::push_named("nowhere")).is_none());
+        assert_eq!(names(&s), vec!["level"]);
+    }
+
+    #[test]
+    fn test_multi_switches() {
+        let mut s = stack(&["menu", "world", "level", "pause"]);
+        let old = s.switch(SceneSwitch::PopN(2));
+        assert_eq!(old.unwrap().name(), "pause");
+        assert_eq!(names(&s), vec!["menu", "world"]);
+        assert!(s.switch(SceneSwitch::PopN(0)).is_none());
+        assert_eq!(names(&s), vec!["menu", "world"]);
+
+        let old = s.switch(SceneSwitch::replace_all(Named("credits")));
+        assert_eq!(old.unwrap().name(), "world");
+        assert_eq!(names(&s), vec!["credits"]);
+
+        let old = s.switch(SceneSwitch::PopAll);
+        assert_eq!(old.unwrap().name(), "credits");
+        assert!(s.scenes.is_empty());
+        assert!(s.switch(SceneSwitch::PopAll).is_none());
+        s.switch(SceneSwitch::replace_all(Named("menu")));
+        assert_eq!(names(&s), vec!["menu"]);
     }
 }
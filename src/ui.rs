@@ -0,0 +1,693 @@
+//! A minimal immediate-mode-ish UI for menus and HUDs.
+//!
+//! You call widget functions like `button()` and `slider()` every
+//! frame between `Ui::begin()` and `Ui::draw()`, and they tell you
+//! right away whether they were clicked or changed.  The `Ui` only
+//! remembers enough between frames to track focus and mouse drags,
+//! the actual values all live in your own code.
+//!
+//! Text is drawn with ggez's TrueType fonts, or with a `BitmapFont`
+//! if the `Style` has one.
+//!
+//! Widgets are positioned in screen space with plain `Rect`'s; there's
+//! no layout engine.  They can be driven by the mouse, or by logical
+//! buttons from an `InputState` via `UiNavigation`, so menus work
+//! with whatever the player has bound to "up", "down" and "confirm".
+//!
+//! Focus is layered: `push_layer()` starts a new layer on top of the
+//! previous ones, and only the topmost layer drawn last frame gets
+//! input.  So a confirmation dialog drawn in its own layer makes the
+//! menu behind it inert without any extra bookkeeping.
+//!
+//! This is deliberately not a real GUI library.  If you need text
+//! entry, scrolling, or automatic layout, look at a real one.
+
+use std::hash::Hash;
+
+use ggez::graphics::{self, Align, Color, DrawMode, DrawParam, Font, Rect, Text};
+use ggez::mint::{Point2, Vector2};
+use ggez::{Context, GameResult};
+
+use crate::bitmap_font::BitmapFont;
+use crate::input::InputState;
+
+/// Which logical buttons navigate the UI.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UiNavigation<Buttons> {
+    pub up: Buttons,
+    pub down: Buttons,
+    pub left: Buttons,
+    pub right: Buttons,
+    pub activate: Buttons,
+}
+
+/// A snapshot of the input the UI cares about for one frame.
+///
+/// The navigation fields are edge triggered: they should be true
+/// only on the frame the button was pressed.
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct UiInput {
+    /// Mouse position in screen coordinates, if there is one.
+    pub mouse_position: Option<Point2<f32>>,
+    /// Whether the (primary) mouse button is held down.
+    pub mouse_down: bool,
+    pub up: bool,
+    pub down: bool,
+    pub left: bool,
+    pub right: bool,
+    pub activate: bool,
+}
+
+impl UiInput {
+    /// Creates a `UiInput` from the logical buttons of an `InputState`.
+    pub fn from_input_state<Axes, Buttons>(
+        input: &InputState<Axes, Buttons>,
+        nav: &UiNavigation<Buttons>,
+    ) -> Self
+    where
+        Axes: Hash + Eq + Clone,
+        Buttons: Hash + Eq + Clone,
+    {
+        UiInput {
+            mouse_position: None,
+            mouse_down: false,
+            up: input.get_button_pressed(nav.up.clone()),
+            down: input.get_button_pressed(nav.down.clone()),
+            left: input.get_button_pressed(nav.left.clone()),
+            right: input.get_button_pressed(nav.right.clone()),
+            activate: input.get_button_pressed(nav.activate.clone()),
+        }
+    }
+
+    /// Adds mouse state to the input.
+    pub fn with_mouse<P>(mut self, position: P, down: bool) -> Self
+    where
+        P: Into<Point2<f32>>,
+    {
+        self.mouse_position = Some(position.into());
+        self.mouse_down = down;
+        self
+    }
+}
+
+/// An image drawn as a resizable panel: the corners are drawn
+/// unscaled, the edges are stretched in one direction, and the
+/// middle is stretched in both.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NineSlice {
+    image: graphics::Image,
+    border: f32,
+}
+
+impl NineSlice {
+    /// Creates a new `NineSlice` from the image, with corners that
+    /// are `border` pixels square.
+    pub fn new(image: graphics::Image, border: f32) -> Self {
+        NineSlice { image, border }
+    }
+
+    /// Draws the panel stretched to fill `rect`, tinted with `color`.
+    pub fn draw(&self, ctx: &mut Context, rect: Rect, color: Color) -> GameResult<()> {
+        let iw = f32::from(self.image.width());
+        let ih = f32::from(self.image.height());
+        // Don't let the corners overlap if the rect is tiny.
+        let b = self.border.min(rect.w / 2.0).min(rect.h / 2.0);
+        let (bx, by) = (self.border / iw, self.border / ih);
+        let src_x = [(0.0, bx), (bx, 1.0 - 2.0 * bx), (1.0 - bx, bx)];
+        let src_y = [(0.0, by), (by, 1.0 - 2.0 * by), (1.0 - by, by)];
+        let dest_x = [
+            (rect.x, b),
+            (rect.x + b, rect.w - 2.0 * b),
+            (rect.right() - b, b),
+        ];
+        let dest_y = [
+            (rect.y, b),
+            (rect.y + b, rect.h - 2.0 * b),
+            (rect.bottom() - b, b),
+        ];
+        for (&(sy, sh), &(dy, dh)) in src_y.iter().zip(dest_y.iter()) {
+            for (&(sx, sw), &(dx, dw)) in src_x.iter().zip(dest_x.iter()) {
+                if sw <= 0.0 || sh <= 0.0 || dw <= 0.0 || dh <= 0.0 {
+                    continue;
+                }
+                let param = DrawParam::new()
+                    .src(Rect::new(sx, sy, sw, sh))
+                    .dest(Point2 { x: dx, y: dy })
+                    .scale(Vector2 {
+                        x: dw / (sw * iw),
+                        y: dh / (sh * ih),
+                    })
+                    .color(color);
+                graphics::draw(ctx, &self.image, param)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Colors and fonts used to draw widgets.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Style {
+    pub font: Font,
+    pub font_size: f32,
+    pub text_color: Color,
+    /// Background of buttons and other widgets.
+    pub background: Color,
+    /// Background of the focused widget.
+    pub focused: Color,
+    /// Background of a widget being clicked.
+    pub pressed: Color,
+    /// Color of slider handles, checkbox marks and selected list items.
+    pub accent: Color,
+    /// If set, widget backgrounds are drawn with this tinted by
+    /// the colors above instead of as plain rectangles.
+    pub panel: Option<NineSlice>,
+    /// If set, text is drawn with this instead of `font`, scaled so
+    /// glyphs are `font_size` tall.
+    pub bitmap_font: Option<BitmapFont>,
+}
+
+impl Default for Style {
+    fn default() -> Self {
+        Style {
+            font: Font::default(),
+            font_size: 16.0,
+            text_color: graphics::WHITE,
+            background: Color::new(0.2, 0.2, 0.25, 0.9),
+            focused: Color::new(0.3, 0.3, 0.45, 0.9),
+            pressed: Color::new(0.15, 0.15, 0.3, 0.9),
+            accent: Color::new(0.9, 0.7, 0.2, 1.0),
+            panel: None,
+            bitmap_font: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum DrawCommand {
+    Panel(Rect, Color),
+    Rect(Rect, Color),
+    Text(String, Rect, Align),
+}
+
+/// Identifies a focusable widget: its layer, and its index among
+/// the focusable widgets of that layer this frame.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+struct WidgetId {
+    layer: usize,
+    index: usize,
+}
+
+/// What happened to a single widget this frame.
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+struct Interaction {
+    focused: bool,
+    pressed: bool,
+    clicked: bool,
+}
+
+/// The UI state.  See the module docs.
+#[derive(Debug)]
+pub struct Ui {
+    style: Style,
+    input: UiInput,
+    last_mouse_position: Option<Point2<f32>>,
+    mouse_was_down: bool,
+
+    // Draw commands, one list per layer.
+    layers: Vec<Vec<DrawCommand>>,
+    layer: usize,
+    // Number of focusable widgets in each layer this frame and last.
+    widget_counts: Vec<usize>,
+    last_widget_counts: Vec<usize>,
+    // The only layer that gets input, ie the top one last frame.
+    input_layer: usize,
+
+    focus: Option<WidgetId>,
+    // Widget the mouse button was pressed on, if any.
+    active: Option<WidgetId>,
+    mouse_over_widget: bool,
+}
+
+impl Ui {
+    pub fn new(style: Style) -> Self {
+        Ui {
+            style,
+            input: UiInput::default(),
+            last_mouse_position: None,
+            mouse_was_down: false,
+            layers: vec![Vec::new()],
+            layer: 0,
+            widget_counts: vec![0],
+            last_widget_counts: vec![0],
+            input_layer: 0,
+            focus: None,
+            active: None,
+            mouse_over_widget: false,
+        }
+    }
+
+    pub fn style(&self) -> &Style {
+        &self.style
+    }
+
+    pub fn style_mut(&mut self) -> &mut Style {
+        &mut self.style
+    }
+
+    /// Starts a new frame of UI with the given input.
+    pub fn begin(&mut self, input: UiInput) {
+        self.last_mouse_position = self.input.mouse_position;
+        self.mouse_was_down = self.input.mouse_down;
+        self.input = input;
+        self.last_widget_counts = std::mem::replace(&mut self.widget_counts, vec![0]);
+        for commands in &mut self.layers {
+            commands.clear();
+        }
+        self.layer = 0;
+        self.mouse_over_widget = false;
+
+        let top = self.last_widget_counts.len() - 1;
+        if top != self.input_layer {
+            // A layer opened or closed; focus and drags don't carry over.
+            self.input_layer = top;
+            self.focus = None;
+            self.active = None;
+        }
+
+        let count = self.last_widget_counts[top];
+        if count == 0 {
+            self.focus = None;
+        } else if input.up || input.down {
+            let index = match self.focus {
+                None => 0,
+                Some(f) if input.down => (f.index + 1) % count,
+                Some(f) => (f.index + count - 1) % count,
+            };
+            self.focus = Some(WidgetId { layer: top, index });
+        } else if let Some(f) = self.focus {
+            if f.index >= count {
+                self.focus = Some(WidgetId {
+                    layer: top,
+                    index: count - 1,
+                });
+            }
+        }
+    }
+
+    /// Starts a new layer on top of the current one.  Only widgets
+    /// in the topmost layer respond to input.
+    pub fn push_layer(&mut self) {
+        self.layer += 1;
+        if self.layers.len() <= self.layer {
+            self.layers.push(Vec::new());
+        }
+        if self.widget_counts.len() <= self.layer {
+            self.widget_counts.push(0);
+        }
+    }
+
+    /// Returns to the layer below the current one.
+    pub fn pop_layer(&mut self) {
+        assert!(self.layer > 0, "Tried to pop the bottom UI layer");
+        self.layer -= 1;
+    }
+
+    /// Whether the mouse is over any widget drawn so far this frame
+    /// or dragging one, so you can avoid also treating a click as a
+    /// click in the game world.
+    pub fn wants_mouse(&self) -> bool {
+        self.mouse_over_widget || self.active.is_some()
+    }
+
+    fn push(&mut self, command: DrawCommand) {
+        self.layers[self.layer].push(command);
+    }
+
+    fn mouse_in(&self, rect: Rect) -> bool {
+        self.input.mouse_position.is_some_and(|p| rect.contains(p))
+    }
+
+    /// Registers a focusable widget occupying `rect` and works out
+    /// what the input did to it.
+    fn interact(&mut self, rect: Rect) -> (WidgetId, Interaction) {
+        let id = WidgetId {
+            layer: self.layer,
+            index: self.widget_counts[self.layer],
+        };
+        self.widget_counts[self.layer] += 1;
+        let mut result = Interaction::default();
+        let hovered = self.mouse_in(rect);
+        self.mouse_over_widget |= hovered;
+        if id.layer != self.input_layer {
+            return (id, result);
+        }
+
+        let mouse_moved = self.input.mouse_position != self.last_mouse_position;
+        if hovered && mouse_moved {
+            self.focus = Some(id);
+        }
+        let mouse_pressed = self.input.mouse_down && !self.mouse_was_down;
+        let mouse_released = !self.input.mouse_down && self.mouse_was_down;
+        if hovered && mouse_pressed {
+            self.active = Some(id);
+            self.focus = Some(id);
+        }
+        if self.active == Some(id) {
+            if self.input.mouse_down {
+                result.pressed = true;
+            } else if mouse_released {
+                result.clicked = hovered;
+                self.active = None;
+            } else {
+                self.active = None;
+            }
+        }
+
+        result.focused = self.focus == Some(id);
+        if result.focused && self.input.activate {
+            result.clicked = true;
+        }
+        (id, result)
+    }
+
+    fn background_color(&self, interaction: Interaction) -> Color {
+        if interaction.pressed {
+            self.style.pressed
+        } else if interaction.focused {
+            self.style.focused
+        } else {
+            self.style.background
+        }
+    }
+
+    /// Draws some text with its top-left corner at `position`.
+    pub fn label<P>(&mut self, text: &str, position: P)
+    where
+        P: Into<Point2<f32>>,
+    {
+        let p = position.into();
+        let rect = Rect::new(p.x, p.y, f32::INFINITY, f32::INFINITY);
+        self.push(DrawCommand::Text(text.to_owned(), rect, Align::Left));
+    }
+
+    /// A button; returns true on the frame it was clicked.
+    pub fn button(&mut self, text: &str, rect: Rect) -> bool {
+        let (_, interaction) = self.interact(rect);
+        let color = self.background_color(interaction);
+        self.push(DrawCommand::Panel(rect, color));
+        self.push(DrawCommand::Text(text.to_owned(), rect, Align::Center));
+        interaction.clicked
+    }
+
+    /// A checkbox with a label; returns true on the frame it was
+    /// toggled.
+    pub fn checkbox(&mut self, text: &str, rect: Rect, value: &mut bool) -> bool {
+        let (_, interaction) = self.interact(rect);
+        if interaction.clicked {
+            *value = !*value;
+        }
+        let color = self.background_color(interaction);
+        self.push(DrawCommand::Panel(rect, color));
+        let size = rect.h * 0.6;
+        let margin = (rect.h - size) / 2.0;
+        let box_rect = Rect::new(rect.x + margin, rect.y + margin, size, size);
+        self.push(DrawCommand::Rect(box_rect, self.style.background));
+        if *value {
+            let inset = size * 0.2;
+            let mark = Rect::new(
+                box_rect.x + inset,
+                box_rect.y + inset,
+                size - inset * 2.0,
+                size - inset * 2.0,
+            );
+            self.push(DrawCommand::Rect(mark, self.style.accent));
+        }
+        let text_rect = Rect::new(rect.x + rect.h, rect.y, (rect.w - rect.h).max(0.0), rect.h);
+        self.push(DrawCommand::Text(text.to_owned(), text_rect, Align::Left));
+        interaction.clicked
+    }
+
+    /// A horizontal slider for a value between `min` and `max`.
+    /// Returns true if the value changed this frame.
+    ///
+    /// Can be dragged with the mouse, or nudged by a tenth of its
+    /// range with the left and right buttons while focused.
+    pub fn slider(&mut self, rect: Rect, value: &mut f32, min: f32, max: f32) -> bool {
+        let (_, interaction) = self.interact(rect);
+        let old = *value;
+        let range = max - min;
+        if interaction.pressed && range > 0.0 {
+            if let Some(p) = self.input.mouse_position {
+                let t = ((p.x - rect.x) / rect.w).clamp(0.0, 1.0);
+                *value = min + t * range;
+            }
+        }
+        if interaction.focused {
+            if self.input.left {
+                *value -= range / 10.0;
+            }
+            if self.input.right {
+                *value += range / 10.0;
+            }
+        }
+        *value = value.max(min).min(max);
+
+        let color = self.background_color(interaction);
+        self.push(DrawCommand::Panel(rect, color));
+        let t = if range > 0.0 {
+            (*value - min) / range
+        } else {
+            0.0
+        };
+        let handle_w = (rect.h / 2.0).min(rect.w);
+        let handle = Rect::new(rect.x + t * (rect.w - handle_w), rect.y, handle_w, rect.h);
+        self.push(DrawCommand::Rect(handle, self.style.accent));
+        *value != old
+    }
+
+    /// A vertical list of items, one of which is selected.  `rect` is
+    /// the area of the whole list; items are split evenly inside it.
+    /// Returns true if the selection changed this frame.
+    ///
+    /// Items can be clicked with the mouse, or the selection moved
+    /// with the left and right buttons while the list is focused
+    /// (up and down move focus between widgets).
+    pub fn list(&mut self, rect: Rect, items: &[&str], selected: &mut usize) -> bool {
+        let (_, interaction) = self.interact(rect);
+        let old = *selected;
+        let item_h = rect.h / items.len().max(1) as f32;
+        if interaction.clicked {
+            if let Some(p) = self.input.mouse_position {
+                if rect.contains(p) {
+                    *selected = ((p.y - rect.y) / item_h) as usize;
+                }
+            }
+        }
+        if interaction.focused && !items.is_empty() {
+            if self.input.left {
+                *selected = (*selected + items.len() - 1) % items.len();
+            }
+            if self.input.right {
+                *selected = (*selected + 1) % items.len();
+            }
+        }
+        *selected = (*selected).min(items.len().saturating_sub(1));
+
+        let color = self.background_color(interaction);
+        self.push(DrawCommand::Panel(rect, color));
+        for (i, item) in items.iter().enumerate() {
+            let item_rect = Rect::new(rect.x, rect.y + i as f32 * item_h, rect.w, item_h);
+            if i == *selected {
+                let mut highlight = self.style.accent;
+                highlight.a *= 0.5;
+                self.push(DrawCommand::Rect(item_rect, highlight));
+            }
+            self.push(DrawCommand::Text(
+                (*item).to_owned(),
+                item_rect,
+                Align::Left,
+            ));
+        }
+        *selected != old
+    }
+
+    /// Draws everything queued up this frame, from the bottom layer up.
+    pub fn draw(&mut self, ctx: &mut Context) -> GameResult<()> {
+        let layer_count = self.widget_counts.len();
+        for commands in self.layers.iter().take(layer_count) {
+            for command in commands {
+                match command {
+                    DrawCommand::Panel(rect, color) => {
+                        if let Some(ref panel) = self.style.panel {
+                            panel.draw(ctx, *rect, *color)?;
+                        } else {
+                            draw_rect(ctx, *rect, *color)?;
+                        }
+                    }
+                    DrawCommand::Rect(rect, color) => draw_rect(ctx, *rect, *color)?,
+                    DrawCommand::Text(text, rect, align) => {
+                        if let Some(ref font) = self.style.bitmap_font {
+                            draw_bitmap_text(ctx, font, &self.style, text, *rect, *align)?;
+                            continue;
+                        }
+                        let mut text =
+                            Text::new((text.as_str(), self.style.font, self.style.font_size));
+                        text.set_bounds(
+                            Point2 {
+                                x: rect.w,
+                                y: rect.h,
+                            },
+                            *align,
+                        );
+                        // Center vertically if the rect has a height.
+                        let y = if rect.h.is_finite() {
+                            rect.y + (rect.h - text.height(ctx) as f32) / 2.0
+                        } else {
+                            rect.y
+                        };
+                        let param = DrawParam::new()
+                            .dest(Point2 { x: rect.x, y })
+                            .color(self.style.text_color);
+                        graphics::draw(ctx, &text, param)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for Ui {
+    fn default() -> Self {
+        Self::new(Style::default())
+    }
+}
+
+fn draw_bitmap_text(
+    ctx: &mut Context,
+    font: &BitmapFont,
+    style: &Style,
+    text: &str,
+    rect: Rect,
+    align: Align,
+) -> GameResult<()> {
+    let (_, glyph_h) = font.glyph_size();
+    let scale = style.font_size / glyph_h;
+    let width = font.text_width(text) * scale;
+    let x = match align {
+        _ if !rect.w.is_finite() => rect.x,
+        Align::Left => rect.x,
+        Align::Center => rect.x + (rect.w - width) / 2.0,
+        Align::Right => rect.right() - width,
+    };
+    let y = if rect.h.is_finite() {
+        rect.y + (rect.h - style.font_size) / 2.0
+    } else {
+        rect.y
+    };
+    let param = DrawParam::new()
+        .dest(Point2 { x, y })
+        .scale(Vector2 { x: scale, y: scale })
+        .color(style.text_color);
+    font.draw(ctx, text, param)
+}
+
+fn draw_rect(ctx: &mut Context, rect: Rect, color: Color) -> GameResult<()> {
+    let mesh = graphics::Mesh::new_rectangle(ctx, DrawMode::fill(), rect, color)?;
+    graphics::draw(ctx, &mesh, DrawParam::default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mouse(x: f32, y: f32, down: bool) -> UiInput {
+        UiInput::default().with_mouse(Point2 { x, y }, down)
+    }
+
+    #[test]
+    fn test_mouse_click() {
+        let mut ui = Ui::default();
+        let rect = Rect::new(10.0, 10.0, 100.0, 20.0);
+
+        ui.begin(mouse(50.0, 20.0, false));
+        assert!(!ui.button("OK", rect));
+        ui.begin(mouse(50.0, 20.0, true));
+        assert!(!ui.button("OK", rect));
+        ui.begin(mouse(50.0, 20.0, false));
+        assert!(ui.button("OK", rect));
+
+        // Press inside, release outside: no click.
+        ui.begin(mouse(50.0, 20.0, true));
+        assert!(!ui.button("OK", rect));
+        ui.begin(mouse(500.0, 20.0, false));
+        assert!(!ui.button("OK", rect));
+    }
+
+    #[test]
+    fn test_keyboard_navigation() {
+        let mut ui = Ui::default();
+        let r1 = Rect::new(0.0, 0.0, 10.0, 10.0);
+        let r2 = Rect::new(0.0, 20.0, 10.0, 10.0);
+        let mut checked = false;
+
+        ui.begin(UiInput::default());
+        ui.button("one", r1);
+        ui.checkbox("two", r2, &mut checked);
+
+        let down = UiInput {
+            down: true,
+            ..UiInput::default()
+        };
+        let activate = UiInput {
+            activate: true,
+            ..UiInput::default()
+        };
+        // First press focuses the first widget, second moves on.
+        ui.begin(down);
+        ui.button("one", r1);
+        ui.checkbox("two", r2, &mut checked);
+        ui.begin(down);
+        ui.button("one", r1);
+        ui.checkbox("two", r2, &mut checked);
+        ui.begin(activate);
+        assert!(!ui.button("one", r1));
+        assert!(ui.checkbox("two", r2, &mut checked));
+        assert!(checked);
+    }
+
+    #[test]
+    fn test_layers_block_input() {
+        let mut ui = Ui::default();
+        let rect = Rect::new(0.0, 0.0, 10.0, 10.0);
+        let dialog = Rect::new(100.0, 100.0, 10.0, 10.0);
+        for _ in 0..2 {
+            ui.begin(mouse(5.0, 5.0, false));
+            ui.button("menu", rect);
+            ui.push_layer();
+            ui.button("dialog", dialog);
+            ui.pop_layer();
+        }
+        ui.begin(mouse(5.0, 5.0, true));
+        ui.button("menu", rect);
+        ui.begin(mouse(5.0, 5.0, false));
+        assert!(!ui.button("menu", rect));
+    }
+
+    #[test]
+    fn test_slider() {
+        let mut ui = Ui::default();
+        let rect = Rect::new(0.0, 0.0, 100.0, 10.0);
+        let mut value = 0.0;
+        ui.begin(mouse(75.0, 5.0, false));
+        assert!(!ui.slider(rect, &mut value, 0.0, 1.0));
+        ui.begin(mouse(75.0, 5.0, true));
+        assert!(ui.slider(rect, &mut value, 0.0, 1.0));
+        assert!((value - 0.75).abs() < 0.001);
+        // Dragging past the end clamps.
+        ui.begin(mouse(500.0, 5.0, true));
+        ui.slider(rect, &mut value, 0.0, 1.0);
+        assert_eq!(value, 1.0);
+    }
+}